@@ -1,14 +1,91 @@
+use crate::analysis::function::{
+    FunctionMetrics, count_decision_points, parse_fn_line, parse_fn_name,
+};
+use crate::analysis::markdown::extract_noir_fences;
+use crate::analysis::options::{AnalysisOptions, DEFAULT_MAX_LINE_LENGTH};
 use anyhow::Result;
-use serde::Serialize;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Leading-whitespace style used on indented code lines.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentStyle {
+    /// No indented code lines.
+    #[default]
+    None,
+    /// Indented code lines use only tabs.
+    Tabs,
+    /// Indented code lines use only spaces.
+    Spaces,
+    /// Both tabs and spaces are used for indentation.
+    Mixed,
+}
+
+/// Line terminators used in a file, detected from its raw contents.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// No line terminators (empty or single-line file without a trailing newline).
+    #[default]
+    None,
+    /// Only `\n`.
+    Lf,
+    /// Only `\r\n`.
+    Crlf,
+    /// Both `\n` and `\r\n`.
+    Mixed,
+}
+
+/// How a single source line was classified by [`analyze_source`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// Empty or whitespace-only line.
+    Blank,
+    /// Line comment or part of a block comment.
+    Comment,
+    /// Code line outside test functions.
+    Code,
+    /// Code line attributed to a `#[test...]` function (including the attribute line).
+    Test,
+}
+
+/// Per-line classification decision, collected by [`analyze_source_with_trace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineTrace {
+    /// 1-based line number.
+    pub line: usize,
+
+    /// Classification assigned to the line.
+    pub kind: LineKind,
+
+    /// Brace depth after the line, as used for test attribution.
+    pub brace_depth: i32,
+}
+
+/// An `assert_eq(...)` call whose argument count looks wrong (`--lint-asserts`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SuspiciousAssert {
+    /// 1-based line number of the call.
+    pub line: usize,
+
+    /// The trimmed source line.
+    pub snippet: String,
+}
+
 /// Metrics computed for a single `.nr` file.
 ///
 /// Values are derived from a line-based scan and simple heuristics (not an AST parse).
-/// /// See the module documentation and [`FileMetrics`] field docs for classification rules and limitations.
-#[derive(Debug, Clone, Serialize)]
+/// See the module documentation and [`FileMetrics`] field docs for classification rules and limitations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct FileMetrics {
     /// Path to the file, relative to the project root
     pub path: PathBuf,
@@ -16,6 +93,16 @@ pub struct FileMetrics {
     /// Heuristic: is this file considered a "test" file?
     pub is_test_file: bool,
 
+    /// Does this non-test file contain `#[test...]` functions (co-located tests)?
+    ///
+    /// True when `test_functions > 0 && !is_test_file`.
+    pub has_inline_tests: bool,
+
+    /// Is the file zero bytes or all blank lines (`code_lines == 0 && comment_lines == 0`)?
+    ///
+    /// Empty files are still listed in the report; they are usually left over by mistake.
+    pub is_empty: bool,
+
     /// Total number of lines in the file (including blank and comment lines).
     pub total_lines: usize,
 
@@ -25,11 +112,69 @@ pub struct FileMetrics {
     /// Lines that are comments:
     /// - starting with `//` after trimming, or
     /// - inside `/* ... */` block comments.
+    ///
+    /// Always `line_comment_lines + block_comment_lines`.
     pub comment_lines: usize,
 
+    /// Comment lines starting with `//` (including doc comments).
+    pub line_comment_lines: usize,
+
+    /// Lines of `/* ... */` block comments, from the opening to the closing line (blank lines inside included).
+    pub block_comment_lines: usize,
+
+    /// `//` comment lines whose text looks like commented-out code rather than prose.
+    ///
+    /// Heuristic (see [`looks_like_code`]); expect some false positives and negatives.
+    pub commented_code_lines: usize,
+
+    /// `//` comment lines whose text starts with `#[`: commented-out attributes such as `// #[test]`.
+    ///
+    /// These are not counted as tests (or as any other attribute); the count is surfaced for audit.
+    pub disabled_attributes: usize,
+
+    /// Doc comment lines: `inner_doc_lines + outer_doc_lines`.
+    pub doc_comment_lines: usize,
+
+    /// Inner doc comment lines (`//!`), documenting the enclosing module.
+    pub inner_doc_lines: usize,
+
+    /// Outer doc comment lines (`///`, but not `////`), documenting the following item.
+    pub outer_doc_lines: usize,
+
     /// Lines that are considered code (everything that's not blank or comment).
     pub code_lines: usize,
 
+    /// Approximate number of statements: `;` on code lines at the top level of the code.
+    ///
+    /// Semicolons are skipped inside string and char literals, comments, and `(...)`/`[...]` groups, so
+    /// `[Field; 3]` array types and any `for (...; ...; ...)`-style header count nothing (Noir only has
+    /// range `for` loops, but the rule keeps the metric language-agnostic). Open groups carry over lines.
+    pub statement_count: usize,
+
+    /// Multi-line closers whose last element ends with a trailing comma (see
+    /// [`FileMetrics::closers_without_trailing_comma`]).
+    pub closers_with_trailing_comma: usize,
+
+    /// Multi-line closers whose last element has no trailing comma.
+    ///
+    /// Heuristic style sample: a code line starting with `)` or `]`, or with `}` after a `name: value` or
+    /// `...,` line (a struct literal or definition), is a closer; the preceding code line decides whether it
+    /// has a trailing comma. Empty groups (the preceding line ends with the opener) are not sampled, and
+    /// block-closing `}` lines are skipped since their last line is a statement, not an element.
+    pub closers_without_trailing_comma: usize,
+
+    /// Number of mutable bindings: `mut` as a whole word on code lines (`let mut x`, `mut x: Field` parameters).
+    ///
+    /// `&mut` references are not bindings and are skipped; so are `mut` in string literals and comments.
+    pub mut_binding_count: usize,
+
+    /// Sum of the declared lengths `N` of `[T; N]` array types (and `[x; N]` repeat expressions) on code lines.
+    ///
+    /// `N` is an integer literal or a `global` initialized with an integer literal in the same file
+    /// (`global N: u32 = 8;`). Globals from other files, computed globals (`global M = N * 2;`) and generic
+    /// lengths cannot be resolved and count 0.
+    pub array_total_declared_len: usize,
+
     /// Number of functions annotated with `#[test...]` (including #[test(should_fail)] variants).
     pub test_functions: usize,
 
@@ -39,23 +184,359 @@ pub struct FileMetrics {
     /// Number of code lines outside tests: code_lines - test_lines.
     pub non_test_lines: usize,
 
-    /// Total number of functions (`fn` and `pub fn`) in this file.
+    /// Total number of functions in this file: `fn` after any run of the modifiers `pub`, `pub(...)`,
+    /// `unconstrained` and `comptime`.
     pub functions: usize,
 
-    /// Number of `pub fn` (public functions) in this file.
+    /// Number of functions with a plain `pub` visibility (e.g. `pub fn`, `unconstrained pub fn`).
+    ///
+    /// Restricted visibilities such as `pub(crate) fn` are not public API and are not counted.
     pub pub_functions: usize,
 
     /// Number of non-test functions (i.e. functions that are not tests).
     pub non_test_functions: usize,
 
-    /// Does this file define a `main` function?
+    /// Number of non-test functions whose statements are more than 80% asserts (see
+    /// [`FunctionMetrics::is_spec`]).
+    pub spec_functions: usize,
+
+    /// Number of functions whose signature declares a return type (`-> T`).
+    pub functions_with_return: usize,
+
+    /// Number of function and `impl` signatures with a `where` clause.
+    ///
+    /// `where` must appear as a whole word between the `fn`/`impl` keyword and the body's `{` (or a trailing
+    /// `;`); string literals and `//` comments in the signature are ignored.
+    pub where_clause_count: usize,
+
+    /// Number of `use` declarations importing from this package: paths starting with `crate::`, `super::`,
+    /// `self::` or a module declared with `mod` in the same file.
+    pub internal_imports: usize,
+
+    /// Number of `use` declarations importing from other packages: `dep::`, `std::` or any other crate name.
+    ///
+    /// Together with [`FileMetrics::internal_imports`] this is a coarse coupling signal; each `use` counts
+    /// once, however many items its `{...}` group imports.
+    pub external_imports: usize,
+
+    /// Number of `pub use` re-exports, which add to the package's public API.
+    ///
+    /// Re-exports are also counted in [`FileMetrics::internal_imports`] / [`FileMetrics::external_imports`];
+    /// restricted visibilities such as `pub(crate) use` are not re-exports.
+    pub reexports: usize,
+
+    /// Number of modules annotated with `#[contract]`.
+    pub contract_modules: usize,
+
+    /// Number of inline modules (`mod name { ... }`), including nested ones.
+    pub inline_modules: usize,
+
+    /// Code lines per top-level inline module, keyed by module name (including the `mod` and closing lines).
+    ///
+    /// Code in nested inline modules counts towards the enclosing top-level module. Omitted from JSON when the
+    /// file has no inline modules.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_module: BTreeMap<String, usize>,
+
+    /// Number of functions annotated with `#[public]` (contract visibility, distinct from `pub`).
+    pub public_functions: usize,
+
+    /// Number of functions annotated with `#[private]` (contract visibility, distinct from `pub`).
+    pub private_functions: usize,
+
+    /// Number of `unsafe { ... }` blocks.
+    pub unsafe_blocks: usize,
+
+    /// Number of code lines inside `unsafe { ... }` blocks (including the opening and closing lines).
+    pub unsafe_lines: usize,
+
+    /// Does this file define a top-level `main` function? A `fn main` nested in a `mod` or `impl` does not count.
     pub has_main: bool,
 
-    /// Number of TODO/FIXME markers in comment lines.
+    /// Number of `main` parameters declared `pub` (public circuit inputs); `None` without `main`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_public_inputs: Option<usize>,
+
+    /// Is `main`'s return type declared `pub`? `Some(false)` when `main` returns nothing; `None` without `main`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_public_output: Option<bool>,
+
+    /// Number of comment lines containing a TODO marker (TODO/FIXME by default).
     pub todo_count: usize,
+
+    /// Occurrences of each TODO marker, keyed by the marker as configured.
+    ///
+    /// A line containing several markers counts once in `todo_count` but once per marker here. Markers that
+    /// do not occur are omitted; the map is omitted from JSON when empty.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub todo_markers: BTreeMap<String, usize>,
+
+    /// Number of debug print calls (`println(`, `print(`, `dbg(`) on code lines.
+    ///
+    /// Names must be whole words (`std::println(` counts, `my_print(` does not); calls inside string literals
+    /// and comments are ignored.
+    pub debug_print_count: usize,
+
+    /// Number of `#[derive(...)]` attributes applied to structs.
+    pub derive_count: usize,
+
+    /// How often each trait is derived on structs (e.g. `Eq`, `Hash`).
+    ///
+    /// Only collected when [`AnalysisOptions::list_derives`] is enabled; omitted from JSON when empty.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub derives: BTreeMap<String, usize>,
+
+    /// Occurrences of each primitive type name ([`PRIMITIVE_TYPES`]) as a whole word on code lines, outside
+    /// strings and comments: signatures, bindings, casts (`as u64`) and generic arguments alike.
+    ///
+    /// Only collected when [`AnalysisOptions::type_usage`] is enabled; omitted from JSON when empty.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub type_usage: BTreeMap<String, usize>,
+
+    /// Names of public items (`pub fn`, `pub struct`, `pub trait`, ...) in source order.
+    ///
+    /// Only collected when [`AnalysisOptions::list_public`] is enabled; omitted from JSON when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub public_items: Vec<String>,
+
+    /// `assert_eq(...)` calls with fewer than 2 or more than 3 top-level arguments (2 values plus an optional
+    /// message), which usually indicates a typo such as `assert_eq(a == b)`.
+    ///
+    /// Heuristic and single-line only: calls whose closing `)` is on a later line are not checked.
+    /// Only collected when [`AnalysisOptions::lint_asserts`] is enabled; omitted from JSON when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suspicious_asserts: Vec<SuspiciousAssert>,
+
+    /// Sum of [`FunctionMetrics::complexity`] over all functions in this file.
+    pub complexity: usize,
+
+    /// Highest [`FunctionMetrics::complexity`] of any function in this file (0 without functions).
+    pub max_function_complexity: usize,
+
+    /// Indentation style detected from the leading whitespace of indented code lines.
+    pub indent_style: IndentStyle,
+
+    /// Line terminators used in this file.
+    pub line_ending: LineEnding,
+
+    /// Whitespace-only lines before the first non-blank line.
+    ///
+    /// A file consisting only of blank lines counts them all here (and none as trailing).
+    pub leading_blank_lines: usize,
+
+    /// Whitespace-only lines after the last non-blank line.
+    pub trailing_blank_lines: usize,
+
+    /// Length of the longest line, in characters (Unicode scalar values, not bytes; line terminators excluded).
+    pub max_line_length: usize,
+
+    /// Number of lines longer than [`AnalysisOptions::max_line_length`] characters (default 100).
+    pub long_line_count: usize,
+
+    /// Per-function metrics (name, line span, code lines) in source order.
+    pub function_metrics: Vec<FunctionMetrics>,
+
+    /// Is this file excluded from project totals via a `// noir-metrics: ignore-file` directive?
+    pub ignored: bool,
+
+    /// Were these metrics computed from Noir code fences in a Markdown file (`--scan-markdown`)?
+    pub is_embedded: bool,
+
+    /// Hex-encoded 64-bit FNV-1a hash of the file contents.
+    ///
+    /// Only computed when [`AnalysisOptions::content_hash`] is enabled; omitted from JSON otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+
+    /// Number of code lines matching each `--count-regex` pattern, keyed by its name.
+    ///
+    /// Only present for patterns configured in [`AnalysisOptions::count_regexes`]; omitted from JSON when empty.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub regex_counts: BTreeMap<String, usize>,
+
+    /// `todo_count` per 1000 code lines (0.0 without code); only set with `--normalize`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todos_per_kloc: Option<f64>,
+
+    /// `complexity` per 1000 code lines (0.0 without code); only set with `--normalize`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complexity_per_kloc: Option<f64>,
+
+    /// `assert`/`assert_eq` calls per 1000 code lines (0.0 without code); only set with `--normalize`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asserts_per_kloc: Option<f64>,
+
+    /// Number of commits touching the file (`git log -- <path>`, optionally bounded by `--since`).
+    ///
+    /// Only set with `--git-churn` inside a git repository; untracked files count 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_count: Option<usize>,
+}
+
+impl FileMetrics {
+    /// Fold the metrics of another analysis of the same file into `self`.
+    ///
+    /// Counts are summed, flags are OR-ed, lists are appended, and maxima are kept. Used to combine the
+    /// per-fence results of [`analyze_markdown_file`].
+    fn absorb(&mut self, other: FileMetrics) {
+        self.is_test_file |= other.is_test_file;
+        self.has_inline_tests |= other.has_inline_tests;
+        self.total_lines += other.total_lines;
+        self.blank_lines += other.blank_lines;
+        self.comment_lines += other.comment_lines;
+        self.line_comment_lines += other.line_comment_lines;
+        self.block_comment_lines += other.block_comment_lines;
+        self.commented_code_lines += other.commented_code_lines;
+        self.disabled_attributes += other.disabled_attributes;
+        self.doc_comment_lines += other.doc_comment_lines;
+        self.inner_doc_lines += other.inner_doc_lines;
+        self.outer_doc_lines += other.outer_doc_lines;
+        self.code_lines += other.code_lines;
+        self.statement_count += other.statement_count;
+        self.closers_with_trailing_comma += other.closers_with_trailing_comma;
+        self.closers_without_trailing_comma += other.closers_without_trailing_comma;
+        self.mut_binding_count += other.mut_binding_count;
+        self.array_total_declared_len += other.array_total_declared_len;
+        self.test_functions += other.test_functions;
+        self.test_lines += other.test_lines;
+        self.non_test_lines += other.non_test_lines;
+        self.functions += other.functions;
+        self.pub_functions += other.pub_functions;
+        self.non_test_functions += other.non_test_functions;
+        self.spec_functions += other.spec_functions;
+        self.functions_with_return += other.functions_with_return;
+        self.where_clause_count += other.where_clause_count;
+        self.internal_imports += other.internal_imports;
+        self.external_imports += other.external_imports;
+        self.reexports += other.reexports;
+        self.contract_modules += other.contract_modules;
+        self.inline_modules += other.inline_modules;
+        for (name, lines) in other.by_module {
+            *self.by_module.entry(name).or_insert(0) += lines;
+        }
+        self.public_functions += other.public_functions;
+        self.private_functions += other.private_functions;
+        self.unsafe_blocks += other.unsafe_blocks;
+        self.unsafe_lines += other.unsafe_lines;
+        self.has_main |= other.has_main;
+        self.main_public_inputs = self.main_public_inputs.or(other.main_public_inputs);
+        self.main_public_output = self.main_public_output.or(other.main_public_output);
+        self.todo_count += other.todo_count;
+        self.debug_print_count += other.debug_print_count;
+        for (marker, count) in other.todo_markers {
+            *self.todo_markers.entry(marker).or_insert(0) += count;
+        }
+        self.derive_count += other.derive_count;
+        for (name, count) in other.derives {
+            *self.derives.entry(name).or_insert(0) += count;
+        }
+        for (name, count) in other.type_usage {
+            *self.type_usage.entry(name).or_insert(0) += count;
+        }
+        self.public_items.extend(other.public_items);
+        self.complexity += other.complexity;
+        self.max_function_complexity = self
+            .max_function_complexity
+            .max(other.max_function_complexity);
+        self.leading_blank_lines += other.leading_blank_lines;
+        self.trailing_blank_lines += other.trailing_blank_lines;
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+        self.long_line_count += other.long_line_count;
+        self.indent_style = match (self.indent_style, other.indent_style) {
+            (IndentStyle::None, style) | (style, IndentStyle::None) => style,
+            (a, b) if a == b => a,
+            _ => IndentStyle::Mixed,
+        };
+        self.line_ending = match (self.line_ending, other.line_ending) {
+            (LineEnding::None, ending) | (ending, LineEnding::None) => ending,
+            (a, b) if a == b => a,
+            _ => LineEnding::Mixed,
+        };
+        self.function_metrics.extend(other.function_metrics);
+        self.suspicious_asserts.extend(other.suspicious_asserts);
+        self.ignored |= other.ignored;
+        for (name, count) in other.regex_counts {
+            *self.regex_counts.entry(name).or_insert(0) += count;
+        }
+        self.is_empty = self.code_lines == 0 && self.comment_lines == 0;
+    }
+}
+
+/// Comment directive that excludes a file from project totals.
+const IGNORE_FILE_DIRECTIVE: &str = "noir-metrics: ignore-file";
+
+/// Number of leading lines searched for [`IGNORE_FILE_DIRECTIVE`].
+const IGNORE_FILE_DIRECTIVE_LINES: usize = 5;
+
+/// Analyze a single `.nr` file on disk and compute line-based metrics.
+///
+/// Reads the file and delegates to [`analyze_source`]. The returned [`FileMetrics::path`] is relative to
+/// `project_root` when possible.
+pub fn analyze_file(
+    path: &Path,
+    project_root: &Path,
+    options: &AnalysisOptions,
+) -> Result<FileMetrics> {
+    let source = fs::read_to_string(path)?;
+    Ok(analyze_contents(&source, path, project_root, options))
+}
+
+/// Analyze the already-loaded contents of the file at `path`.
+///
+/// Like [`analyze_file`] without the IO: `path` is made relative to `project_root` when it lies below it,
+/// and the content hash is recorded when [`AnalysisOptions::content_hash`] is set.
+pub(crate) fn analyze_contents(
+    source: &str,
+    path: &Path,
+    project_root: &Path,
+    options: &AnalysisOptions,
+) -> FileMetrics {
+    let rel_path = path.strip_prefix(project_root).unwrap_or(path);
+
+    let mut metrics = analyze_source(source, rel_path, options);
+    if options.content_hash {
+        metrics.content_hash = Some(content_hash(source.as_bytes()));
+    }
+
+    metrics
+}
+
+/// Analyze the Noir code embedded in a Markdown file.
+///
+/// Fenced blocks tagged `noir` or `nr` are extracted with [`extract_noir_fences`], each is analyzed with
+/// [`analyze_source`], and the results are combined into a single entry for the `.md` file with
+/// [`FileMetrics::is_embedded`] set. Function and suspicious-assert line numbers are relative to the start of
+/// their block.
+///
+/// Returns `Ok(None)` when the file contains no Noir fences.
+pub fn analyze_markdown_file(
+    path: &Path,
+    project_root: &Path,
+    options: &AnalysisOptions,
+) -> Result<Option<FileMetrics>> {
+    let markdown = fs::read_to_string(path)?;
+    let rel_path = path.strip_prefix(project_root).unwrap_or(path);
+
+    let blocks = extract_noir_fences(&markdown);
+    if blocks.is_empty() {
+        return Ok(None);
+    }
+
+    let mut combined = FileMetrics {
+        path: rel_path.to_path_buf(),
+        is_embedded: true,
+        ..Default::default()
+    };
+    for block in &blocks {
+        combined.absorb(analyze_source(block, rel_path, options));
+    }
+
+    Ok(Some(combined))
 }
 
-/// Analyze a single `.nr` file and compute line-based metrics.
+/// Analyze Noir source text and compute line-based metrics.
+///
+/// `rel_path` is reported as [`FileMetrics::path`] and used for the test-file heuristic; no IO is performed.
 ///
 /// Line classification:
 /// - Blank lines: `trim().is_empty()`.
@@ -68,22 +549,90 @@ pub struct FileMetrics {
 /// - Test line attribution uses a brace-depth heuristic: once a test function is entered, lines are counted as
 ///   test lines until the brace depth returns to zero.
 ///
+/// Contract detection:
+/// - `#[contract]` before a `mod`/`pub mod` line counts a contract module.
+/// - `#[public]`/`#[private]` before a `fn` line count contract-visibility functions. Like `#[test]`, these
+///   attributes stay pending until the next matching item line.
+///
+/// Unsafe detection:
+/// - A code line containing `unsafe {` starts an unsafe block. Like test attribution, a brace-depth heuristic
+///   (counted from the `unsafe` keyword onward) decides where the block ends; all code lines in between are
+///   counted as [`FileMetrics::unsafe_lines`].
+///
+/// Function spans:
+/// - Each `fn`/`pub fn` line starts a [`FunctionMetrics`] entry that ends once its body braces balance
+///   (or on the same line for body-less declarations ending in `;`). Nested `fn` lines are not tracked separately.
+///
+/// Return types:
+/// - [`FileMetrics::functions_with_return`] counts functions with `->` in their signature, from the `fn` line up to
+///   the body's `{` (or a trailing `;`), possibly spanning several lines. `->` in string literals or `//`
+///   comments is ignored.
+///
+/// Derives:
+/// - Single-line `#[derive(...)]` attributes directly preceding a `struct` (other attributes may sit in between)
+///   count towards [`FileMetrics::derive_count`]; with [`AnalysisOptions::list_derives`] each derived trait is
+///   tallied in [`FileMetrics::derives`]. Derives on other items are not counted.
+///
 /// TODO/FIXME detection:
-/// - `todo_count` increments when `TODO` or `FIXME` (case-insensitive) appears in comment lines.
+/// - `todo_count` increments when a marker from [`AnalysisOptions::todo_markers`] (`TODO` or `FIXME` by
+///   default) appears case-insensitively in a comment line; each matching marker is tallied in
+///   [`FileMetrics::todo_markers`].
+///
+/// Debug prints:
+/// - [`FileMetrics::debug_print_count`] counts `println(`, `print(` and `dbg(` calls on code lines, outside
+///   string literals and comments.
+///
+/// Complexity:
+/// - Each function's complexity is 1 plus the decision points (`if`, `for`, `while`, `loop`, `match`, `&&`,
+///   `||`) on its code lines; [`FileMetrics::complexity`] sums these per file.
+/// - With [`AnalysisOptions::skip_complexity`] every complexity value is 0.
+///
+/// Indentation:
+/// - [`FileMetrics::indent_style`] looks at the leading whitespace of code lines only (blank and comment lines
+///   are skipped). A line that mixes tabs and spaces makes the whole file `mixed`.
+///
+/// Line endings:
+/// - [`FileMetrics::line_ending`] is read from the raw contents (`\r\n` versus a bare `\n`); a lone `\r` is
+///   not treated as a terminator.
 ///
-/// Path handling:
-/// - The returned [`FileMetrics::path`] is relative to `project_root` when possible.
+/// Public items (opt-in via [`AnalysisOptions::list_public`]):
+/// - Code lines starting with `pub` (or `pub(...)`), optionally followed by `unconstrained`/`comptime`, then one of
+///   `fn`, `struct`, `trait`, `global`, `type`, `mod`, `enum` contribute the following identifier. Generic
+///   parameters and where-clauses are not part of the name.
+///
+/// Custom regex counts (opt-in via [`AnalysisOptions::count_regexes`]):
+/// - Each code line matching a pattern increments that pattern's entry in [`FileMetrics::regex_counts`];
+///   blank and comment lines are never counted.
+///
+/// Ignore directive:
+/// - A `// noir-metrics: ignore-file` line comment within the first few lines sets [`FileMetrics::ignored`].
+///   Ignored files are still analyzed and listed, but are skipped when computing project totals.
 ///
 /// Limitations:
 /// - The analysis does not parse Noir syntax and may misclassify complex cases (e.g. braces in strings,
 ///   inline block comments, or comment delimiters in unusual positions).
-pub fn analyze_file(path: &Path, project_root: &Path) -> Result<FileMetrics> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
+pub fn analyze_source(source: &str, rel_path: &Path, options: &AnalysisOptions) -> FileMetrics {
+    analyze_source_with_trace(source, rel_path, options, None)
+}
 
+/// Like [`analyze_source`], additionally recording the classification of every line into `trace`.
+///
+/// Used by `--explain` to show where the line heuristics made a decision.
+pub fn analyze_source_with_trace(
+    source: &str,
+    rel_path: &Path,
+    options: &AnalysisOptions,
+    mut trace: Option<&mut Vec<LineTrace>>,
+) -> FileMetrics {
     let mut total_lines = 0usize;
     let mut blank_lines = 0usize;
     let mut comment_lines = 0usize;
+    let mut line_comment_lines = 0usize;
+    let mut block_comment_lines = 0usize;
+    let mut commented_code_lines = 0usize;
+    let mut disabled_attributes = 0usize;
+    let mut inner_doc_lines = 0usize;
+    let mut outer_doc_lines = 0usize;
     let mut code_lines = 0usize;
 
     let mut test_functions = 0usize;
@@ -93,208 +642,1926 @@ pub fn analyze_file(path: &Path, project_root: &Path) -> Result<FileMetrics> {
     let mut functions = 0usize;
     let mut pub_functions = 0usize;
     let mut non_test_functions = 0usize;
+    let mut functions_with_return = 0usize;
     let mut has_main = false;
+    let mut main_signature: Option<String> = None;
+    let mut in_main_signature = false;
+    let mut unsafe_blocks = 0usize;
+    let mut unsafe_lines = 0usize;
+    let mut contract_modules = 0usize;
+    let mut inline_modules = 0usize;
+    let mut by_module: BTreeMap<String, usize> = BTreeMap::new();
+    let mut current_module: Option<String> = None;
+    let mut module_depth: i32 = 0;
+    let mut public_functions = 0usize;
+    let mut private_functions = 0usize;
     let mut todo_count = 0usize;
+    let mut todo_markers: BTreeMap<String, usize> = BTreeMap::new();
+    let markers: Vec<(String, String)> = options
+        .todo_markers()
+        .into_iter()
+        .map(|m| (m.to_lowercase(), m))
+        .collect();
+    let mut derive_count = 0usize;
+    let mut derives: BTreeMap<String, usize> = BTreeMap::new();
+    let mut type_usage: BTreeMap<String, usize> = BTreeMap::new();
+    let mut pending_derive_attrs = 0usize;
+    let mut pending_derives: Vec<String> = Vec::new();
+    let mut ignored = false;
+    let mut public_items = Vec::new();
+    let mut suspicious_asserts = Vec::new();
+    let mut regex_counts: BTreeMap<String, usize> = options
+        .count_regexes
+        .iter()
+        .map(|c| (c.name.clone(), 0))
+        .collect();
+    let mut function_metrics = Vec::new();
+    let mut indent_tabs = false;
+    let mut indent_spaces = false;
+    let mut statement_count = 0usize;
+    let mut closers_with_trailing_comma = 0usize;
+    let mut closers_without_trailing_comma = 0usize;
+    let mut prev_code = String::new();
+    let mut debug_print_count = 0usize;
+    let mut mut_binding_count = 0usize;
+    let globals = literal_globals(source);
+    let modules = declared_modules(source);
+    let mut array_total_declared_len = 0usize;
+    let mut group_depth = 0usize;
 
-    let mut pending_test_attr = false;
+    let mut pending_attrs: Vec<String> = Vec::new();
     let mut inside_test = false;
     let mut brace_depth: i32 = 0;
+    // Unlike `brace_depth`, never reset on entering a test; 0 means the line starts at the top level.
+    let mut item_depth: i32 = 0;
     let mut in_block_comment = false;
 
-    for line_result in reader.lines() {
-        let line = line_result?;
+    let line_length_limit = options.max_line_length.unwrap_or(DEFAULT_MAX_LINE_LENGTH);
+    let mut max_line_length = 0usize;
+    let mut long_line_count = 0usize;
+
+    let mut seen_non_blank = false;
+    let mut leading_blank_lines = 0usize;
+    let mut blank_run = 0usize;
+
+    let mut inside_unsafe = false;
+    let mut unsafe_depth: i32 = 0;
+
+    let mut in_signature = false;
+    let mut signature_has_return = false;
+
+    let mut in_bound_signature = false;
+    let mut signature_has_where = false;
+    let mut where_clause_count = 0usize;
+    let mut internal_imports = 0usize;
+    let mut external_imports = 0usize;
+    let mut reexports = 0usize;
+
+    let mut current_fn: Option<FunctionMetrics> = None;
+    let mut fn_depth: i32 = 0;
+    let mut fn_body_opened = false;
+
+    for line in source.lines() {
         total_lines += 1;
 
         let trimmed = line.trim();
 
+        let length = line.chars().count();
+        max_line_length = max_line_length.max(length);
+        if length > line_length_limit {
+            long_line_count += 1;
+        }
+
+        if !trimmed.is_empty() {
+            seen_non_blank = true;
+            blank_run = 0;
+        } else if seen_non_blank {
+            blank_run += 1;
+        } else {
+            leading_blank_lines += 1;
+        }
+
+        if total_lines <= IGNORE_FILE_DIRECTIVE_LINES && is_ignore_directive(trimmed) {
+            ignored = true;
+        }
+
         if in_block_comment {
             comment_lines += 1;
+            block_comment_lines += 1;
 
-            if line_has_todo(trimmed) {
+            if record_todos(trimmed, &markers, &mut todo_markers) {
                 todo_count += 1;
             }
 
             if trimmed.contains("*/") {
                 in_block_comment = false;
             }
+            record(&mut trace, total_lines, LineKind::Comment, brace_depth);
             continue;
         }
 
         if trimmed.starts_with("/*") {
             comment_lines += 1;
+            block_comment_lines += 1;
 
-            if line_has_todo(trimmed) {
+            if record_todos(trimmed, &markers, &mut todo_markers) {
                 todo_count += 1;
             }
 
             if !trimmed.contains("*/") {
                 in_block_comment = true;
             }
+            record(&mut trace, total_lines, LineKind::Comment, brace_depth);
             continue;
         }
 
-        let mut is_test_attr_line = false;
+        let attrs = parse_attributes(trimmed);
+        let is_test_attr_line = attrs.iter().any(|a| is_test_attr(a));
+        pending_attrs.extend(attrs.into_iter().filter(|a| !a.starts_with("derive(")));
 
-        if trimmed.starts_with("#[test") {
-            pending_test_attr = true;
-            is_test_attr_line = true;
+        if strip_visibility(trimmed).starts_with("mod ") {
+            if pending_attrs.iter().any(|a| a == "contract") {
+                contract_modules += 1;
+            }
+            pending_attrs.clear();
         }
 
-        let is_fn_line = trimmed.starts_with("fn ") || trimmed.starts_with("pub fn ");
-        let is_pub_fn = trimmed.starts_with("pub fn ");
+        let fn_modifiers = parse_fn_line(trimmed);
+        let is_fn_line = fn_modifiers.is_some();
+        let is_pub_fn = fn_modifiers.is_some_and(|m| m.is_pub);
+
+        if is_fn_line || trimmed.starts_with("impl ") || trimmed.starts_with("impl<") {
+            in_bound_signature = true;
+            signature_has_where = false;
+        }
 
         if is_fn_line {
+            in_signature = true;
+            signature_has_return = false;
+
+            let attributes = std::mem::take(&mut pending_attrs);
+            let is_test = attributes.iter().any(|a| is_test_attr(a));
+
+            if attributes.iter().any(|a| a == "public") {
+                public_functions += 1;
+            }
+            if attributes.iter().any(|a| a == "private") {
+                private_functions += 1;
+            }
+
+            if current_fn.is_none() {
+                current_fn = Some(FunctionMetrics {
+                    name: parse_fn_name(trimmed),
+                    start_line: total_lines,
+                    end_line: total_lines,
+                    is_test,
+                    is_pub: is_pub_fn,
+                    is_unconstrained: fn_modifiers.is_some_and(|m| m.is_unconstrained),
+                    is_comptime: fn_modifiers.is_some_and(|m| m.is_comptime),
+                    should_fail: attributes
+                        .iter()
+                        .any(|a| is_test_attr(a) && a.contains("should_fail")),
+                    complexity: if options.skip_complexity { 0 } else { 1 },
+                    attributes,
+                    ..Default::default()
+                });
+                fn_depth = 0;
+                fn_body_opened = false;
+            }
+
             functions += 1;
             if is_pub_fn {
                 pub_functions += 1;
             }
 
-            if pending_test_attr {
+            if is_test {
                 test_functions += 1;
                 inside_test = true;
-                pending_test_attr = false;
                 brace_depth = 0;
             } else {
                 non_test_functions += 1;
             }
 
-            if trimmed.starts_with("fn main(") || trimmed.starts_with("pub fn main(") {
+            if item_depth == 0 && parse_fn_name(trimmed) == "main" {
                 has_main = true;
+                if main_signature.is_none() {
+                    main_signature = Some(String::new());
+                    in_main_signature = true;
+                }
             }
         }
 
+        let kind;
         if trimmed.is_empty() {
             blank_lines += 1;
+            kind = LineKind::Blank;
         } else if trimmed.starts_with("//") {
             comment_lines += 1;
+            line_comment_lines += 1;
+            kind = LineKind::Comment;
+
+            if looks_like_code(trimmed) {
+                commented_code_lines += 1;
+            }
+            if trimmed
+                .strip_prefix("//")
+                .is_some_and(|text| text.trim_start().starts_with("#["))
+            {
+                disabled_attributes += 1;
+            }
+
+            if trimmed.starts_with("//!") {
+                inner_doc_lines += 1;
+            } else if trimmed.starts_with("///") && !trimmed.starts_with("////") {
+                outer_doc_lines += 1;
+            }
 
-            if line_has_todo(trimmed) {
+            if record_todos(trimmed, &markers, &mut todo_markers) {
                 todo_count += 1;
             }
         } else {
             code_lines += 1;
+            let statements = count_statements(line, &mut group_depth);
+            statement_count += statements;
+            let code = strip_literals(line).trim().to_string();
+            match trailing_comma_sample(&prev_code, &code) {
+                Some(true) => closers_with_trailing_comma += 1,
+                Some(false) => closers_without_trailing_comma += 1,
+                None => {}
+            }
+            match import_kind(&code, &modules) {
+                Some(ImportKind::Internal) => internal_imports += 1,
+                Some(ImportKind::External) => external_imports += 1,
+                None => {}
+            }
+            if code.starts_with("pub use ") {
+                reexports += 1;
+            }
+            prev_code = code;
+            debug_print_count += count_debug_prints(line);
+            mut_binding_count += count_mut_bindings(line);
+            array_total_declared_len += array_declared_len(line, &globals);
+            if options.type_usage {
+                count_type_usage(line, &mut type_usage);
+            }
+
+            if let Some(name) = parse_inline_mod(trimmed) {
+                inline_modules += 1;
+                if current_module.is_none() {
+                    current_module = Some(name);
+                    module_depth = 0;
+                }
+            }
+            if let Some(name) = &current_module {
+                *by_module.entry(name.clone()).or_insert(0) += 1;
+                module_depth += count_braces(line);
+                if module_depth <= 0 {
+                    current_module = None;
+                }
+            }
+
+            if options.list_public
+                && let Some(name) = parse_public_item(trimmed)
+            {
+                public_items.push(name);
+            }
+
+            if options.lint_asserts && has_suspicious_assert_eq(line) {
+                suspicious_asserts.push(SuspiciousAssert {
+                    line: total_lines,
+                    snippet: trimmed.to_string(),
+                });
+            }
+
+            if let Some(traits) = parse_derive(trimmed) {
+                pending_derive_attrs += 1;
+                pending_derives.extend(traits);
+            } else if !trimmed.starts_with("#[") {
+                if is_struct_line(trimmed) {
+                    pending_attrs.clear();
+                    derive_count += pending_derive_attrs;
+                    if options.list_derives {
+                        for name in pending_derives.drain(..) {
+                            *derives.entry(name).or_insert(0) += 1;
+                        }
+                    }
+                }
+                pending_derive_attrs = 0;
+                pending_derives.clear();
+            }
+
+            for c in &options.count_regexes {
+                if c.pattern.is_match(line) {
+                    *regex_counts.entry(c.name.clone()).or_insert(0) += 1;
+                }
+            }
+
+            for ch in line.chars().take_while(|c| c.is_whitespace()) {
+                match ch {
+                    '\t' => indent_tabs = true,
+                    ' ' => indent_spaces = true,
+                    _ => {}
+                }
+            }
+
+            if let Some(f) = current_fn.as_mut() {
+                f.code_lines += 1;
+                f.statement_count += statements;
+                f.assert_count += count_asserts(line);
+                if !options.skip_complexity {
+                    f.complexity += count_decision_points(trimmed);
+                }
+            }
+
+            if in_signature {
+                let (has_arrow, ended) = scan_signature(line);
+                signature_has_return |= has_arrow;
+
+                if in_main_signature && let Some(signature) = main_signature.as_mut() {
+                    let code = line.split("//").next().unwrap_or_default();
+                    signature.push_str(code.split('{').next().unwrap_or_default());
+                    signature.push(' ');
+                }
+
+                if ended {
+                    in_signature = false;
+                    in_main_signature = false;
+                    if signature_has_return {
+                        functions_with_return += 1;
+                    }
+                }
+            }
+
+            if in_bound_signature {
+                let (has_where, ended) = scan_where(line);
+                signature_has_where |= has_where;
+
+                if ended {
+                    in_bound_signature = false;
+                    if signature_has_where {
+                        where_clause_count += 1;
+                    }
+                }
+            }
+
+            if inside_unsafe {
+                unsafe_lines += 1;
+                if find_unsafe_block(line).is_some() {
+                    unsafe_blocks += 1;
+                }
+
+                unsafe_depth += count_braces(line);
+                inside_unsafe = unsafe_depth > 0;
+            } else if let Some(pos) = find_unsafe_block(line) {
+                unsafe_blocks += 1;
+                unsafe_lines += 1;
+
+                unsafe_depth = count_braces(&line[pos..]);
+                inside_unsafe = unsafe_depth > 0;
+            }
 
             if inside_test || is_test_attr_line {
                 test_lines += 1;
+                kind = LineKind::Test;
             } else {
                 non_test_lines += 1;
+                kind = LineKind::Code;
             }
         }
 
-        let braces_delta = count_braces(&line);
+        let braces_delta = count_braces(line);
         brace_depth += braces_delta;
+        item_depth += braces_delta;
+        record(&mut trace, total_lines, kind, brace_depth);
 
         if inside_test && brace_depth == 0 {
             inside_test = false;
         }
+
+        if let Some(mut f) = current_fn.take() {
+            fn_depth += braces_delta;
+            fn_body_opened |= line.contains('{');
+
+            let closed = if fn_body_opened {
+                fn_depth <= 0
+            } else {
+                trimmed.ends_with(';')
+            };
+
+            if closed {
+                f.end_line = total_lines;
+                f.is_spec = f.looks_like_spec();
+                function_metrics.push(f);
+            } else {
+                current_fn = Some(f);
+            }
+        }
+    }
+
+    // An unterminated function runs to the end of the file.
+    if let Some(mut f) = current_fn {
+        f.end_line = total_lines;
+        f.is_spec = f.looks_like_spec();
+        function_metrics.push(f);
     }
 
-    let rel_path = path
-        .strip_prefix(project_root)
-        .unwrap_or(path)
-        .to_path_buf();
+    let spec_functions = function_metrics.iter().filter(|f| f.is_spec).count();
+
+    let is_test_file = is_test_file(rel_path);
 
-    let is_test_file = is_test_file(&rel_path);
+    let main_visibility = main_signature.as_deref().map(parse_main_visibility);
 
-    Ok(FileMetrics {
-        path: rel_path,
+    let complexity = function_metrics.iter().map(|f| f.complexity).sum();
+    let max_function_complexity = function_metrics
+        .iter()
+        .map(|f| f.complexity)
+        .max()
+        .unwrap_or(0);
+
+    let indent_style = match (indent_tabs, indent_spaces) {
+        (false, false) => IndentStyle::None,
+        (true, false) => IndentStyle::Tabs,
+        (false, true) => IndentStyle::Spaces,
+        (true, true) => IndentStyle::Mixed,
+    };
+
+    let line_ending = detect_line_ending(source.as_bytes());
+
+    FileMetrics {
+        path: rel_path.to_path_buf(),
         is_test_file,
+        has_inline_tests: test_functions > 0 && !is_test_file,
+        is_empty: code_lines == 0 && comment_lines == 0,
         total_lines,
         blank_lines,
         comment_lines,
+        line_comment_lines,
+        block_comment_lines,
+        commented_code_lines,
+        disabled_attributes,
+        doc_comment_lines: inner_doc_lines + outer_doc_lines,
+        inner_doc_lines,
+        outer_doc_lines,
         code_lines,
+        statement_count,
+        closers_with_trailing_comma,
+        closers_without_trailing_comma,
+        mut_binding_count,
+        array_total_declared_len,
         test_functions,
         test_lines,
         non_test_lines,
         functions,
         pub_functions,
         non_test_functions,
+        spec_functions,
+        functions_with_return,
+        where_clause_count,
+        internal_imports,
+        external_imports,
+        reexports,
+        unsafe_blocks,
+        unsafe_lines,
+        contract_modules,
+        inline_modules,
+        by_module,
+        public_functions,
+        private_functions,
         has_main,
+        main_public_inputs: main_visibility.map(|(inputs, _)| inputs),
+        main_public_output: main_visibility.map(|(_, output)| output),
         todo_count,
-    })
+        todo_markers,
+        debug_print_count,
+        derive_count,
+        derives,
+        type_usage,
+        public_items,
+        suspicious_asserts,
+        complexity,
+        max_function_complexity,
+        indent_style,
+        line_ending,
+        leading_blank_lines,
+        trailing_blank_lines: blank_run,
+        max_line_length,
+        long_line_count,
+        function_metrics,
+        ignored,
+        is_embedded: false,
+        content_hash: None,
+        regex_counts,
+        todos_per_kloc: None,
+        complexity_per_kloc: None,
+        asserts_per_kloc: None,
+        commit_count: None,
+    }
 }
 
-/// Count the net number of braces on a line: `{` as +1, `}` as -1.
-fn count_braces(line: &str) -> i32 {
-    let mut delta = 0i32;
+/// 64-bit FNV-1a hash of `bytes`, as 16 lowercase hex digits.
+///
+/// Not cryptographic; it only needs to be stable across runs and platforms to spot identical files.
+fn content_hash(bytes: &[u8]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
 
-    for ch in line.chars() {
-        match ch {
-            '{' => delta += 1,
-            '}' => delta -= 1,
-            _ => {}
-        }
-    }
+    let hash = bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    });
 
-    delta
+    format!("{hash:016x}")
 }
 
-/// Check if a string contains todo or fixme
-fn line_has_todo(s: &str) -> bool {
-    let lower = s.to_lowercase();
-    lower.contains("todo") || lower.contains("fixme")
+/// Parse the trait names of a single-line `#[derive(A, B)]` attribute.
+fn parse_derive(trimmed: &str) -> Option<Vec<String>> {
+    let inner = trimmed.strip_prefix("#[derive(")?.strip_suffix(")]")?;
+
+    Some(
+        inner
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
 }
 
-/// Heuristic to decide if a file is a "test file".
+/// Contents of the `#[...]` attributes at the start of a trimmed line (e.g. `test(should_fail)`, `oracle(f)`).
 ///
-/// Rules:
-/// - If any path component is exactly "tests" or "test" return true.
-/// - If the file name ends with `_test.nr`, return true.
-fn is_test_file(rel_path: &Path) -> bool {
-    if rel_path
-        .components()
-        .any(|c| matches!(c.as_os_str().to_str(), Some("tests" | "test")))
-    {
-        return true;
-    }
+/// Several attributes may share a line (`#[test] #[recursive]`); scanning stops at the first text that is not
+/// an attribute. Returns an empty list for lines that do not start with `#[`.
+fn parse_attributes(trimmed: &str) -> Vec<String> {
+    let mut attrs = Vec::new();
+    let mut rest = trimmed;
 
-    if let Some(file_name) = rel_path.file_name().and_then(|s| s.to_str())
-        && file_name.ends_with("_test.nr")
-    {
-        return true;
+    while let Some(body) = rest.strip_prefix("#[") {
+        let mut depth = 1;
+        let Some(end) = body.find(|c| {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        }) else {
+            break;
+        };
+
+        attrs.push(body[..end].trim().to_string());
+        rest = body[end + 1..].trim_start();
     }
 
-    false
+    attrs
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::PathBuf;
+/// Is this attribute (as returned by [`parse_attributes`]) a `#[test]` or `#[test(...)]` marker?
+fn is_test_attr(attr: &str) -> bool {
+    attr == "test" || attr.starts_with("test(")
+}
 
-    #[test]
-    fn counts_lines_and_tests_exactly() {
-        let project_root = PathBuf::from("tests/fixtures/file_metrics");
-        let path = project_root.join("src/metrics.nr");
+/// Does this trimmed code line start a struct definition (`struct`, `pub struct`, `pub(crate) struct`)?
+fn is_struct_line(trimmed: &str) -> bool {
+    let rest = if let Some(rest) = trimmed.strip_prefix("pub") {
+        match rest.strip_prefix('(') {
+            Some(scoped) => scoped.split_once(')').map_or("", |(_, after)| after),
+            None => rest,
+        }
+        .trim_start()
+    } else {
+        trimmed
+    };
+
+    rest.starts_with("struct ")
+}
 
-        let metrics = analyze_file(&path, &project_root).expect("analyze_file should succeed");
+/// Scan one line of a function signature.
+///
+/// Returns whether a `->` appears before the body's `{` (outside string literals and `//` comments), and
+/// whether the signature ends on this line (at `{` or a trailing `;`).
+fn scan_signature(line: &str) -> (bool, bool) {
+    let (code, ended) = signature_code(line);
+    (code.contains("->"), ended)
+}
 
-        assert_eq!(
-            metrics.code_lines,
-            metrics.test_lines + metrics.non_test_lines,
-            "code_lines should equal test_lines + non_test_lines",
-        );
+/// Does one line of a function or `impl` signature contain the `where` keyword (see
+/// [`FileMetrics::where_clause_count`])? Also returns whether the signature ends on this line.
+fn scan_where(line: &str) -> (bool, bool) {
+    let (code, ended) = signature_code(line);
+    let has_where = code
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word == "where");
+    (has_where, ended)
+}
 
-        let v = serde_json::to_value(&metrics).expect("FileMetrics should serialize");
-        insta::assert_json_snapshot!(v);
-    }
+/// Signature text of one line, up to the body's `{`, without string literals and `//` comments; and
+/// whether the signature ends on this line (at `{` or a trailing `;`).
+fn signature_code(line: &str) -> (String, bool) {
+    let mut code = String::new();
+    let mut in_string = false;
+    let mut chars = line.chars().peekable();
 
-    #[test]
-    fn is_test_file_detects_tests_dir() {
-        assert!(is_test_file(Path::new("tests/main.nr")));
-        assert!(is_test_file(Path::new("src/tests/main.nr")));
-        assert!(is_test_file(Path::new("src/test/main.nr")));
-    }
+    while let Some(ch) = chars.next() {
+        if in_string {
+            match ch {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
 
-    #[test]
-    fn is_test_file_detects_suffix() {
-        assert!(is_test_file(Path::new("src/foo_test.nr")));
+        match ch {
+            '"' => in_string = true,
+            '/' if chars.peek() == Some(&'/') => break,
+            '{' => return (code, true),
+            _ => code.push(ch),
+        }
     }
 
-    #[test]
-    fn is_test_file_false_for_regular_files() {
-        assert!(!is_test_file(Path::new("src/main.nr")));
-        assert!(!is_test_file(Path::new("src/lib.nr")));
+    let ended = code.trim_end().ends_with(';');
+    (code, ended)
+}
+
+/// Count top-level `;` on a code line (see [`FileMetrics::statement_count`]).
+///
+/// `depth` is the number of `(`/`[` groups still open from previous lines and is updated in place.
+fn count_statements(line: &str, depth: &mut usize) -> usize {
+    let mut count = 0;
+    let mut in_string = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_string {
+            match ch {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => in_string = true,
+            '\'' => {
+                // Char literal: `'x'` or `'\x'`.
+                let mut rest = chars.clone();
+                if rest.next() == Some('\\') {
+                    rest.next();
+                }
+                if rest.next() == Some('\'') {
+                    chars = rest;
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => break,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '(' | '[' => *depth += 1,
+            ')' | ']' => *depth = depth.saturating_sub(1),
+            ';' if *depth == 0 => count += 1,
+            _ => {}
+        }
+    }
+
+    count
+}
+
+/// Name of the inline module opened on a trimmed code line (`mod name {`, `pub mod name {`,
+/// `pub(crate) mod name {`); `None` for other lines, including `mod name;` declarations.
+fn parse_inline_mod(trimmed: &str) -> Option<String> {
+    let rest = strip_visibility(trimmed).strip_prefix("mod ")?.trim_start();
+
+    let name: String = rest
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    let after = rest[name.len()..].trim_start();
+
+    (!name.is_empty() && after.starts_with('{')).then_some(name)
+}
+
+/// Classify the line terminators in `bytes` (see [`FileMetrics::line_ending`]).
+fn detect_line_ending(bytes: &[u8]) -> LineEnding {
+    let mut lf = false;
+    let mut crlf = false;
+
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'\n' {
+            if i > 0 && bytes[i - 1] == b'\r' {
+                crlf = true;
+            } else {
+                lf = true;
+            }
+        }
+    }
+
+    match (lf, crlf) {
+        (false, false) => LineEnding::None,
+        (true, false) => LineEnding::Lf,
+        (false, true) => LineEnding::Crlf,
+        (true, true) => LineEnding::Mixed,
+    }
+}
+
+/// Count debug print calls on a code line (see [`FileMetrics::debug_print_count`]).
+fn count_debug_prints(line: &str) -> usize {
+    let code = strip_literals(line);
+
+    ["println", "print", "dbg"]
+        .iter()
+        .map(|name| {
+            code.match_indices(name)
+                .filter(|(pos, _)| {
+                    let before = code[..*pos].chars().next_back();
+                    let after = code[pos + name.len()..].trim_start();
+                    !before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+                        && after.starts_with('(')
+                })
+                .count()
+        })
+        .sum()
+}
+
+/// Count mutable bindings on a code line (see [`FileMetrics::mut_binding_count`]).
+fn count_mut_bindings(line: &str) -> usize {
+    let code = strip_literals(line);
+
+    code.match_indices("mut")
+        .filter(|(pos, kw)| {
+            let before = &code[..*pos];
+            let after = code[pos + kw.len()..].chars().next();
+
+            let word_start = !before
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_');
+            // `&mut` (or `& mut`) is a reference, not a binding.
+            let reference = before.trim_end().ends_with('&');
+            word_start && !reference && after.is_some_and(char::is_whitespace)
+        })
+        .count()
+}
+
+/// Classify a code line as a multi-line closer (see [`FileMetrics::closers_without_trailing_comma`]):
+/// `Some(true)` when `prev`, the preceding code line, ends with a trailing comma, `Some(false)` when it does
+/// not, and `None` when `code` is not a sampled closer. Both lines are comment- and literal-stripped.
+fn trailing_comma_sample(prev: &str, code: &str) -> Option<bool> {
+    let closer = code.chars().next()?;
+    if prev.is_empty() || prev.ends_with(['(', '[', '{']) {
+        return None;
+    }
+
+    let has_comma = prev.ends_with(',');
+    match closer {
+        ')' | ']' => Some(has_comma),
+        '}' if has_comma || looks_like_field(prev) => Some(has_comma),
+        _ => None,
+    }
+}
+
+/// Does a code line look like a `name: value` struct field (not a `path::item`, `let x: T = ...` or a
+/// statement)?
+fn looks_like_field(code: &str) -> bool {
+    let Some((name, rest)) = code.split_once(':') else {
+        return false;
+    };
+    let name = name.strip_prefix("pub ").unwrap_or(name).trim();
+    !rest.starts_with(':')
+        && !code.ends_with(';')
+        && !name.is_empty()
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Count `assert(...)` and `assert_eq(...)` calls on a code line (see [`FunctionMetrics::assert_count`]).
+fn count_asserts(line: &str) -> usize {
+    let code = strip_literals(line);
+
+    ["assert(", "assert_eq("]
+        .iter()
+        .flat_map(|call| code.match_indices(call))
+        .filter(|(pos, _)| {
+            !code[..*pos]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        })
+        .count()
+}
+
+/// Does the line contain an `assert_eq(...)` call, closed on the same line, with other than 2 or 3
+/// top-level arguments (see [`FileMetrics::suspicious_asserts`])?
+fn has_suspicious_assert_eq(line: &str) -> bool {
+    let code = strip_literals(line);
+
+    code.match_indices("assert_eq(").any(|(pos, call)| {
+        let word_start = !code[..pos]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        if !word_start {
+            return false;
+        }
+
+        let mut depth = 0usize;
+        let mut args = 1;
+        let mut empty = true;
+        let mut trailing_comma = false;
+        for ch in code[pos + call.len()..].chars() {
+            match ch {
+                '(' | '[' | '{' => depth += 1,
+                ')' if depth == 0 => {
+                    let count = if empty {
+                        0
+                    } else {
+                        args - usize::from(trailing_comma)
+                    };
+                    return !(2..=3).contains(&count);
+                }
+                ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    args += 1;
+                    trailing_comma = true;
+                    continue;
+                }
+                _ => {}
+            }
+            if !ch.is_whitespace() {
+                empty = false;
+                trailing_comma = false;
+            }
+        }
+
+        // Not closed on this line.
+        false
+    })
+}
+
+/// Primitive Noir types tallied in [`FileMetrics::type_usage`].
+pub const PRIMITIVE_TYPES: &[&str] = &[
+    "Field", "bool", "str", "u1", "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64",
+];
+
+/// Add the primitive type names on a code line to `counts` (see [`FileMetrics::type_usage`]).
+fn count_type_usage(line: &str, counts: &mut BTreeMap<String, usize>) {
+    let code = strip_literals(line);
+
+    for word in code.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        if PRIMITIVE_TYPES.contains(&word) {
+            *counts.entry(word.to_string()).or_insert(0) += 1;
+        }
+    }
+}
+
+/// `global NAME = LITERAL;` declarations of a source file whose value is an integer literal.
+fn literal_globals(source: &str) -> BTreeMap<String, usize> {
+    source
+        .lines()
+        .filter_map(|line| {
+            let code = strip_literals(line);
+            let mut rest = code.trim();
+            for prefix in ["pub ", "comptime "] {
+                rest = rest.strip_prefix(prefix).unwrap_or(rest).trim_start();
+            }
+            let (name, value) = rest.strip_prefix("global ")?.split_once('=')?;
+            let name = name.split(':').next()?.trim();
+            let value = parse_int_literal(value.trim().strip_suffix(';')?.trim())?;
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+/// Names of the modules declared in a source file (`mod name;` or `mod name { ... }`, with any visibility).
+fn declared_modules(source: &str) -> BTreeSet<String> {
+    source
+        .lines()
+        .filter_map(|line| {
+            let code = strip_literals(line);
+            let rest = strip_visibility(code.trim()).strip_prefix("mod ")?;
+            let name: String = rest
+                .trim_start()
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            (!name.is_empty()).then_some(name)
+        })
+        .collect()
+}
+
+/// Where a `use` declaration imports from (see [`FileMetrics::internal_imports`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImportKind {
+    Internal,
+    External,
+}
+
+/// Classify a literal-stripped code line starting a `use` declaration; `None` for any other line.
+fn import_kind(code: &str, modules: &BTreeSet<String>) -> Option<ImportKind> {
+    let path = strip_visibility(code).strip_prefix("use ")?.trim_start();
+    let first = path
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .filter(|segment| !segment.is_empty())?;
+
+    Some(match first {
+        "crate" | "super" | "self" => ImportKind::Internal,
+        name if modules.contains(name) => ImportKind::Internal,
+        _ => ImportKind::External,
+    })
+}
+
+/// `code` without a leading `pub ` or `pub(...) ` visibility.
+fn strip_visibility(code: &str) -> &str {
+    if let Some(rest) = code.strip_prefix("pub ") {
+        return rest.trim_start();
+    }
+    if let Some(rest) = code.strip_prefix("pub(")
+        && let Some((_, rest)) = rest.split_once(')')
+    {
+        return rest.trim_start();
+    }
+    code
+}
+
+/// Sum of the `[T; N]` array lengths on a code line (see [`FileMetrics::array_total_declared_len`]).
+fn array_declared_len(line: &str, globals: &BTreeMap<String, usize>) -> usize {
+    let code = strip_literals(line);
+    // Open `(`/`[` groups, with the byte offset after a `;` directly inside a `[` group.
+    let mut open: Vec<(char, Option<usize>)> = Vec::new();
+    let mut total = 0;
+
+    for (i, ch) in code.char_indices() {
+        match ch {
+            '(' | '[' => open.push((ch, None)),
+            ';' => {
+                if let Some(('[', len_start)) = open.last_mut() {
+                    *len_start = Some(i + 1);
+                }
+            }
+            ')' | ']' => {
+                if let Some(('[', Some(start))) = open.pop()
+                    && ch == ']'
+                {
+                    let len = code[start..i].trim();
+                    total += parse_int_literal(len)
+                        .or_else(|| globals.get(len).copied())
+                        .unwrap_or(0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    total
+}
+
+/// Value of a decimal or `0x` hex integer literal, allowing `_` separators (`1_000`, `0xff`).
+fn parse_int_literal(s: &str) -> Option<usize> {
+    let digits = s.replace('_', "");
+    match digits.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => digits.parse().ok(),
+    }
+}
+
+/// Code text of a line with string literal contents, `//` comments and inline `/* ... */` comments removed.
+fn strip_literals(line: &str) -> String {
+    let mut code = String::new();
+    let mut in_string = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_string {
+            match ch {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => {
+                    in_string = false;
+                    code.push(ch);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => {
+                in_string = true;
+                code.push(ch);
+            }
+            '/' if chars.peek() == Some(&'/') => break,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                code.push(' ');
+            }
+            _ => code.push(ch),
+        }
+    }
+
+    code
+}
+
+/// Count the `pub` parameters of a `main` signature and check whether its return type is `pub`.
+///
+/// `signature` is the signature text from `fn main(` up to (excluding) the body's `{`, possibly joined from
+/// several lines. Parameters are split on commas outside `()`, `<>` and `[]`.
+fn parse_main_visibility(signature: &str) -> (usize, bool) {
+    let Some(start) = signature.find("main(") else {
+        return (0, false);
+    };
+
+    let mut depth = 0usize;
+    let mut params = vec![String::new()];
+    let mut rest = "";
+
+    for (i, ch) in signature[start + "main(".len()..].char_indices() {
+        match ch {
+            '(' | '<' | '[' => depth += 1,
+            ')' if depth == 0 => {
+                rest = &signature[start + "main(".len() + i + 1..];
+                break;
+            }
+            ')' | '>' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                params.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(param) = params.last_mut() {
+            param.push(ch);
+        }
+    }
+
+    let is_pub = |ty: &str| {
+        let ty = ty.trim();
+        ty.strip_prefix("pub")
+            .is_some_and(|after| after.starts_with(char::is_whitespace))
+    };
+
+    let public_inputs = params
+        .iter()
+        .filter_map(|p| p.split_once(':'))
+        .filter(|(_, ty)| is_pub(ty))
+        .count();
+    let public_output = rest.split_once("->").is_some_and(|(_, ty)| is_pub(ty));
+
+    (public_inputs, public_output)
+}
+
+/// Append a line decision to the trace, if one is being collected.
+fn record(trace: &mut Option<&mut Vec<LineTrace>>, line: usize, kind: LineKind, brace_depth: i32) {
+    if let Some(trace) = trace {
+        trace.push(LineTrace {
+            line,
+            kind,
+            brace_depth,
+        });
+    }
+}
+
+/// Check if a trimmed line is a `// noir-metrics: ignore-file` directive.
+fn is_ignore_directive(trimmed: &str) -> bool {
+    trimmed
+        .strip_prefix("//")
+        .map(|rest| rest.trim() == IGNORE_FILE_DIRECTIVE)
+        .unwrap_or(false)
+}
+
+/// Keywords that start a commented-out statement or item.
+const CODE_KEYWORDS: &[&str] = &[
+    "fn ",
+    "pub ",
+    "let ",
+    "use ",
+    "mod ",
+    "struct ",
+    "impl ",
+    "global ",
+    "if ",
+    "for ",
+    "return ",
+    "assert(",
+    "assert_eq(",
+];
+
+/// Heuristic: does a trimmed `//` comment line contain commented-out code?
+///
+/// Doc comments (`///`, `//!`) are never code. Otherwise the comment text counts as code when it ends
+/// with `;`, `{` or `}`, or starts with a Noir keyword such as `fn`, `let` or `assert(`. Prose that
+/// happens to end in a semicolon is a false positive.
+fn looks_like_code(trimmed: &str) -> bool {
+    if trimmed.starts_with("///") || trimmed.starts_with("//!") {
+        return false;
+    }
+
+    let Some(text) = trimmed.strip_prefix("//").map(str::trim) else {
+        return false;
+    };
+
+    text.ends_with(';')
+        || text.ends_with('{')
+        || text.ends_with('}')
+        || CODE_KEYWORDS.iter().any(|k| text.starts_with(k))
+}
+
+/// Count the net number of braces on a line: `{` as +1, `}` as -1.
+fn count_braces(line: &str) -> i32 {
+    let mut delta = 0i32;
+
+    for ch in line.chars() {
+        match ch {
+            '{' => delta += 1,
+            '}' => delta -= 1,
+            _ => {}
+        }
+    }
+
+    delta
+}
+
+/// Find the byte offset of an `unsafe {` block opener (the `unsafe` keyword as a whole word followed by `{`).
+fn find_unsafe_block(line: &str) -> Option<usize> {
+    line.match_indices("unsafe").find_map(|(pos, kw)| {
+        let before = line[..pos].chars().next_back();
+        let after = line[pos + kw.len()..].trim_start();
+
+        let word_start = !before.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        (word_start && after.starts_with('{')).then_some(pos)
+    })
+}
+
+/// Extract the name of a public item declared on a trimmed code line (see [`analyze_file`]).
+fn parse_public_item(trimmed: &str) -> Option<String> {
+    let rest = trimmed.strip_prefix("pub")?;
+    let rest = if let Some(scoped) = rest.strip_prefix('(') {
+        &scoped[scoped.find(')')? + 1..]
+    } else {
+        rest
+    };
+
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let mut words = rest.split_whitespace();
+    let mut keyword = words.next()?;
+    while matches!(keyword, "unconstrained" | "comptime") {
+        keyword = words.next()?;
+    }
+
+    if !matches!(
+        keyword,
+        "fn" | "struct" | "trait" | "global" | "type" | "mod" | "enum"
+    ) {
+        return None;
+    }
+
+    let name: String = words
+        .next()?
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+
+    (!name.is_empty()).then_some(name)
+}
+
+/// Tally every marker in `markers` (`(lowercase, configured)` pairs) that occurs in `s`, ignoring case.
+///
+/// Returns whether any marker was found.
+fn record_todos(
+    s: &str,
+    markers: &[(String, String)],
+    counts: &mut BTreeMap<String, usize>,
+) -> bool {
+    let lower = s.to_lowercase();
+    let mut found = false;
+    for (needle, marker) in markers {
+        if lower.contains(needle.as_str()) {
+            *counts.entry(marker.clone()).or_insert(0) += 1;
+            found = true;
+        }
+    }
+    found
+}
+
+/// Heuristic to decide if a file is a "test file".
+///
+/// Rules:
+/// - If any path component is exactly "tests" or "test" return true.
+/// - If the file name ends with `_test.nr`, return true.
+fn is_test_file(rel_path: &Path) -> bool {
+    if rel_path
+        .components()
+        .any(|c| matches!(c.as_os_str().to_str(), Some("tests" | "test")))
+    {
+        return true;
+    }
+
+    if let Some(file_name) = rel_path.file_name().and_then(|s| s.to_str())
+        && file_name.ends_with("_test.nr")
+    {
+        return true;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn content_hash_is_fnv1a() {
+        assert_eq!(content_hash(b""), "cbf29ce484222325");
+        assert_eq!(content_hash(b"a"), "af63dc4c8601ec8c");
+        assert_ne!(
+            content_hash(b"fn main() {}"),
+            content_hash(b"fn main() { }")
+        );
+    }
+
+    #[test]
+    fn counts_custom_todo_markers() {
+        let source = "// @TODO tidy up\n// todo: default marker\n/* JIRA-123 */\nfn main() {}\n";
+        let path = Path::new("src/main.nr");
+
+        let default = analyze_source(source, path, &AnalysisOptions::default());
+        assert_eq!(default.todo_count, 2);
+        assert_eq!(default.todo_markers.get("todo"), Some(&2));
+
+        let options = AnalysisOptions {
+            todo_markers: vec!["@todo".to_string(), "todo".to_string(), "JIRA-".to_string()],
+            ..Default::default()
+        };
+        let custom = analyze_source(source, path, &options);
+        assert_eq!(custom.todo_count, 3);
+        assert_eq!(custom.todo_markers.get("@todo"), Some(&1));
+        assert_eq!(custom.todo_markers.get("todo"), Some(&2));
+        assert_eq!(custom.todo_markers.get("JIRA-"), Some(&1));
+    }
+
+    #[test]
+    fn counts_code_lines_matching_custom_regex() {
+        let path = Path::new("tests/fixtures/simple_noir/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let options = AnalysisOptions {
+            count_regexes: vec!["asserts=assert".parse().unwrap()],
+            ..Default::default()
+        };
+
+        let mut trace = Vec::new();
+        let metrics = analyze_source_with_trace(&source, path, &options, Some(&mut trace));
+
+        // Same count derived from the built-in line classification.
+        let expected = source
+            .lines()
+            .zip(&trace)
+            .filter(|(_, t)| matches!(t.kind, LineKind::Code | LineKind::Test))
+            .filter(|(text, _)| text.contains("assert"))
+            .count();
+
+        assert_eq!(expected, 3);
+        assert_eq!(metrics.regex_counts.get("asserts"), Some(&expected));
+    }
+
+    #[test]
+    fn counts_functions_with_return_types() {
+        let root = Path::new("tests/fixtures/return_types");
+        let path = root.join("src/main.nr");
+        let metrics = analyze_file(&path, root, &AnalysisOptions::default())
+            .expect("analysis should succeed");
+
+        assert_eq!(metrics.functions, 5);
+        assert_eq!(metrics.functions_with_return, 2);
+    }
+
+    #[test]
+    fn detects_main_public_inputs_and_output() {
+        let path = Path::new("tests/fixtures/main_visibility/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        assert_eq!(metrics.main_public_inputs, Some(1));
+        assert_eq!(metrics.main_public_output, Some(true));
+
+        let helper = analyze_source(
+            "fn helper(x: pub Field) {}\n",
+            path,
+            &AnalysisOptions::default(),
+        );
+        assert_eq!(helper.main_public_inputs, None);
+        assert_eq!(helper.main_public_output, None);
+
+        let nested = analyze_source(
+            "mod inner {\n    fn main(x: pub Field) {}\n}\n\nimpl Foo {\n    fn main() {}\n}\n",
+            path,
+            &AnalysisOptions::default(),
+        );
+        assert!(!nested.has_main);
+        assert_eq!(nested.main_public_inputs, None);
+
+        let after_nested = analyze_source(
+            "mod inner {\n    fn main() {}\n}\n\nfn main(x: pub Field, y: Field) {}\n",
+            path,
+            &AnalysisOptions::default(),
+        );
+        assert!(after_nested.has_main);
+        assert_eq!(after_nested.main_public_inputs, Some(1));
+    }
+
+    #[test]
+    fn parses_main_visibility() {
+        assert_eq!(
+            parse_main_visibility("fn main(x: Field, y: pub Field) -> pub Field "),
+            (1, true)
+        );
+        assert_eq!(
+            parse_main_visibility("fn main(x: pub [Field; 2], y: pub Field) "),
+            (2, false)
+        );
+        assert_eq!(
+            parse_main_visibility("fn main(m: Map<u8, Field>, publisher: Field) -> Field "),
+            (0, false)
+        );
+        assert_eq!(
+            parse_main_visibility("pub fn main() -> pub (Field, Field) "),
+            (0, true)
+        );
+    }
+
+    #[test]
+    fn scan_signature_ignores_strings_and_comments() {
+        assert_eq!(scan_signature("fn f(x: Field) -> Field {"), (true, true));
+        assert_eq!(scan_signature("fn f(x: Field) { x -> y"), (false, true));
+        assert_eq!(scan_signature("fn f(s: str<2>) // -> none"), (false, false));
+        assert_eq!(scan_signature("    -> Field"), (true, false));
+        assert_eq!(scan_signature("fn f() -> Field;"), (true, true));
+    }
+
+    #[test]
+    fn counts_struct_derives() {
+        let root = Path::new("tests/fixtures/derives");
+        let path = root.join("src/main.nr");
+        let options = AnalysisOptions {
+            list_derives: true,
+            ..Default::default()
+        };
+        let metrics = analyze_file(&path, root, &options).expect("analysis should succeed");
+
+        assert_eq!(metrics.derive_count, 3);
+        assert_eq!(
+            metrics.derives,
+            BTreeMap::from([
+                ("Default".to_string(), 1),
+                ("Eq".to_string(), 2),
+                ("Hash".to_string(), 1),
+            ])
+        );
+
+        let plain = analyze_file(&path, root, &AnalysisOptions::default()).unwrap();
+        assert_eq!(plain.derive_count, 3);
+        assert!(plain.derives.is_empty());
+    }
+
+    #[test]
+    fn trace_records_line_classification() {
+        let path = Path::new("tests/fixtures/simple_noir/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+
+        let mut trace = Vec::new();
+        let metrics =
+            analyze_source_with_trace(&source, path, &AnalysisOptions::default(), Some(&mut trace));
+
+        assert_eq!(trace.len(), metrics.total_lines);
+        assert_eq!(
+            trace[2],
+            LineTrace {
+                line: 3,
+                kind: LineKind::Comment,
+                brace_depth: 1,
+            },
+            "`// test comment` inside main"
+        );
+        assert_eq!(trace[5].kind, LineKind::Blank);
+        assert_eq!(
+            trace[8].kind,
+            LineKind::Test,
+            "`main(1, 2);` inside test_main"
+        );
+        assert_eq!(trace[4].brace_depth, 0, "main's closing brace");
+    }
+
+    #[test]
+    fn counts_lines_and_tests_exactly() {
+        let project_root = PathBuf::from("tests/fixtures/file_metrics");
+        let path = project_root.join("src/metrics.nr");
+
+        let metrics = analyze_file(&path, &project_root, &AnalysisOptions::default())
+            .expect("analyze_file should succeed");
+
+        assert_eq!(
+            metrics.code_lines,
+            metrics.test_lines + metrics.non_test_lines,
+            "code_lines should equal test_lines + non_test_lines",
+        );
+
+        let v = serde_json::to_value(&metrics).expect("FileMetrics should serialize");
+        insta::assert_json_snapshot!(v);
+    }
+
+    #[test]
+    fn detects_tab_indentation() {
+        let project_root = PathBuf::from("tests/fixtures/tab_indent");
+        let path = project_root.join("src/main.nr");
+
+        let metrics = analyze_file(&path, &project_root, &AnalysisOptions::default())
+            .expect("analyze_file should succeed");
+
+        assert_eq!(metrics.indent_style, IndentStyle::Tabs);
+
+        let v = serde_json::to_value(&metrics).expect("FileMetrics should serialize");
+        assert_eq!(v["indent_style"], "tabs");
+    }
+
+    #[test]
+    fn counts_contract_visibility_attributes() {
+        let project_root = PathBuf::from("tests/fixtures/contract");
+        let path = project_root.join("src/main.nr");
+
+        let metrics = analyze_file(&path, &project_root, &AnalysisOptions::default())
+            .expect("analyze_file should succeed");
+
+        assert_eq!(metrics.contract_modules, 1);
+        assert_eq!(metrics.public_functions, 2);
+        assert_eq!(metrics.private_functions, 1);
+        assert_eq!(metrics.functions, 4);
+        assert_eq!(metrics.pub_functions, 1);
+
+        // Restricted visibility on the contract module; its attribute must not leak onto `mint`.
+        let source = "#[contract]\npub(crate) mod token {\n    #[public]\n    fn mint() {}\n}\n";
+        let metrics = analyze_source(
+            source,
+            Path::new("src/main.nr"),
+            &AnalysisOptions::default(),
+        );
+        assert_eq!(metrics.contract_modules, 1);
+        assert_eq!(metrics.function_metrics[0].attributes, ["public"]);
+    }
+
+    #[test]
+    fn counts_unsafe_blocks_and_lines() {
+        let project_root = PathBuf::from("tests/fixtures/unsafe_block");
+        let path = project_root.join("src/main.nr");
+
+        let metrics = analyze_file(&path, &project_root, &AnalysisOptions::default())
+            .expect("analyze_file should succeed");
+
+        assert_eq!(metrics.unsafe_blocks, 2);
+        assert_eq!(metrics.unsafe_lines, 4);
+    }
+
+    #[test]
+    fn find_unsafe_block_requires_keyword_and_brace() {
+        assert_eq!(find_unsafe_block("let x = unsafe { f() };"), Some(8));
+        assert_eq!(find_unsafe_block("unsafe {"), Some(0));
+        assert_eq!(find_unsafe_block("unsafe fn f() {"), None);
+        assert_eq!(find_unsafe_block("let not_unsafe {"), None);
+    }
+
+    #[test]
+    fn lists_public_item_names() {
+        let project_root = PathBuf::from("tests/fixtures/public_items");
+        let path = project_root.join("src/lib.nr");
+        let options = AnalysisOptions {
+            list_public: true,
+            ..Default::default()
+        };
+
+        let metrics =
+            analyze_file(&path, &project_root, &options).expect("analyze_file should succeed");
+
+        assert_eq!(
+            metrics.public_items,
+            vec![
+                "Point",
+                "Shape",
+                "MAX_POINTS",
+                "origin",
+                "distance",
+                "area",
+                "hash"
+            ]
+        );
+
+        let default = analyze_file(&path, &project_root, &AnalysisOptions::default())
+            .expect("analyze_file should succeed");
+        assert!(default.public_items.is_empty());
+    }
+
+    #[test]
+    fn is_test_file_detects_tests_dir() {
+        assert!(is_test_file(Path::new("tests/main.nr")));
+        assert!(is_test_file(Path::new("src/tests/main.nr")));
+        assert!(is_test_file(Path::new("src/test/main.nr")));
+    }
+
+    #[test]
+    fn is_test_file_detects_suffix() {
+        assert!(is_test_file(Path::new("src/foo_test.nr")));
+    }
+
+    #[test]
+    fn is_test_file_false_for_regular_files() {
+        assert!(!is_test_file(Path::new("src/main.nr")));
+        assert!(!is_test_file(Path::new("src/lib.nr")));
+    }
+
+    #[test]
+    fn commented_out_code_is_told_apart_from_prose() {
+        let path = Path::new("tests/fixtures/commented_code/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        assert_eq!(metrics.comment_lines, 8);
+        assert_eq!(metrics.commented_code_lines, 4);
+    }
+
+    #[test]
+    fn inner_and_outer_doc_lines_are_counted_separately() {
+        let path = Path::new("tests/fixtures/doc_comments/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        assert_eq!(metrics.inner_doc_lines, 2);
+        assert_eq!(metrics.outer_doc_lines, 4);
+        assert_eq!(metrics.doc_comment_lines, 6);
+        assert_eq!(metrics.comment_lines, 8);
+    }
+
+    #[test]
+    fn functions_are_detected_after_any_modifier_run() {
+        let path = Path::new("tests/fixtures/fn_modifiers/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        assert_eq!(metrics.functions, 9);
+        assert_eq!(metrics.pub_functions, 4);
+        assert!(metrics.has_main);
+
+        let flags: Vec<(&str, bool, bool, bool)> = metrics
+            .function_metrics
+            .iter()
+            .map(|f| (f.name.as_str(), f.is_pub, f.is_unconstrained, f.is_comptime))
+            .collect();
+        assert_eq!(
+            flags,
+            [
+                ("plain", false, false, false),
+                ("public", true, false, false),
+                ("crate_visible", false, false, false),
+                ("helper", false, true, false),
+                ("odd_order", true, true, false),
+                ("usual_order", true, true, false),
+                ("at_compile_time", false, false, true),
+                ("public_comptime", true, false, true),
+                ("main", false, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn comment_lines_split_into_line_and_block_styles() {
+        let path = Path::new("tests/fixtures/comment_styles/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        assert_eq!(metrics.line_comment_lines, 4);
+        assert_eq!(metrics.block_comment_lines, 6);
+        assert_eq!(
+            metrics.comment_lines,
+            metrics.line_comment_lines + metrics.block_comment_lines
+        );
+    }
+
+    #[test]
+    fn type_usage_tallies_primitive_types_when_enabled() {
+        let path = Path::new("tests/fixtures/type_usage/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+        assert!(metrics.type_usage.is_empty());
+
+        let options = AnalysisOptions {
+            type_usage: true,
+            ..Default::default()
+        };
+        let metrics = analyze_source(&source, path, &options);
+        assert_eq!(
+            metrics.type_usage,
+            BTreeMap::from(
+                [
+                    ("Field", 5),
+                    ("bool", 1),
+                    ("str", 1),
+                    ("u1", 1),
+                    ("u32", 2),
+                    ("u64", 2),
+                    ("i8", 1)
+                ]
+                .map(|(name, count)| (name.to_string(), count))
+            )
+        );
+    }
+
+    #[test]
+    fn statements_are_counted_outside_strings_and_groups() {
+        let path = Path::new("tests/fixtures/statements/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        // global, three on one line, label, values, assert_eq, assert in the loop, `let _`.
+        assert_eq!(metrics.statement_count, 9);
+    }
+
+    #[test]
+    fn where_clauses_are_counted_per_signature() {
+        let path = Path::new("tests/fixtures/where_clauses/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        // `sum` (multi-line), the `impl` and `Eq::eq`; not `plain`, whose comment and string mention `where`.
+        assert_eq!(metrics.where_clause_count, 3);
+    }
+
+    #[test]
+    fn stacked_attributes_classify_a_function_in_each_category() {
+        let path = Path::new("tests/fixtures/stacked_attrs/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        assert_eq!(metrics.test_functions, 2);
+        assert_eq!(metrics.public_functions, 1);
+
+        let attributes = |name: &str| {
+            let f = metrics
+                .function_metrics
+                .iter()
+                .find(|f| f.name == name)
+                .expect("function should be listed");
+            (f.is_test, f.should_fail, f.attributes.clone())
+        };
+        assert_eq!(attributes("main"), (false, false, vec!["recursive".into()]));
+        assert_eq!(
+            attributes("test_recursive_double"),
+            (true, false, vec!["test".into(), "recursive".into()])
+        );
+        assert_eq!(
+            attributes("test_public_failure"),
+            (
+                true,
+                true,
+                vec!["test(should_fail)".into(), "public".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn parse_attributes_reads_stacked_attributes() {
+        assert_eq!(
+            parse_attributes("#[test] #[recursive]"),
+            ["test", "recursive"]
+        );
+        assert_eq!(
+            parse_attributes(r#"#[test(should_fail_with = "a[0]")] // note"#),
+            [r#"test(should_fail_with = "a[0]")"#]
+        );
+        assert!(parse_attributes("fn main() {").is_empty());
+    }
+
+    #[test]
+    fn debug_prints_skip_comments_strings_and_longer_names() {
+        let path = Path::new("tests/fixtures/debug_prints/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        // `std::println`, `print` and `println` on the same line; not the comment, string or `my_print`.
+        assert_eq!(metrics.debug_print_count, 3);
+    }
+
+    #[test]
+    fn line_endings_are_classified_from_raw_bytes() {
+        let project_root = PathBuf::from("tests/fixtures/line_endings");
+
+        for (file, expected) in [
+            ("src/main.nr", LineEnding::Lf),
+            ("src/crlf.nr", LineEnding::Crlf),
+            ("src/mixed.nr", LineEnding::Mixed),
+        ] {
+            let metrics = analyze_file(
+                &project_root.join(file),
+                &project_root,
+                &AnalysisOptions::default(),
+            )
+            .expect("analyze_file should succeed");
+            assert_eq!(metrics.line_ending, expected, "{file}");
+            assert_eq!(metrics.code_lines, 3, "{file}");
+        }
+
+        assert_eq!(detect_line_ending(b"fn main() {}"), LineEnding::None);
+    }
+
+    #[test]
+    fn mut_bindings_skip_references_literals_and_identifiers() {
+        let path = Path::new("tests/fixtures/mut_bindings/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        // `mut acc` parameter, `let mut total`, `let mut i`; not `&mut`, `mutable`, comments or strings.
+        assert_eq!(metrics.mut_binding_count, 3);
+    }
+
+    #[test]
+    fn array_lengths_resolve_same_file_literal_globals() {
+        let path = Path::new("tests/fixtures/array_lengths/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        // `N` (8) twice, `SIZE` (16) twice, the literal `4` twice; the computed `DOUBLE` counts 0.
+        assert_eq!(metrics.array_total_declared_len, 56);
+        assert_eq!(
+            array_declared_len("let g: [[Field; 2]; 3] = [[0; 2]; 3];", &BTreeMap::new()),
+            10
+        );
+    }
+
+    #[test]
+    fn lint_asserts_flags_assert_eq_with_wrong_arity() {
+        let path = Path::new("tests/fixtures/suspicious_asserts/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+        assert!(metrics.suspicious_asserts.is_empty());
+
+        let options = AnalysisOptions {
+            lint_asserts: true,
+            ..Default::default()
+        };
+        let metrics = analyze_source(&source, path, &options);
+        let lines: Vec<usize> = metrics.suspicious_asserts.iter().map(|s| s.line).collect();
+        assert_eq!(lines, [8, 9]);
+        assert_eq!(metrics.suspicious_asserts[0].snippet, "assert_eq(x == y);");
+
+        let root = Path::new("tests/fixtures/suspicious_asserts");
+        let markdown = analyze_markdown_file(&root.join("docs/lint.md"), root, &options)
+            .expect("fixture should be readable")
+            .expect("fixture has a noir fence");
+        let lines: Vec<usize> = markdown.suspicious_asserts.iter().map(|s| s.line).collect();
+        assert_eq!(lines, [2], "lines are relative to the fence");
+    }
+
+    #[test]
+    fn assert_dominated_functions_are_flagged_as_spec() {
+        let path = Path::new("tests/fixtures/spec_functions/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+        let spec: Vec<&str> = metrics
+            .function_metrics
+            .iter()
+            .filter(|f| f.is_spec)
+            .map(|f| f.name.as_str())
+            .collect();
+        // `helper` and `main` assert too, but not in most statements; tests are never spec.
+        assert_eq!(spec, ["check_ordering"]);
+        assert_eq!(metrics.spec_functions, 1);
+
+        let check = &metrics.function_metrics[1];
+        assert_eq!((check.assert_count, check.statement_count), (4, 4));
+    }
+
+    #[test]
+    fn inline_module_code_lines_are_attributed_to_the_module() {
+        let path = Path::new("tests/fixtures/inline_modules/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        assert_eq!(metrics.inline_modules, 2);
+        assert_eq!(
+            metrics.by_module,
+            BTreeMap::from([("math".to_string(), 10)])
+        );
+        assert_eq!(metrics.code_lines, 14);
+    }
+
+    #[test]
+    fn long_lines_are_counted_in_characters() {
+        let path = Path::new("tests/fixtures/long_lines/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+        // The long line ends in `é`, which is two bytes but one character.
+        assert_eq!(metrics.max_line_length, 108);
+        assert_eq!(metrics.long_line_count, 1);
+
+        let options = AnalysisOptions {
+            max_line_length: Some(108),
+            ..Default::default()
+        };
+        let strict = analyze_source(&source, path, &options);
+        assert_eq!(
+            strict.long_line_count, 0,
+            "a line exactly at the limit is fine"
+        );
+    }
+
+    #[test]
+    fn counts_leading_and_trailing_blank_lines() {
+        let path = Path::new("tests/fixtures/edge_blanks/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+        let metrics = analyze_source(&source, path, &AnalysisOptions::default());
+
+        assert_eq!(metrics.leading_blank_lines, 2);
+        assert_eq!(metrics.trailing_blank_lines, 3);
+        assert_eq!(metrics.blank_lines, 6, "inner blank line is not at an edge");
+
+        let all_blank = analyze_source("\n  \n\t\n", path, &AnalysisOptions::default());
+        assert_eq!(all_blank.leading_blank_lines, 3);
+        assert_eq!(all_blank.trailing_blank_lines, 0);
+    }
+
+    #[test]
+    fn fast_preset_skips_complexity() {
+        use crate::analysis::options::AnalysisPreset;
+
+        let path = Path::new("tests/fixtures/function_spans/src/main.nr");
+        let source = fs::read_to_string(path).expect("fixture should exist");
+
+        let fast = analyze_source(
+            &source,
+            path,
+            &AnalysisOptions::preset(AnalysisPreset::Fast),
+        );
+        let full = analyze_source(
+            &source,
+            path,
+            &AnalysisOptions::preset(AnalysisPreset::Full),
+        );
+
+        assert_eq!(fast.complexity, 0);
+        assert_eq!(fast.max_function_complexity, 0);
+        assert!(fast.function_metrics.iter().all(|f| f.complexity == 0));
+        assert!(full.complexity >= full.functions && full.functions > 0);
+        assert_eq!(fast.code_lines, full.code_lines);
+        assert_eq!(fast.functions, full.functions);
+    }
+
+    #[test]
+    fn is_ignore_directive_requires_line_comment() {
+        assert!(is_ignore_directive("// noir-metrics: ignore-file"));
+        assert!(is_ignore_directive("//noir-metrics: ignore-file"));
+        assert!(!is_ignore_directive("noir-metrics: ignore-file"));
+        assert!(!is_ignore_directive("// noir-metrics: ignore-file please"));
+    }
+
+    #[test]
+    fn count_statements_skips_literals_comments_and_groups() {
+        let mut depth = 0;
+        assert_eq!(count_statements("let a = 1; let b = 2;", &mut depth), 2);
+        assert_eq!(count_statements(r#"let s = "a;b"; // c; d"#, &mut depth), 1);
+        assert_eq!(
+            count_statements("let c = ';'; /* ; */ x += 1;", &mut depth),
+            2
+        );
+        assert_eq!(
+            count_statements("let a: [Field; 3] = [0; 3];", &mut depth),
+            1
+        );
+        assert_eq!(depth, 0);
+
+        assert_eq!(count_statements("foo(a; b,", &mut depth), 0);
+        assert_eq!(depth, 1);
+        assert_eq!(count_statements("c);", &mut depth), 1);
+        assert_eq!(depth, 0);
     }
 
     #[test]
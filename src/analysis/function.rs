@@ -0,0 +1,216 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Metrics computed for a single function within a `.nr` file.
+///
+/// Spans are derived from the same brace-depth heuristic used for test attribution: a function starts at
+/// its `fn` line and ends on the line where its body braces balance again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct FunctionMetrics {
+    /// Function name as written after `fn`.
+    pub name: String,
+
+    /// 1-based line number of the `fn` signature line.
+    pub start_line: usize,
+
+    /// 1-based line number where the function body closes.
+    pub end_line: usize,
+
+    /// Number of code lines within the function span.
+    pub code_lines: usize,
+
+    /// Approximate cyclomatic complexity: 1 plus the number of decision points
+    /// (see [`count_decision_points`]) on the function's code lines.
+    pub complexity: usize,
+
+    /// Is this function annotated with `#[test...]`?
+    pub is_test: bool,
+
+    /// Does the signature carry a plain `pub` visibility? Restricted visibilities such as `pub(crate)` do not
+    /// count.
+    pub is_pub: bool,
+
+    /// Is this an `unconstrained fn`?
+    pub is_unconstrained: bool,
+
+    /// Is this a `comptime fn`?
+    pub is_comptime: bool,
+
+    /// Number of top-level statements (`;`) within the function span (see [`FileMetrics::statement_count`]).
+    ///
+    /// [`FileMetrics::statement_count`]: crate::analysis::file::FileMetrics::statement_count
+    pub statement_count: usize,
+
+    /// Number of `assert(...)` / `assert_eq(...)` calls within the function span.
+    pub assert_count: usize,
+
+    /// Is this a non-test function whose statements are more than 80% asserts (a "spec" function)?
+    pub is_spec: bool,
+
+    /// Is this a `#[test(should_fail)]` / `#[test(should_fail_with = "...")]` test?
+    pub should_fail: bool,
+
+    /// Attributes preceding the function, as written inside `#[...]` (e.g. `test`, `recursive`, `oracle(f)`).
+    ///
+    /// A function carries all of its stacked attributes, so it can be e.g. both a test and `#[recursive]`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+}
+
+impl FunctionMetrics {
+    /// Does this non-test function consist of more than 80% assert statements?
+    pub(crate) fn looks_like_spec(&self) -> bool {
+        !self.is_test && self.assert_count > 0 && self.assert_count * 5 > self.statement_count * 4
+    }
+
+    /// Number of lines in the span `start_line..=end_line`.
+    pub fn lines(&self) -> usize {
+        self.end_line - self.start_line + 1
+    }
+}
+
+/// Modifiers in front of `fn` on a function signature line (see [`parse_fn_line`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct FnModifiers {
+    /// Plain `pub`; restricted `pub(...)` visibilities are accepted but leave this unset.
+    pub is_pub: bool,
+    /// `unconstrained`.
+    pub is_unconstrained: bool,
+    /// `comptime`.
+    pub is_comptime: bool,
+}
+
+/// Recognize a trimmed function signature line: a run of the modifiers `pub`, `pub(...)`, `unconstrained`
+/// and `comptime`, in any order, followed by `fn` and whitespace (e.g. `unconstrained pub fn f(`).
+///
+/// Returns `None` for any other line.
+pub(crate) fn parse_fn_line(trimmed: &str) -> Option<FnModifiers> {
+    let mut modifiers = FnModifiers::default();
+    let mut rest = trimmed;
+
+    loop {
+        let word_end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (word, after) = rest.split_at(word_end);
+
+        let after = match word {
+            "pub" if after.starts_with('(') => &after[after.find(')')? + 1..],
+            "pub" => {
+                modifiers.is_pub = true;
+                after
+            }
+            "unconstrained" => {
+                modifiers.is_unconstrained = true;
+                after
+            }
+            "comptime" => {
+                modifiers.is_comptime = true;
+                after
+            }
+            "fn" => return after.starts_with(char::is_whitespace).then_some(modifiers),
+            _ => return None,
+        };
+
+        if !after.starts_with(char::is_whitespace) {
+            return None;
+        }
+        rest = after.trim_start();
+    }
+}
+
+/// Extract the function name from a trimmed `fn`/`pub fn` line.
+///
+/// The name ends at the first character that is not part of an identifier (e.g. `(` or `<`).
+pub(crate) fn parse_fn_name(trimmed: &str) -> String {
+    let Some(idx) = trimmed.find("fn ") else {
+        return String::new();
+    };
+
+    trimmed[idx + 3..]
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect()
+}
+
+/// Count decision points on a code line.
+///
+/// Decision points are the keywords `if`, `for`, `while`, `loop`, `match` (as whole words) and the
+/// short-circuit operators `&&` and `||`. This is a lexical heuristic: keywords inside string literals are
+/// counted too.
+pub(crate) fn count_decision_points(line: &str) -> usize {
+    let keywords = line
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| matches!(*word, "if" | "for" | "while" | "loop" | "match"))
+        .count();
+
+    keywords + line.matches("&&").count() + line.matches("||").count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fn_name_handles_generics_and_visibility() {
+        assert_eq!(parse_fn_name("fn main(x: Field) {"), "main");
+        assert_eq!(parse_fn_name("pub fn helper<T>(x: T) -> T {"), "helper");
+        assert_eq!(parse_fn_name("fn spaced  (x: Field)"), "spaced");
+    }
+
+    #[test]
+    fn parse_fn_line_accepts_modifier_runs() {
+        let modifiers = |is_pub, is_unconstrained, is_comptime| {
+            Some(FnModifiers {
+                is_pub,
+                is_unconstrained,
+                is_comptime,
+            })
+        };
+
+        assert_eq!(parse_fn_line("fn f() {"), modifiers(false, false, false));
+        assert_eq!(parse_fn_line("pub fn f() {"), modifiers(true, false, false));
+        assert_eq!(
+            parse_fn_line("pub(crate) fn f() {"),
+            modifiers(false, false, false)
+        );
+        assert_eq!(
+            parse_fn_line("unconstrained pub fn f() {"),
+            modifiers(true, true, false)
+        );
+        assert_eq!(
+            parse_fn_line("pub unconstrained fn f() {"),
+            modifiers(true, true, false)
+        );
+        assert_eq!(
+            parse_fn_line("comptime fn f() {"),
+            modifiers(false, false, true)
+        );
+
+        assert_eq!(parse_fn_line("fnord(x);"), None);
+        assert_eq!(parse_fn_line("pub struct Point {"), None);
+        assert_eq!(parse_fn_line("let unconstrained = fn_ptr;"), None);
+        assert_eq!(parse_fn_line("pub(crate)fn f() {"), None);
+    }
+
+    #[test]
+    fn count_decision_points_counts_keywords_and_operators() {
+        assert_eq!(count_decision_points("let x = 1;"), 0);
+        assert_eq!(count_decision_points("if a && b {"), 2);
+        assert_eq!(count_decision_points("} else if c || d {"), 2);
+        assert_eq!(count_decision_points("for i in 0..10 {"), 1);
+        assert_eq!(count_decision_points("let iffy = format;"), 0);
+    }
+
+    #[test]
+    fn lines_is_inclusive() {
+        let f = FunctionMetrics {
+            start_line: 3,
+            end_line: 5,
+            ..Default::default()
+        };
+        assert_eq!(f.lines(), 3);
+    }
+}
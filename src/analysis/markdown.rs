@@ -0,0 +1,74 @@
+/// Extract the contents of fenced code blocks tagged `noir` or `nr` from Markdown text.
+///
+/// Both backtick (```` ``` ````) and tilde (`~~~`) fences are recognized; the first word of the info
+/// string selects the language. A fence is closed by a line made of the same fence character, at least
+/// as long as the opening fence. An unclosed fence runs to the end of the document.
+pub fn extract_noir_fences(markdown: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(char, usize, bool)> = None;
+    let mut current = String::new();
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+
+        match open {
+            None => {
+                if let Some((ch, len)) = fence_marker(trimmed) {
+                    let info = trimmed[len..].trim();
+                    let lang = info.split_whitespace().next().unwrap_or("");
+                    open = Some((ch, len, matches!(lang, "noir" | "nr")));
+                }
+            }
+            Some((ch, len, is_noir)) => {
+                let closes = fence_marker(trimmed)
+                    .is_some_and(|(c, l)| c == ch && l >= len && trimmed[l..].trim().is_empty());
+
+                if closes {
+                    if is_noir {
+                        blocks.push(std::mem::take(&mut current));
+                    }
+                    open = None;
+                } else if is_noir {
+                    current.push_str(line);
+                    current.push('\n');
+                }
+            }
+        }
+    }
+
+    if let Some((_, _, true)) = open {
+        blocks.push(current);
+    }
+
+    blocks
+}
+
+/// Return the fence character and run length if `trimmed` starts with a code fence (3+ `` ` `` or `~`).
+fn fence_marker(trimmed: &str) -> Option<(char, usize)> {
+    let ch = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == ch).count();
+    (len >= 3).then_some((ch, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_only_noir_fences() {
+        let md = "# Title\n\n```noir\nfn main() {}\n```\n\n```rust\nfn other() {}\n```\n\n~~~nr title\nfn two() {}\n~~~\n";
+        assert_eq!(
+            extract_noir_fences(md),
+            vec!["fn main() {}\n".to_string(), "fn two() {}\n".to_string()]
+        );
+    }
+
+    #[test]
+    fn closing_fence_must_match_opening() {
+        let md = "````noir\n```\nstill inside\n````\n";
+        assert_eq!(
+            extract_noir_fences(md),
+            vec!["```\nstill inside\n".to_string()]
+        );
+    }
+}
@@ -1,2 +1,5 @@
 pub mod file;
+pub mod function;
+pub mod markdown;
+pub mod options;
 pub mod project;
@@ -0,0 +1,217 @@
+use crate::project::WalkOptions;
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use regex::Regex;
+use std::str::FromStr;
+
+/// Options controlling a project analysis.
+///
+/// The default discovers every `.nr` file under the project's source directory recursively (the whole root
+/// with [`WalkOptions::all`]) and computes the default metric set; opt-in metrics are enabled through the
+/// remaining fields.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisOptions {
+    /// File discovery options applied to the [`Project`](crate::NoirProject).
+    pub walk: WalkOptions,
+
+    /// Collect the names of public items per file ([`FileMetrics::public_items`](crate::FileMetrics::public_items)).
+    pub list_public: bool,
+
+    /// Tally the traits derived on structs ([`FileMetrics::derives`](crate::FileMetrics::derives)).
+    pub list_derives: bool,
+
+    /// Tally primitive type names on code lines ([`FileMetrics::type_usage`](crate::FileMetrics::type_usage)).
+    pub type_usage: bool,
+
+    /// Also analyze Noir code embedded in ```` ```noir ```` / ```` ```nr ```` fences of `.md` files.
+    ///
+    /// Embedded files are marked with [`FileMetrics::is_embedded`](crate::FileMetrics::is_embedded).
+    pub scan_markdown: bool,
+
+    /// Record a hash of each file's contents ([`FileMetrics::content_hash`](crate::FileMetrics::content_hash)).
+    pub content_hash: bool,
+
+    /// Custom line-count metrics ([`FileMetrics::regex_counts`](crate::FileMetrics::regex_counts)).
+    pub count_regexes: Vec<CountRegex>,
+
+    /// Words counted as TODO markers, matched case-insensitively anywhere in a comment line.
+    ///
+    /// Empty means [`DEFAULT_TODO_MARKERS`].
+    pub todo_markers: Vec<String>,
+
+    /// Lines longer than this many characters count towards
+    /// [`FileMetrics::long_line_count`](crate::FileMetrics::long_line_count).
+    ///
+    /// `None` means [`DEFAULT_MAX_LINE_LENGTH`].
+    pub max_line_length: Option<usize>,
+
+    /// Flag single-line `assert_eq(...)` calls with an unexpected argument count
+    /// ([`FileMetrics::suspicious_asserts`](crate::FileMetrics::suspicious_asserts)).
+    pub lint_asserts: bool,
+
+    /// Skip the per-function decision-point scan; every complexity metric is reported as 0.
+    pub skip_complexity: bool,
+
+    /// Analyze `.nr` files on this many threads: `Some(0)` uses one per available CPU, `None` and `Some(1)`
+    /// analyze sequentially.
+    ///
+    /// Files are still reported in path order, so the result does not depend on the thread count.
+    pub parallel: Option<usize>,
+}
+
+impl AnalysisOptions {
+    /// Number of threads to analyze `files` files on (at least 1, never more than `files`).
+    pub(crate) fn thread_count(&self, files: usize) -> usize {
+        let threads = match self.parallel {
+            None => 1,
+            Some(0) => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            Some(n) => n,
+        };
+        threads.min(files).max(1)
+    }
+}
+
+/// Named starting points for [`AnalysisOptions`] (`--profile`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AnalysisPreset {
+    /// Core line and function counts only; complexity is skipped.
+    Fast,
+    /// Every general-purpose metric: public items, derive histogram, type usage, `assert_eq` arity lint and
+    /// content hashes.
+    Full,
+    /// Security-relevant counts: public items plus `asserts`, `unconstrained` and `oracles` regex counts
+    /// (unsafe metrics are always computed).
+    Audit,
+}
+
+/// Line length threshold used when [`AnalysisOptions::max_line_length`] is unset.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 100;
+
+/// TODO markers used when [`AnalysisOptions::todo_markers`] is empty.
+pub const DEFAULT_TODO_MARKERS: &[&str] = &["todo", "fixme"];
+
+impl AnalysisOptions {
+    /// Options for a [`AnalysisPreset`]; individual fields can still be adjusted afterwards.
+    pub fn preset(preset: AnalysisPreset) -> Self {
+        match preset {
+            AnalysisPreset::Fast => AnalysisOptions {
+                skip_complexity: true,
+                ..Default::default()
+            },
+            AnalysisPreset::Full => AnalysisOptions {
+                list_public: true,
+                list_derives: true,
+                type_usage: true,
+                lint_asserts: true,
+                content_hash: true,
+                ..Default::default()
+            },
+            AnalysisPreset::Audit => AnalysisOptions {
+                list_public: true,
+                count_regexes: [
+                    r"asserts=\bassert(_eq)?\s*\(",
+                    r"unconstrained=\bunconstrained\b",
+                    r"oracles=#\[oracle\b",
+                ]
+                .iter()
+                .map(|spec| spec.parse().expect("built-in pattern is valid"))
+                .collect(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The configured TODO markers, falling back to [`DEFAULT_TODO_MARKERS`].
+    pub fn todo_markers(&self) -> Vec<String> {
+        if self.todo_markers.is_empty() {
+            DEFAULT_TODO_MARKERS.iter().map(|m| m.to_string()).collect()
+        } else {
+            self.todo_markers.clone()
+        }
+    }
+}
+
+/// A named regular expression whose matching code lines are counted per file (`--count-regex`).
+#[derive(Debug, Clone)]
+pub struct CountRegex {
+    /// Key used in `regex_counts`.
+    pub name: String,
+
+    /// Pattern matched against each code line (unanchored).
+    pub pattern: Regex,
+}
+
+impl FromStr for CountRegex {
+    type Err = anyhow::Error;
+
+    /// Parse `<name>=<pattern>`; the pattern may itself contain `=`.
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((name, pattern)) = s.split_once('=') else {
+            bail!("expected <name>=<pattern>, got `{s}`");
+        };
+        if name.is_empty() {
+            bail!("missing name in `{s}`");
+        }
+
+        let pattern =
+            Regex::new(pattern).with_context(|| format!("invalid pattern for `{name}`"))?;
+
+        Ok(CountRegex {
+            name: name.to_string(),
+            pattern,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_count_regex() {
+        let c: CountRegex = "asserts=^\\s*assert(_eq)?\\(".parse().unwrap();
+        assert_eq!(c.name, "asserts");
+        assert!(c.pattern.is_match("    assert_eq(x, y);"));
+
+        assert!("no-equals".parse::<CountRegex>().is_err());
+        assert!("=x".parse::<CountRegex>().is_err());
+        assert!("bad=(".parse::<CountRegex>().is_err());
+    }
+
+    #[test]
+    fn presets_toggle_metrics() {
+        let fast = AnalysisOptions::preset(AnalysisPreset::Fast);
+        assert!(fast.skip_complexity && !fast.list_public);
+
+        let full = AnalysisOptions::preset(AnalysisPreset::Full);
+        assert!(!full.skip_complexity && full.list_public && full.list_derives);
+        assert!(full.type_usage && full.lint_asserts && full.content_hash);
+
+        let audit = AnalysisOptions::preset(AnalysisPreset::Audit);
+        let names: Vec<&str> = audit
+            .count_regexes
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["asserts", "unconstrained", "oracles"]);
+        assert!(
+            audit.count_regexes[0]
+                .pattern
+                .is_match("    assert_eq(a, b);")
+        );
+        assert!(
+            audit.count_regexes[2]
+                .pattern
+                .is_match("#[oracle(get_secret)]")
+        );
+    }
+
+    #[test]
+    fn todo_markers_default_when_unset() {
+        let mut options = AnalysisOptions::default();
+        assert_eq!(options.todo_markers(), vec!["todo", "fixme"]);
+
+        options.todo_markers = vec!["@todo".to_string()];
+        assert_eq!(options.todo_markers(), vec!["@todo"]);
+    }
+}
@@ -1,56 +1,315 @@
-use crate::analysis::file::{FileMetrics, analyze_file};
+use crate::analysis::file::{
+    FileMetrics, IndentStyle, LineEnding, analyze_contents, analyze_file, analyze_markdown_file,
+};
+use crate::analysis::options::AnalysisOptions;
+use crate::budget::BudgetViolation;
+use crate::diff::FileDiff;
+use crate::profile::ProfileResult;
 use crate::project::Project;
-use anyhow::Result;
-use serde::Serialize;
-use std::path::PathBuf;
+use anyhow::{Context, Result, anyhow, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Condvar, Mutex, mpsc};
 
 /// Aggregated metrics for a whole Noir project.
 ///
 /// These totals are derived by summing per-file [`FileMetrics`] values and computing
 /// derived fields such as [`ProjectTotals::test_code_percentage`].
-#[derive(Debug, Clone, Serialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(default)]
 pub struct ProjectTotals {
-    /// Number of `.nr` files in the project.
-    pub files: usize,
+    /// Number of `.nr` files in the project that contribute to totals (excludes ignored files).
+    pub files: u64,
+
+    /// Number of `.nr` files excluded from totals via a `// noir-metrics: ignore-file` directive.
+    pub ignored_files: u64,
+
+    /// Number of Markdown files whose embedded Noir fences contribute to totals (`--scan-markdown`).
+    pub embedded_files: u64,
 
     /// Total number of lines across all `.nr` files.
-    pub total_lines: usize,
+    pub total_lines: u64,
 
     /// Total blank lines across all `.nr` files.
-    pub blank_lines: usize,
+    pub blank_lines: u64,
 
     /// Total comment lines across all `.nr` files.
-    pub comment_lines: usize,
+    pub comment_lines: u64,
+
+    /// Total `//` comment lines (see [`FileMetrics::line_comment_lines`]).
+    pub line_comment_lines: u64,
+
+    /// Total `/* ... */` comment lines (see [`FileMetrics::block_comment_lines`]).
+    pub block_comment_lines: u64,
+
+    /// Total commented-out code lines (see [`FileMetrics::commented_code_lines`]).
+    pub commented_code_lines: u64,
+
+    /// Total commented-out attributes (see [`FileMetrics::disabled_attributes`]).
+    pub disabled_attributes: u64,
+
+    /// Total doc comment lines (see [`FileMetrics::doc_comment_lines`]).
+    pub doc_comment_lines: u64,
+
+    /// Total inner (`//!`) doc comment lines.
+    pub inner_doc_lines: u64,
+
+    /// Total outer (`///`) doc comment lines.
+    pub outer_doc_lines: u64,
 
     /// Total code lines across all `.nr` files.
-    pub code_lines: usize,
+    pub code_lines: u64,
+
+    /// Total statements (see [`FileMetrics::statement_count`]).
+    pub statement_count: u64,
+
+    /// Total multi-line closers with a trailing comma (see [`FileMetrics::closers_without_trailing_comma`]).
+    pub closers_with_trailing_comma: u64,
+
+    /// Total multi-line closers without a trailing comma.
+    pub closers_without_trailing_comma: u64,
+
+    /// Does the project predominantly use trailing commas in multi-line calls, arrays and struct literals?
+    ///
+    /// `Some(true)` when more sampled closers have a trailing comma than not, `Some(false)` when fewer, and
+    /// omitted when there are no samples or they are evenly split. This is a coarse heuristic for style
+    /// dashboards, not a formatter check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub predominant_trailing_comma: Option<bool>,
+
+    /// Total mutable bindings (see [`FileMetrics::mut_binding_count`]).
+    pub mut_binding_count: u64,
+
+    /// Total declared array length (see [`FileMetrics::array_total_declared_len`]).
+    pub array_total_declared_len: u64,
 
     /// Total number of `#[test...]` functions across all files.
-    pub test_functions: usize,
+    pub test_functions: u64,
 
     /// Total code lines inside `#[test...]` functions.
-    pub test_lines: usize,
+    pub test_lines: u64,
 
     /// Total code lines outside `#[test...]` functions.
-    pub non_test_lines: usize,
+    pub non_test_lines: u64,
 
     /// Total number of functions (`fn` and `pub fn`) across all `.nr` files.
-    pub functions: usize,
+    pub functions: u64,
 
     /// Total number of `pub fn` functions across all `.nr` files.
-    pub pub_functions: usize,
+    pub pub_functions: u64,
 
     /// Total number of non-test functions across all `.nr` files.
-    pub non_test_functions: usize,
+    pub non_test_functions: u64,
+
+    /// Total number of spec functions (non-test functions dominated by asserts).
+    pub spec_functions: u64,
+
+    /// Total number of functions declaring a return type.
+    pub functions_with_return: u64,
+
+    /// Total function and `impl` signatures with a `where` clause.
+    pub where_clause_count: u64,
+
+    /// Total `use` declarations importing from the same package (see [`FileMetrics::internal_imports`]).
+    pub internal_imports: u64,
+
+    /// Total `use` declarations importing from other packages (see [`FileMetrics::external_imports`]).
+    pub external_imports: u64,
+
+    /// Total `pub use` re-exports (see [`FileMetrics::reexports`]).
+    pub reexports: u64,
+
+    /// Total number of `unsafe { ... }` blocks across all `.nr` files.
+    pub unsafe_blocks: u64,
+
+    /// Total code lines inside `unsafe { ... }` blocks.
+    pub unsafe_lines: u64,
+
+    /// Total number of `#[contract]` modules across all `.nr` files.
+    pub contract_modules: u64,
+
+    /// Total number of inline modules (`mod name { ... }`).
+    pub inline_modules: u64,
+
+    /// Total number of `#[public]` contract functions across all `.nr` files.
+    pub public_functions: u64,
+
+    /// Total number of `#[private]` contract functions across all `.nr` files.
+    pub private_functions: u64,
+
+    /// Total number of comment lines with a TODO marker across the project.
+    pub todo_count: u64,
+
+    /// Sum of per-file [`FileMetrics::todo_markers`] (omitted from JSON when empty).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub todo_markers: BTreeMap<String, u64>,
 
-    /// Total number of TODO/FIXME markers in comments across the project.
-    pub todo_count: usize,
+    /// Total debug print calls (see [`FileMetrics::debug_print_count`]).
+    pub debug_print_count: u64,
 
-    /// Number of files that define a `main` function.
-    pub files_with_main: usize,
+    /// Total number of `#[derive(...)]` attributes on structs.
+    pub derive_count: u64,
 
-    /// Percentage of code lines that are test lines (0.0 if there is no code).
+    /// Sum of per-file [`FileMetrics::derives`] (omitted from JSON without `--list-derives`).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub derives: BTreeMap<String, u64>,
+
+    /// Sum of per-file [`FileMetrics::type_usage`] (omitted from JSON without `--type-usage`).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub type_usage: BTreeMap<String, u64>,
+
+    /// Number of files that define a top-level `main` function.
+    pub files_with_main: u64,
+
+    /// Number of non-test files containing inline `#[test...]` functions ([`FileMetrics::has_inline_tests`]).
+    pub files_with_inline_tests: u64,
+
+    /// Number of empty or all-blank files ([`FileMetrics::is_empty`]).
+    pub empty_files: u64,
+
+    /// Number of distinct module names among non-test `.nr` files.
+    ///
+    /// A module is named by its file stem, except `mod.nr`, which is named after its directory; files with
+    /// the same name in different directories count once.
+    pub module_count: u64,
+
+    /// Does the project contain a `lib.nr` or `main.nr` file?
+    pub has_lib_or_main: bool,
+
+    /// Sum of per-file [`FileMetrics::complexity`].
+    pub complexity: u64,
+
+    /// Highest complexity of any single function in the project.
+    pub max_function_complexity: u64,
+
+    /// Mean number of code lines per function (0.0 if there are no functions).
+    pub average_function_length: f64,
+
+    /// Mean number of functions per counted file, embedded files included (0.0 without files).
+    pub mean_functions_per_file: f64,
+
+    /// File with the most functions and that count; the first such file on ties, omitted without functions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_functions_in_file: Option<(PathBuf, usize)>,
+
+    /// Composite maintainability score in `[0, 100]` (higher is better).
+    ///
+    /// Computed from aggregated metrics as:
+    ///
+    /// ```text
+    /// 100 - 0.5 * average_function_length
+    ///     - 5.0 * (average_complexity - 1)
+    ///     - 20.0 * (1 - min(comment_density / 0.2, 1))
+    /// ```
+    ///
+    /// where `average_complexity = complexity / functions` (1.0 without functions) and
+    /// `comment_density = comment_lines / (comment_lines + code_lines)`. The result is clamped to `[0, 100]`.
+    pub maintainability_index: f64,
+
+    /// Sum of per-file [`FileMetrics::regex_counts`] (omitted from JSON without `--count-regex`).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub regex_counts: BTreeMap<String, u64>,
+
+    /// Number of files starting with blank lines ([`FileMetrics::leading_blank_lines`] > 0).
+    pub files_with_leading_blank_lines: u64,
+
+    /// Number of files ending with blank lines ([`FileMetrics::trailing_blank_lines`] > 0).
+    pub files_with_trailing_blank_lines: u64,
+
+    /// Longest line in any file, in characters.
+    pub max_line_length: u64,
+
+    /// Total number of lines over the line length threshold (see [`FileMetrics::long_line_count`]).
+    pub long_line_count: u64,
+
+    /// Number of files per detected indentation style (`none`, `tabs`, `spaces`, `mixed`).
+    pub indent_styles: BTreeMap<IndentStyle, u64>,
+
+    /// Number of files mixing `\n` and `\r\n` line endings (see [`FileMetrics::line_ending`]).
+    pub files_with_mixed_eol: u64,
+
+    /// Percentage of code lines that are test lines (0.0 if there is no code), clamped to `[0, 100]`.
     pub test_code_percentage: f64,
+
+    /// `todo_count` per 1000 code lines (0.0 without code); only set with `--normalize`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todos_per_kloc: Option<f64>,
+
+    /// `complexity` per 1000 code lines (0.0 without code); only set with `--normalize`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complexity_per_kloc: Option<f64>,
+
+    /// `assert`/`assert_eq` calls per 1000 code lines (0.0 without code); only set with `--normalize`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asserts_per_kloc: Option<f64>,
+}
+
+impl ProjectTotals {
+    /// Fraction of commented lines: `comment_lines / (comment_lines + code_lines)` (0.0 without either).
+    pub fn comment_density(&self) -> f64 {
+        let commented = self.comment_lines + self.code_lines;
+        if commented == 0 {
+            0.0
+        } else {
+            self.comment_lines as f64 / commented as f64
+        }
+    }
+}
+
+/// An internal consistency rule broken by a report (see [`MetricsReport::validate`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantViolation {
+    /// File the violation is about; `None` for project totals.
+    pub path: Option<PathBuf>,
+
+    /// Description of the broken rule with the offending values (e.g. `code_lines (5) != ...`).
+    pub message: String,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}", path.display(), self.message),
+            None => write!(f, "totals: {}", self.message),
+        }
+    }
+}
+
+/// A function ranked by length across the whole project.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LongestFunction {
+    /// Path of the file containing the function, relative to the project root.
+    pub path: PathBuf,
+
+    /// Function name.
+    pub name: String,
+
+    /// 1-based line number of the `fn` signature line.
+    pub start_line: usize,
+
+    /// 1-based line number where the function body closes.
+    pub end_line: usize,
+
+    /// Number of lines in the span `start_line..=end_line`.
+    pub lines: usize,
+}
+
+/// A `#[test]` function, as listed by `--list-tests`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TestInfo {
+    /// Path of the file containing the test, relative to the project root.
+    pub path: PathBuf,
+
+    /// Test function name.
+    pub name: String,
+
+    /// Is the test expected to fail (`#[test(should_fail)]` or `should_fail_with`)?
+    pub should_fail: bool,
 }
 
 /// Full metrics report for a project.
@@ -58,7 +317,7 @@ pub struct ProjectTotals {
 /// This type is the primary output for library consumers and JSON output:
 /// - [`MetricsReport::totals`] contains project-level aggregates.
 /// - [`MetricsReport::files`] contains per-file metrics.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct MetricsReport {
     /// Absolute path to the project root.
     pub project_root: PathBuf,
@@ -68,65 +327,1036 @@ pub struct MetricsReport {
 
     /// Per-file metrics for each discovered `.nr` file.
     pub files: Vec<FileMetrics>,
+
+    /// Totals over non-test files only (only populated on request, `--group-tests`; see [`grouped_totals`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totals_production: Option<ProjectTotals>,
+
+    /// Totals over test files only (only populated on request, `--group-tests`; see [`grouped_totals`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totals_tests: Option<ProjectTotals>,
+
+    /// Most directories above any `.nr` file (`src/main.nr` is 1, `src/a/b.nr` is 2; see [`directory_shape`]).
+    #[serde(default)]
+    pub max_directory_depth: usize,
+
+    /// Directory with the most direct `.nr` children, with that count (`.` for the report root).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub widest_directory: Option<(PathBuf, usize)>,
+
+    /// Longest functions project-wide (only populated on request, see [`longest_functions`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub longest_functions: Vec<LongestFunction>,
+
+    /// Every test function in the project (only populated on request, see [`test_list`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<TestInfo>,
+
+    /// Per-file changes against a baseline report (only populated on request, see [`diff_files`]).
+    ///
+    /// [`diff_files`]: crate::diff::diff_files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_diffs: Vec<FileDiff>,
+
+    /// `tool.schema_version` of the JSON baseline that `file_diffs` were computed against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline_schema_version: Option<u32>,
+
+    /// Code lines attributed to git authors (only populated on request, `--by-author`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_author: BTreeMap<String, u64>,
+
+    /// Groups of files with identical contents (only populated on request, `--find-duplicates`).
+    ///
+    /// Each group lists at least two paths, sorted; groups are ordered by their first path.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_groups: Vec<Vec<PathBuf>>,
+
+    /// Evaluation of the configured target profile (only present when a `[profile]` is configured).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<ProfileResult>,
+
+    /// Files exceeding a `budgets.toml` limit (see [`evaluate_budgets`](crate::evaluate_budgets)).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub budget_violations: Vec<BudgetViolation>,
+}
+
+impl MetricsReport {
+    /// Re-express every [`FileMetrics::path`] relative to `dir` instead of the project root.
+    ///
+    /// `dir` is resolved against the current directory and must contain every file (typically an ancestor
+    /// of the project root, such as a repository root); otherwise an error names the first file outside it.
+    pub fn make_paths_relative_to(&mut self, dir: &Path) -> Result<()> {
+        let dir = dir
+            .canonicalize()
+            .with_context(|| format!("cannot resolve {}", dir.display()))?;
+
+        let paths = self.files.iter_mut().map(|fm| &mut fm.path).chain(
+            self.totals
+                .max_functions_in_file
+                .as_mut()
+                .map(|(path, _)| path),
+        );
+        for path in paths {
+            let absolute = self.project_root.join(&*path);
+            let Ok(relative) = absolute.strip_prefix(&dir) else {
+                bail!("{} is not under {}", absolute.display(), dir.display());
+            };
+            *path = relative.to_path_buf();
+        }
+
+        Ok(())
+    }
+
+    /// Rewrite every [`FileMetrics::path`]: first remove the leading `strip` directory, then prepend `add`.
+    ///
+    /// `strip` only applies to paths that start with it (whole components, so `src` does not match
+    /// `srcs/a.nr`); other paths keep their original form before `add` is applied. `totals.max_functions_in_file`
+    /// is rewritten too; other sections derived from file paths (`longest_functions`, `tests`, `file_diffs`,
+    /// `duplicate_groups`) are not touched, so call this before computing them.
+    pub fn rewrite_paths(&mut self, strip: Option<&Path>, add: Option<&Path>) {
+        let paths = self.files.iter_mut().map(|fm| &mut fm.path).chain(
+            self.totals
+                .max_functions_in_file
+                .as_mut()
+                .map(|(path, _)| path),
+        );
+        for path in paths {
+            if let Some(rest) = strip.and_then(|prefix| path.strip_prefix(prefix).ok()) {
+                *path = rest.to_path_buf();
+            }
+            if let Some(prefix) = add {
+                *path = prefix.join(&*path);
+            }
+        }
+    }
+
+    /// Check the report's internal invariants and return every violation found.
+    ///
+    /// Per file:
+    /// - `code_lines == test_lines + non_test_lines` and `test_lines <= code_lines`;
+    /// - `total_lines == blank_lines + comment_lines + code_lines` (Markdown-embedded files are skipped, as
+    ///   lines outside code fences are not classified);
+    /// - `comment_lines == line_comment_lines + block_comment_lines` and
+    ///   `doc_comment_lines == inner_doc_lines + outer_doc_lines`;
+    /// - `test_functions` and `pub_functions` do not exceed `functions`.
+    ///
+    /// Totals:
+    /// - the summed line, function and TODO counts equal those recomputed from the non-ignored files;
+    /// - `test_code_percentage` and `maintainability_index` lie in `[0, 100]`.
+    ///
+    /// Reports whose file list was filtered after the totals were computed (e.g. `--only-changed`) fail the
+    /// totals checks by design.
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = Vec::new();
+
+        for fm in &self.files {
+            let mut check = |ok: bool, message: String| {
+                if !ok {
+                    violations.push(InvariantViolation {
+                        path: Some(fm.path.clone()),
+                        message,
+                    });
+                }
+            };
+
+            check(
+                fm.code_lines == fm.test_lines + fm.non_test_lines,
+                format!(
+                    "code_lines ({}) != test_lines ({}) + non_test_lines ({})",
+                    fm.code_lines, fm.test_lines, fm.non_test_lines
+                ),
+            );
+            check(
+                fm.test_lines <= fm.code_lines,
+                format!(
+                    "test_lines ({}) > code_lines ({})",
+                    fm.test_lines, fm.code_lines
+                ),
+            );
+            check(
+                fm.is_embedded
+                    || fm.total_lines == fm.blank_lines + fm.comment_lines + fm.code_lines,
+                format!(
+                    "total_lines ({}) != blank_lines ({}) + comment_lines ({}) + code_lines ({})",
+                    fm.total_lines, fm.blank_lines, fm.comment_lines, fm.code_lines
+                ),
+            );
+            check(
+                fm.comment_lines == fm.line_comment_lines + fm.block_comment_lines,
+                format!(
+                    "comment_lines ({}) != line_comment_lines ({}) + block_comment_lines ({})",
+                    fm.comment_lines, fm.line_comment_lines, fm.block_comment_lines
+                ),
+            );
+            check(
+                fm.doc_comment_lines == fm.inner_doc_lines + fm.outer_doc_lines,
+                format!(
+                    "doc_comment_lines ({}) != inner_doc_lines ({}) + outer_doc_lines ({})",
+                    fm.doc_comment_lines, fm.inner_doc_lines, fm.outer_doc_lines
+                ),
+            );
+            check(
+                fm.test_functions <= fm.functions && fm.pub_functions <= fm.functions,
+                format!(
+                    "test_functions ({}) or pub_functions ({}) exceed functions ({})",
+                    fm.test_functions, fm.pub_functions, fm.functions
+                ),
+            );
+        }
+
+        let mut check_totals = |ok: bool, message: String| {
+            if !ok {
+                violations.push(InvariantViolation {
+                    path: None,
+                    message,
+                });
+            }
+        };
+
+        match compute_totals(&self.files) {
+            Ok(expected) => {
+                let sums = [
+                    ("files", self.totals.files, expected.files),
+                    ("total_lines", self.totals.total_lines, expected.total_lines),
+                    ("blank_lines", self.totals.blank_lines, expected.blank_lines),
+                    (
+                        "comment_lines",
+                        self.totals.comment_lines,
+                        expected.comment_lines,
+                    ),
+                    ("code_lines", self.totals.code_lines, expected.code_lines),
+                    ("test_lines", self.totals.test_lines, expected.test_lines),
+                    (
+                        "non_test_lines",
+                        self.totals.non_test_lines,
+                        expected.non_test_lines,
+                    ),
+                    ("functions", self.totals.functions, expected.functions),
+                    (
+                        "test_functions",
+                        self.totals.test_functions,
+                        expected.test_functions,
+                    ),
+                    ("todo_count", self.totals.todo_count, expected.todo_count),
+                ];
+                for (name, actual, expected) in sums {
+                    check_totals(
+                        actual == expected,
+                        format!("{name} ({actual}) != sum over files ({expected})"),
+                    );
+                }
+            }
+            Err(e) => check_totals(false, format!("totals cannot be recomputed: {e}")),
+        }
+
+        for (name, value) in [
+            ("test_code_percentage", self.totals.test_code_percentage),
+            ("maintainability_index", self.totals.maintainability_index),
+        ] {
+            check_totals(
+                (0.0..=100.0).contains(&value),
+                format!("{name} ({value}) is outside [0, 100]"),
+            );
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Fill the `*_per_kloc` density fields of every file and of the totals (`--normalize`).
+    ///
+    /// Densities are derived from the existing counts against code lines; files without code get 0.0.
+    pub fn add_per_kloc(&mut self) {
+        let mut total_asserts = 0;
+        for fm in &mut self.files {
+            let code_lines = fm.code_lines as u64;
+            let asserts = assert_calls(fm);
+            if !fm.ignored {
+                total_asserts += asserts;
+            }
+            fm.todos_per_kloc = Some(per_kloc(fm.todo_count as u64, code_lines));
+            fm.complexity_per_kloc = Some(per_kloc(fm.complexity as u64, code_lines));
+            fm.asserts_per_kloc = Some(per_kloc(asserts, code_lines));
+        }
+
+        let totals = &mut self.totals;
+        totals.todos_per_kloc = Some(per_kloc(totals.todo_count, totals.code_lines));
+        totals.complexity_per_kloc = Some(per_kloc(totals.complexity, totals.code_lines));
+        totals.asserts_per_kloc = Some(per_kloc(total_asserts, totals.code_lines));
+    }
+}
+
+/// Number of `assert`/`assert_eq` calls in a file, summed over its functions.
+fn assert_calls(fm: &FileMetrics) -> u64 {
+    fm.function_metrics
+        .iter()
+        .map(|f| f.assert_count as u64)
+        .sum()
+}
+
+/// `count` per 1000 code lines (0.0 when `code_lines == 0`).
+fn per_kloc(count: u64, code_lines: u64) -> f64 {
+    if code_lines == 0 {
+        0.0
+    } else {
+        count as f64 * 1000.0 / code_lines as f64
+    }
 }
 
 /// Analyze a project: collect per-file metrics and aggregate totals.
 ///
 /// The file list is sourced from [`Project::nr_files`]. Each file is analyzed using [`analyze_file`],
-/// and totals are computed via aggregation.
-pub fn analyze_project(project: &Project) -> Result<MetricsReport> {
-    let nr_files = project.nr_files()?;
-
+/// and totals are computed via aggregation. Per-file analysis honors `options`; file discovery uses
+/// [`Project::walk`].
+pub fn analyze_project(project: &Project, options: &AnalysisOptions) -> Result<MetricsReport> {
     let mut files_metrics = Vec::new();
-    for path in &nr_files {
-        let metrics = analyze_file(path, &project.root)?;
+    let totals = analyze_project_with_sink(project, options, |metrics| {
         files_metrics.push(metrics);
-    }
+        Ok(())
+    })?;
 
-    let totals = compute_totals(&files_metrics);
+    let (max_directory_depth, widest_directory) = directory_shape(&files_metrics);
 
     Ok(MetricsReport {
         project_root: project.root.clone(),
         totals,
         files: files_metrics,
+        max_directory_depth,
+        widest_directory,
+        ..Default::default()
     })
 }
 
-/// Compute project-level totals from per-file metrics.
+/// Analyze a project file by file, handing each file's metrics to `sink` as soon as it is computed.
+///
+/// Files are visited in the same order as in [`analyze_project`] (sorted `.nr` files, then Markdown files with
+/// `scan_markdown`). Totals are accumulated incrementally and returned at the end, so the caller decides
+/// whether to keep the per-file metrics. An error from `sink` stops the analysis.
 ///
-/// The `test_code_percentage` field is computed from `test_lines / code_lines * 100.0`
-/// and is `0.0` when `code_lines == 0`.
-fn compute_totals(files: &[FileMetrics]) -> ProjectTotals {
-    let mut totals = ProjectTotals {
-        files: files.len(),
+/// With [`AnalysisOptions::parallel`], `.nr` files are analyzed on several threads and still handed to `sink`
+/// one by one in the same order, each as soon as the files before it are done; the first failing file (in
+/// path order) is reported.
+pub fn analyze_project_with_sink(
+    project: &Project,
+    options: &AnalysisOptions,
+    mut sink: impl FnMut(FileMetrics) -> Result<()>,
+) -> Result<ProjectTotals> {
+    let mut acc = TotalsAccumulator::default();
+
+    let paths = project.nr_files()?;
+    let threads = options.thread_count(paths.len());
+    if threads > 1 {
+        analyze_files_in_parallel(&paths, &project.root, options, threads, |metrics| {
+            acc.add(&metrics)?;
+            sink(metrics)
+        })?;
+    } else {
+        for path in &paths {
+            let metrics = analyze_file(path, &project.root, options)?;
+            acc.add(&metrics)?;
+            sink(metrics)?;
+        }
+    }
+
+    if options.scan_markdown {
+        for path in &project.markdown_files()? {
+            if let Some(metrics) = analyze_markdown_file(path, &project.root, options)? {
+                acc.add(&metrics)?;
+                sink(metrics)?;
+            }
+        }
+    }
+
+    Ok(acc.finish())
+}
+
+/// Analyze `paths` on `threads` scoped threads and hand each result to `emit` in the order of `paths`.
+///
+/// Workers take the next unclaimed path from a shared counter and send their results over a bounded
+/// channel, so a file is emitted as soon as every file before it is done. A worker does not start on
+/// path `i` until fewer than `threads` paths before it are still waiting to be emitted, so at most
+/// `threads` finished results are held for reordering even when one file is much slower than the rest.
+/// The first error (an analysis failure in path order, or from `emit`) stops the workers.
+fn analyze_files_in_parallel(
+    paths: &[PathBuf],
+    root: &Path,
+    options: &AnalysisOptions,
+    threads: usize,
+    mut emit: impl FnMut(FileMetrics) -> Result<()>,
+) -> Result<()> {
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    // Number of results handed to `emit` so far; workers wait on it to keep the reorder window bounded.
+    let emitted = (Mutex::new(0), Condvar::new());
+    let (tx, rx) = mpsc::sync_channel(threads);
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            let tx = tx.clone();
+            let (next, stop, emitted) = (&next, &stop, &emitted);
+            scope.spawn(move || {
+                while !stop.load(AtomicOrdering::Relaxed) {
+                    let index = next.fetch_add(1, AtomicOrdering::Relaxed);
+                    let Some(path) = paths.get(index) else {
+                        break;
+                    };
+                    let (count, advanced) = emitted;
+                    let mut count = count
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    while index >= *count + threads && !stop.load(AtomicOrdering::Relaxed) {
+                        count = advanced
+                            .wait(count)
+                            .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    }
+                    drop(count);
+                    if stop.load(AtomicOrdering::Relaxed) {
+                        break;
+                    }
+                    if tx.send((index, analyze_file(path, root, options))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let mut pending = BTreeMap::new();
+        let mut expected = 0;
+        let mut result = Ok(());
+        for (index, metrics) in &rx {
+            pending.insert(index, metrics);
+            while let Some(metrics) = pending.remove(&expected) {
+                expected += 1;
+                result = metrics.and_then(&mut emit);
+                if result.is_err() {
+                    break;
+                }
+            }
+            if result.is_err() {
+                stop.store(true, AtomicOrdering::Relaxed);
+            }
+            let (count, advanced) = &emitted;
+            *count
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = expected;
+            advanced.notify_all();
+            if result.is_err() {
+                break;
+            }
+        }
+        // Unblock workers waiting on a full channel so the scope can join them.
+        drop(rx);
+        result
+    })
+}
+
+/// Analyze in-memory `(path, contents)` pairs as if they were the `.nr` files of a project at `root`.
+///
+/// No filesystem access happens: paths are only used for reporting (made relative to `root` when below it)
+/// and for path-based heuristics such as test-file detection. Files are sorted by path, matching the order of
+/// [`analyze_project`]. Walk options and `scan_markdown` do not apply.
+pub fn analyze_files<I>(files: I, root: &Path, options: &AnalysisOptions) -> Result<MetricsReport>
+where
+    I: IntoIterator<Item = (PathBuf, String)>,
+{
+    let mut files_metrics: Vec<FileMetrics> = files
+        .into_iter()
+        .map(|(path, source)| analyze_contents(&source, &path, root, options))
+        .collect();
+    files_metrics.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let totals = compute_totals(&files_metrics)?;
+    let (max_directory_depth, widest_directory) = directory_shape(&files_metrics);
+
+    Ok(MetricsReport {
+        project_root: root.to_path_buf(),
+        totals,
+        files: files_metrics,
+        max_directory_depth,
+        widest_directory,
         ..Default::default()
+    })
+}
+
+/// Compute the maintainability index documented on [`ProjectTotals::maintainability_index`].
+fn maintainability_index(totals: &ProjectTotals) -> f64 {
+    let average_complexity = if totals.functions == 0 {
+        1.0
+    } else {
+        totals.complexity as f64 / totals.functions as f64
     };
 
+    let comment_density = totals.comment_density();
+
+    let score = 100.0
+        - 0.5 * totals.average_function_length
+        - 5.0 * (average_complexity - 1.0)
+        - 20.0 * (1.0 - (comment_density / 0.2).min(1.0));
+
+    score.clamp(0.0, 100.0)
+}
+
+/// Nesting depth of the deepest `.nr` file and the directory with the most direct `.nr` children.
+///
+/// Depth counts the directories in a file's reported path, so it follows any path rewriting. Ties for the
+/// widest directory go to the first directory by path. Markdown files are not counted.
+pub fn directory_shape(files: &[FileMetrics]) -> (usize, Option<(PathBuf, usize)>) {
+    let mut max_depth = 0;
+    let mut children: BTreeMap<PathBuf, usize> = BTreeMap::new();
+
+    for fm in files
+        .iter()
+        .filter(|fm| fm.path.extension() == Some("nr".as_ref()))
+    {
+        let dir = fm.path.parent().unwrap_or(Path::new(""));
+        max_depth = max_depth.max(dir.components().count());
+
+        let dir = if dir.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            dir.to_path_buf()
+        };
+        *children.entry(dir).or_default() += 1;
+    }
+
+    let widest = children.into_iter().rev().max_by_key(|(_, count)| *count);
+
+    (max_depth, widest)
+}
+
+/// Group files whose [`FileMetrics::content_hash`] is identical.
+///
+/// Files without a hash are skipped; only groups with two or more files are returned.
+pub fn duplicate_groups(files: &[FileMetrics]) -> Vec<Vec<PathBuf>> {
+    let mut by_hash: BTreeMap<&str, Vec<PathBuf>> = BTreeMap::new();
+
+    for fm in files {
+        if let Some(hash) = &fm.content_hash {
+            by_hash.entry(hash).or_default().push(fm.path.clone());
+        }
+    }
+
+    let mut groups: Vec<Vec<PathBuf>> = by_hash
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .map(|mut paths| {
+            paths.sort();
+            paths
+        })
+        .collect();
+    groups.sort();
+    groups
+}
+
+/// Rank the `n` longest functions across all files.
+///
+/// Functions are ordered by span length (descending), then by path and start line for stable output.
+pub fn longest_functions(files: &[FileMetrics], n: usize) -> Vec<LongestFunction> {
+    let mut ranked: Vec<LongestFunction> = files
+        .iter()
+        .flat_map(|fm| {
+            fm.function_metrics.iter().map(|f| LongestFunction {
+                path: fm.path.clone(),
+                name: f.name.clone(),
+                start_line: f.start_line,
+                end_line: f.end_line,
+                lines: f.lines(),
+            })
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.lines
+            .cmp(&a.lines)
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+    ranked.truncate(n);
+    ranked
+}
+
+/// Split totals into `(production, tests)`: totals over non-test files and over test files
+/// ([`FileMetrics::is_test_file`]).
+///
+/// Every file lands in exactly one half, so summed metrics such as `code_lines` add up to the combined totals.
+/// Inline `#[test]` functions in source files stay in the production half (as its `test_lines`).
+pub fn grouped_totals(files: &[FileMetrics]) -> Result<(ProjectTotals, ProjectTotals)> {
+    let mut production = TotalsAccumulator::default();
+    let mut tests = TotalsAccumulator::default();
+
+    for fm in files {
+        if fm.is_test_file {
+            tests.add(fm)?;
+        } else {
+            production.add(fm)?;
+        }
+    }
+
+    Ok((production.finish(), tests.finish()))
+}
+
+/// Source files with non-test functions but no corresponding test file, in file order.
+///
+/// A source file `dir/<stem>.nr` is covered by a test file ([`FileMetrics::is_test_file`]) at
+/// `dir/<stem>_test.nr`, or by one named `<stem>.nr` or `<stem>_test.nr` anywhere under a `tests`/`test`
+/// directory. Only files in `files` are considered, so test directories outside the walk (e.g. a root-level
+/// `tests/` without `--all`) do not count. Ignored and embedded files are skipped.
+pub fn files_without_tests(files: &[FileMetrics]) -> Vec<PathBuf> {
+    let test_files: Vec<&Path> = files
+        .iter()
+        .filter(|fm| fm.is_test_file)
+        .map(|fm| fm.path.as_path())
+        .collect();
+
+    let is_covered = |path: &Path| {
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            return false;
+        };
+        let sibling = path.with_file_name(format!("{stem}_test.nr"));
+        let under_tests_dir = |test: &Path| {
+            test.components()
+                .any(|c| matches!(c.as_os_str().to_str(), Some("tests" | "test")))
+                && test
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|test_stem| {
+                        test_stem == stem || test_stem.strip_suffix("_test") == Some(stem)
+                    })
+        };
+        test_files
+            .iter()
+            .any(|test| *test == sibling || under_tests_dir(test))
+    };
+
+    files
+        .iter()
+        .filter(|fm| {
+            !fm.is_test_file && !fm.ignored && !fm.is_embedded && fm.non_test_functions > 0
+        })
+        .filter(|fm| !is_covered(&fm.path))
+        .map(|fm| fm.path.clone())
+        .collect()
+}
+
+/// List all test functions, in file order and then source order.
+pub fn test_list(files: &[FileMetrics]) -> Vec<TestInfo> {
+    files
+        .iter()
+        .flat_map(|fm| {
+            fm.function_metrics
+                .iter()
+                .filter(|f| f.is_test)
+                .map(|f| TestInfo {
+                    path: fm.path.clone(),
+                    name: f.name.clone(),
+                    should_fail: f.should_fail,
+                })
+        })
+        .collect()
+}
+
+/// Compute project-level totals from per-file metrics.
+///
+/// Files marked as [`FileMetrics::ignored`] are counted in `ignored_files` and otherwise skipped.
+/// Embedded Markdown entries ([`FileMetrics::is_embedded`]) are counted in `embedded_files` instead of
+/// `files`; their line and function metrics are summed like any other file.
+///
+/// Totals are accumulated as `u64` with checked addition; an overflow is reported as an error rather
+/// than wrapping or panicking.
+///
+/// The `test_code_percentage` field is computed from `test_lines / code_lines * 100.0`, clamped to
+/// `[0, 100]`, and is `0.0` when `code_lines == 0`.
+pub(crate) fn compute_totals(files: &[FileMetrics]) -> Result<ProjectTotals> {
+    let mut acc = TotalsAccumulator::default();
     for fm in files {
-        totals.total_lines += fm.total_lines;
-        totals.blank_lines += fm.blank_lines;
-        totals.comment_lines += fm.comment_lines;
-        totals.code_lines += fm.code_lines;
-        totals.test_functions += fm.test_functions;
-        totals.test_lines += fm.test_lines;
-        totals.non_test_lines += fm.non_test_lines;
-
-        totals.functions += fm.functions;
-        totals.pub_functions += fm.pub_functions;
-        totals.non_test_functions += fm.non_test_functions;
-        totals.todo_count += fm.todo_count;
+        acc.add(fm)?;
+    }
+    Ok(acc.finish())
+}
+
+/// Incrementally builds [`ProjectTotals`] one file at a time, so totals can be computed without keeping every
+/// [`FileMetrics`] in memory (see [`analyze_project_with_sink`]).
+///
+/// Feeding files one by one with [`TotalsAccumulator::add`] and calling [`TotalsAccumulator::finish`] yields
+/// the same totals as a batch [`MetricsReport`]; the batch path is built on this type.
+///
+/// ```
+/// use noir_metrics::{FileMetrics, TotalsAccumulator};
+/// use std::path::PathBuf;
+///
+/// let mut acc = TotalsAccumulator::default();
+/// for code_lines in [10, 32] {
+///     let fm = FileMetrics {
+///         path: PathBuf::from("src/main.nr"),
+///         code_lines,
+///         ..Default::default()
+///     };
+///     acc.add(&fm).unwrap();
+/// }
+///
+/// let totals = acc.finish();
+/// assert_eq!((totals.files, totals.code_lines), (2, 42));
+/// ```
+#[derive(Debug, Default)]
+pub struct TotalsAccumulator {
+    totals: ProjectTotals,
+    function_code_lines: u64,
+    modules: BTreeSet<String>,
+}
+
+impl TotalsAccumulator {
+    /// Add one file's metrics to the running sums.
+    ///
+    /// Fails when a sum would overflow `u64`; the accumulator should then be discarded.
+    pub fn add(&mut self, fm: &FileMetrics) -> Result<()> {
+        if fm.ignored {
+            add(&mut self.totals.ignored_files, 1, "ignored_files")?;
+            return Ok(());
+        }
+
+        if fm.is_embedded {
+            add(&mut self.totals.embedded_files, 1, "embedded_files")?;
+        } else {
+            add(&mut self.totals.files, 1, "files")?;
+
+            let file_name = fm.path.file_name().and_then(|name| name.to_str());
+            self.totals.has_lib_or_main |= matches!(file_name, Some("lib.nr" | "main.nr"));
+
+            if !fm.is_test_file
+                && let Some(module) = module_name(&fm.path)
+            {
+                self.modules.insert(module.to_string());
+            }
+        }
+        if fm.functions > 0
+            && self
+                .totals
+                .max_functions_in_file
+                .as_ref()
+                .is_none_or(|(_, max)| fm.functions > *max)
+        {
+            self.totals.max_functions_in_file = Some((fm.path.clone(), fm.functions));
+        }
+        add(&mut self.totals.total_lines, fm.total_lines, "total_lines")?;
+        add(&mut self.totals.blank_lines, fm.blank_lines, "blank_lines")?;
+        add(
+            &mut self.totals.comment_lines,
+            fm.comment_lines,
+            "comment_lines",
+        )?;
+        add(
+            &mut self.totals.line_comment_lines,
+            fm.line_comment_lines,
+            "line_comment_lines",
+        )?;
+        add(
+            &mut self.totals.block_comment_lines,
+            fm.block_comment_lines,
+            "block_comment_lines",
+        )?;
+        add(
+            &mut self.totals.commented_code_lines,
+            fm.commented_code_lines,
+            "commented_code_lines",
+        )?;
+        add(
+            &mut self.totals.disabled_attributes,
+            fm.disabled_attributes,
+            "disabled_attributes",
+        )?;
+        add(
+            &mut self.totals.doc_comment_lines,
+            fm.doc_comment_lines,
+            "doc_comment_lines",
+        )?;
+        add(
+            &mut self.totals.inner_doc_lines,
+            fm.inner_doc_lines,
+            "inner_doc_lines",
+        )?;
+        add(
+            &mut self.totals.outer_doc_lines,
+            fm.outer_doc_lines,
+            "outer_doc_lines",
+        )?;
+        add(&mut self.totals.code_lines, fm.code_lines, "code_lines")?;
+        add(
+            &mut self.totals.statement_count,
+            fm.statement_count,
+            "statement_count",
+        )?;
+        add(
+            &mut self.totals.closers_with_trailing_comma,
+            fm.closers_with_trailing_comma,
+            "closers_with_trailing_comma",
+        )?;
+        add(
+            &mut self.totals.closers_without_trailing_comma,
+            fm.closers_without_trailing_comma,
+            "closers_without_trailing_comma",
+        )?;
+        add(
+            &mut self.totals.mut_binding_count,
+            fm.mut_binding_count,
+            "mut_binding_count",
+        )?;
+        add(
+            &mut self.totals.array_total_declared_len,
+            fm.array_total_declared_len,
+            "array_total_declared_len",
+        )?;
+        add(
+            &mut self.totals.test_functions,
+            fm.test_functions,
+            "test_functions",
+        )?;
+        add(&mut self.totals.test_lines, fm.test_lines, "test_lines")?;
+        add(
+            &mut self.totals.non_test_lines,
+            fm.non_test_lines,
+            "non_test_lines",
+        )?;
+
+        add(&mut self.totals.functions, fm.functions, "functions")?;
+        add(
+            &mut self.totals.pub_functions,
+            fm.pub_functions,
+            "pub_functions",
+        )?;
+        add(
+            &mut self.totals.non_test_functions,
+            fm.non_test_functions,
+            "non_test_functions",
+        )?;
+        add(
+            &mut self.totals.spec_functions,
+            fm.spec_functions,
+            "spec_functions",
+        )?;
+        add(
+            &mut self.totals.functions_with_return,
+            fm.functions_with_return,
+            "functions_with_return",
+        )?;
+        add(
+            &mut self.totals.where_clause_count,
+            fm.where_clause_count,
+            "where_clause_count",
+        )?;
+        add(
+            &mut self.totals.internal_imports,
+            fm.internal_imports,
+            "internal_imports",
+        )?;
+        add(
+            &mut self.totals.external_imports,
+            fm.external_imports,
+            "external_imports",
+        )?;
+        add(&mut self.totals.reexports, fm.reexports, "reexports")?;
+        add(
+            &mut self.totals.unsafe_blocks,
+            fm.unsafe_blocks,
+            "unsafe_blocks",
+        )?;
+        add(
+            &mut self.totals.unsafe_lines,
+            fm.unsafe_lines,
+            "unsafe_lines",
+        )?;
+        add(
+            &mut self.totals.contract_modules,
+            fm.contract_modules,
+            "contract_modules",
+        )?;
+        add(
+            &mut self.totals.inline_modules,
+            fm.inline_modules,
+            "inline_modules",
+        )?;
+        add(
+            &mut self.totals.public_functions,
+            fm.public_functions,
+            "public_functions",
+        )?;
+        add(
+            &mut self.totals.private_functions,
+            fm.private_functions,
+            "private_functions",
+        )?;
+        add(&mut self.totals.todo_count, fm.todo_count, "todo_count")?;
+        add(
+            &mut self.totals.debug_print_count,
+            fm.debug_print_count,
+            "debug_print_count",
+        )?;
+        add(
+            &mut self.totals.derive_count,
+            fm.derive_count,
+            "derive_count",
+        )?;
+        for (name, count) in &fm.derives {
+            add(
+                self.totals.derives.entry(name.clone()).or_insert(0),
+                *count,
+                "derives",
+            )?;
+        }
+        for (name, count) in &fm.type_usage {
+            add(
+                self.totals.type_usage.entry(name.clone()).or_insert(0),
+                *count,
+                "type_usage",
+            )?;
+        }
         if fm.has_main {
-            totals.files_with_main += 1;
+            add(&mut self.totals.files_with_main, 1, "files_with_main")?;
+        }
+        if fm.is_empty {
+            add(&mut self.totals.empty_files, 1, "empty_files")?;
+        }
+        if fm.has_inline_tests {
+            add(
+                &mut self.totals.files_with_inline_tests,
+                1,
+                "files_with_inline_tests",
+            )?;
+        }
+        add(&mut self.totals.complexity, fm.complexity, "complexity")?;
+        self.totals.max_function_complexity = self
+            .totals
+            .max_function_complexity
+            .max(fm.max_function_complexity as u64);
+        if fm.leading_blank_lines > 0 {
+            add(
+                &mut self.totals.files_with_leading_blank_lines,
+                1,
+                "files_with_leading_blank_lines",
+            )?;
+        }
+        if fm.trailing_blank_lines > 0 {
+            add(
+                &mut self.totals.files_with_trailing_blank_lines,
+                1,
+                "files_with_trailing_blank_lines",
+            )?;
+        }
+        self.totals.max_line_length = self.totals.max_line_length.max(fm.max_line_length as u64);
+        add(
+            &mut self.totals.long_line_count,
+            fm.long_line_count,
+            "long_line_count",
+        )?;
+        for f in &fm.function_metrics {
+            add(
+                &mut self.function_code_lines,
+                f.code_lines,
+                "function code_lines",
+            )?;
+        }
+        add(
+            self.totals
+                .indent_styles
+                .entry(fm.indent_style)
+                .or_insert(0),
+            1,
+            "indent_styles",
+        )?;
+        if fm.line_ending == LineEnding::Mixed {
+            add(
+                &mut self.totals.files_with_mixed_eol,
+                1,
+                "files_with_mixed_eol",
+            )?;
+        }
+        for (marker, count) in &fm.todo_markers {
+            add(
+                self.totals.todo_markers.entry(marker.clone()).or_insert(0),
+                *count,
+                "todo_markers",
+            )?;
+        }
+        for (name, count) in &fm.regex_counts {
+            add(
+                self.totals.regex_counts.entry(name.clone()).or_insert(0),
+                *count,
+                "regex_counts",
+            )?;
         }
+
+        Ok(())
     }
 
-    totals.test_code_percentage = if totals.code_lines == 0 {
+    /// Compute the derived totals (module count, percentages, averages, maintainability) and return them.
+    pub fn finish(self) -> ProjectTotals {
+        let mut totals = self.totals;
+        totals.module_count = self.modules.len() as u64;
+
+        totals.test_code_percentage = test_code_percentage(totals.test_lines, totals.code_lines);
+
+        totals.predominant_trailing_comma = match totals
+            .closers_with_trailing_comma
+            .cmp(&totals.closers_without_trailing_comma)
+        {
+            Ordering::Greater => Some(true),
+            Ordering::Less => Some(false),
+            Ordering::Equal => None,
+        };
+
+        totals.average_function_length = if totals.functions == 0 {
+            0.0
+        } else {
+            self.function_code_lines as f64 / totals.functions as f64
+        };
+
+        let counted_files = totals.files + totals.embedded_files;
+        totals.mean_functions_per_file = if counted_files == 0 {
+            0.0
+        } else {
+            totals.functions as f64 / counted_files as f64
+        };
+
+        totals.maintainability_index = maintainability_index(&totals);
+
+        totals
+    }
+}
+
+/// `test_lines / code_lines * 100`, clamped to `[0, 100]` (0.0 when `code_lines == 0`).
+///
+/// `test_lines` should never exceed `code_lines`; when the line heuristics disagree it is clamped here and
+/// reported once per file by [`MetricsReport::validate`].
+pub(crate) fn test_code_percentage(test_lines: u64, code_lines: u64) -> f64 {
+    if code_lines == 0 {
         0.0
     } else {
-        (totals.test_lines as f64 / totals.code_lines as f64) * 100.0
-    };
+        (test_lines as f64 / code_lines as f64 * 100.0).clamp(0.0, 100.0)
+    }
+}
 
-    totals
+/// Module name of a `.nr` file: its stem, or the parent directory name for `mod.nr`.
+fn module_name(path: &Path) -> Option<&str> {
+    let stem = path.file_stem()?.to_str()?;
+    if stem == "mod" {
+        path.parent()?.file_name()?.to_str()
+    } else {
+        Some(stem)
+    }
+}
+
+/// Add a per-file count to a project total, failing instead of wrapping on overflow.
+///
+/// `usize` never exceeds 64 bits on supported targets, so the widening cast is lossless.
+fn add(total: &mut u64, value: usize, field: &str) -> Result<()> {
+    *total = total
+        .checked_add(value as u64)
+        .ok_or_else(|| anyhow!("project total `{field}` overflowed u64"))?;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -135,41 +1365,176 @@ mod tests {
     use crate::project::Project;
     use std::path::PathBuf;
 
+    #[test]
+    fn streamed_files_and_totals_match_the_batch_report() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/project_metrics"))
+            .expect("project should be valid");
+        let options = AnalysisOptions::default();
+
+        let mut paths = Vec::new();
+        let totals = analyze_project_with_sink(&project, &options, |fm| {
+            paths.push(fm.path);
+            Ok(())
+        })
+        .expect("streaming analysis should succeed");
+
+        let report = analyze_project(&project, &options).expect("analyze_project should succeed");
+        let batch_paths: Vec<PathBuf> = report.files.iter().map(|fm| fm.path.clone()).collect();
+
+        assert_eq!(paths, batch_paths);
+        assert!(paths.is_sorted());
+        assert_eq!(
+            serde_json::to_value(&totals).unwrap(),
+            serde_json::to_value(&report.totals).unwrap()
+        );
+    }
+
+    #[test]
+    fn accumulating_file_by_file_matches_compute_totals() {
+        for fixture in [
+            "project_metrics",
+            "inline_tests",
+            "empty_files",
+            "spec_functions",
+        ] {
+            let mut project = Project::from_root(PathBuf::from("tests/fixtures").join(fixture))
+                .expect("project should be valid");
+            project.walk.all = true;
+            let report = analyze_project(&project, &AnalysisOptions::default())
+                .expect("analyze_project should succeed");
+
+            let mut acc = TotalsAccumulator::default();
+            for fm in &report.files {
+                acc.add(fm).expect("totals should not overflow");
+            }
+
+            assert_eq!(
+                serde_json::to_value(acc.finish()).unwrap(),
+                serde_json::to_value(compute_totals(&report.files).unwrap()).unwrap(),
+                "{fixture}"
+            );
+        }
+    }
+
+    #[test]
+    fn validate_accepts_analyzed_reports() {
+        for fixture in [
+            "project_metrics",
+            "comment_styles",
+            "doc_comments",
+            "inline_modules",
+        ] {
+            let project = Project::from_root(PathBuf::from("tests/fixtures").join(fixture))
+                .expect("project should be valid");
+            let report = analyze_project(&project, &AnalysisOptions::default())
+                .expect("analyze_project should succeed");
+            assert_eq!(report.validate(), Ok(()), "{fixture}");
+        }
+    }
+
+    #[test]
+    fn validate_reports_each_broken_invariant() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/project_metrics"))
+            .expect("project should be valid");
+        let mut report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        report.files[0].non_test_lines += 1;
+        report.totals.functions += 2;
+        report.totals.test_code_percentage = 120.0;
+
+        let violations = report.validate().expect_err("the report is inconsistent");
+        let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "src/main.nr: code_lines (11) != test_lines (8) + non_test_lines (4)".to_string(),
+                format!(
+                    "totals: non_test_lines ({}) != sum over files ({})",
+                    report.totals.non_test_lines,
+                    report.totals.non_test_lines + 1
+                ),
+                format!(
+                    "totals: functions ({}) != sum over files ({})",
+                    report.totals.functions,
+                    report.totals.functions - 2
+                ),
+                "totals: test_code_percentage (120) is outside [0, 100]".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn per_kloc_densities_use_code_lines() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/project_metrics"))
+            .expect("project should be valid");
+        let mut report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+        assert_eq!(report.totals.todos_per_kloc, None);
+
+        report.add_per_kloc();
+
+        // One TODO in three code lines.
+        let pub_todo = report
+            .files
+            .iter()
+            .find(|fm| fm.path.ends_with("src/pub_todo.nr"))
+            .expect("pub_todo.nr should be listed");
+        assert_eq!((pub_todo.todo_count, pub_todo.code_lines), (1, 3));
+        assert_eq!(pub_todo.todos_per_kloc, Some(1000.0 / 3.0));
+
+        let expected = report.totals.todo_count as f64 * 1000.0 / report.totals.code_lines as f64;
+        assert_eq!(report.totals.todos_per_kloc, Some(expected));
+
+        let asserts: u64 = report
+            .files
+            .iter()
+            .filter(|fm| !fm.ignored)
+            .flat_map(|fm| &fm.function_metrics)
+            .map(|f| f.assert_count as u64)
+            .sum();
+        assert!(asserts > 0, "fixture should contain asserts");
+        let expected = asserts as f64 * 1000.0 / report.totals.code_lines as f64;
+        assert_eq!(report.totals.asserts_per_kloc, Some(expected));
+        assert_eq!(per_kloc(5, 0), 0.0);
+    }
+
     #[test]
     fn project_totals_match_sum_of_file_metrics() {
         let root = PathBuf::from("tests/fixtures/project_metrics");
         let project = Project::from_root(root).expect("project should be valid");
 
-        let report = analyze_project(&project).expect("analyze_project should succeed");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
 
         // Manual sums from file metrics
-        let mut files = 0usize;
-        let mut total_lines = 0usize;
-        let mut blank_lines = 0usize;
-        let mut comment_lines = 0usize;
-        let mut code_lines = 0usize;
-        let mut test_functions = 0usize;
-        let mut test_lines = 0usize;
-        let mut non_test_lines = 0usize;
-        let mut functions = 0usize;
-        let mut pub_functions = 0usize;
-        let mut non_test_functions = 0usize;
-        let mut todo_count = 0usize;
-        let mut files_with_main = 0usize;
+        let mut files = 0u64;
+        let mut total_lines = 0u64;
+        let mut blank_lines = 0u64;
+        let mut comment_lines = 0u64;
+        let mut code_lines = 0u64;
+        let mut test_functions = 0u64;
+        let mut test_lines = 0u64;
+        let mut non_test_lines = 0u64;
+        let mut functions = 0u64;
+        let mut pub_functions = 0u64;
+        let mut non_test_functions = 0u64;
+        let mut todo_count = 0u64;
+        let mut files_with_main = 0u64;
 
         for fm in &report.files {
             files += 1;
-            total_lines += fm.total_lines;
-            blank_lines += fm.blank_lines;
-            comment_lines += fm.comment_lines;
-            code_lines += fm.code_lines;
-            test_functions += fm.test_functions;
-            test_lines += fm.test_lines;
-            non_test_lines += fm.non_test_lines;
-            functions += fm.functions;
-            pub_functions += fm.pub_functions;
-            non_test_functions += fm.non_test_functions;
-            todo_count += fm.todo_count;
+            total_lines += fm.total_lines as u64;
+            blank_lines += fm.blank_lines as u64;
+            comment_lines += fm.comment_lines as u64;
+            code_lines += fm.code_lines as u64;
+            test_functions += fm.test_functions as u64;
+            test_lines += fm.test_lines as u64;
+            non_test_lines += fm.non_test_lines as u64;
+            functions += fm.functions as u64;
+            pub_functions += fm.pub_functions as u64;
+            non_test_functions += fm.non_test_functions as u64;
+            todo_count += fm.todo_count as u64;
             if fm.has_main {
                 files_with_main += 1;
             }
@@ -215,4 +1580,517 @@ mod tests {
             "test_code_percentage mismatch: expected {expected_pct}, got {actual_pct}"
         );
     }
+
+    #[test]
+    fn longest_functions_are_ranked_with_spans() {
+        let root = PathBuf::from("tests/fixtures/function_spans");
+        let project = Project::from_root(root).expect("project should be valid");
+
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+        let ranked = longest_functions(&report.files, 3);
+
+        let summary: Vec<(&str, usize, usize, usize)> = ranked
+            .iter()
+            .map(|f| (f.name.as_str(), f.start_line, f.end_line, f.lines))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("long_helper", 5, 12, 8),
+                ("main", 14, 18, 5),
+                ("short_helper", 1, 3, 3),
+            ]
+        );
+        assert!(ranked.iter().all(|f| f.path.ends_with("src/main.nr")));
+    }
+
+    #[test]
+    fn maintainability_index_matches_hand_computation() {
+        let root = PathBuf::from("tests/fixtures/function_spans");
+        let project = Project::from_root(root).expect("project should be valid");
+
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+        let totals = &report.totals;
+
+        // 4 functions with 3 + 7 + 5 + 3 = 18 code lines; one `if` in long_helper.
+        assert_eq!(totals.functions, 4);
+        assert_eq!(totals.complexity, 5);
+        assert!((totals.average_function_length - 4.5).abs() < 1e-9);
+
+        // comment density = 1 / (1 + 19) = 0.05
+        // 100 - 0.5 * 4.5 - 5.0 * (1.25 - 1) - 20.0 * (1 - 0.05 / 0.2) = 81.5
+        let mi = totals.maintainability_index;
+        assert!((0.0..=100.0).contains(&mi), "out of range: {mi}");
+        assert!((mi - 81.5).abs() < 1e-9, "unexpected index: {mi}");
+    }
+
+    #[test]
+    fn trailing_comma_style_is_detected() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/trailing_commas"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        // Struct definition, struct literal, call and array closers use trailing commas; one call does not.
+        // The `if`/`else` and function-body braces are not sampled.
+        let main = &report.files[0];
+        assert_eq!(main.closers_with_trailing_comma, 4);
+        assert_eq!(main.closers_without_trailing_comma, 1);
+        assert_eq!(report.totals.predominant_trailing_comma, Some(true));
+
+        let empty = compute_totals(&[]).expect("no files cannot overflow");
+        assert_eq!(empty.predominant_trailing_comma, None);
+    }
+
+    #[test]
+    fn imports_are_classified_as_internal_or_external() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/imports"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let counts: Vec<(&Path, usize, usize)> = report
+            .files
+            .iter()
+            .map(|fm| (fm.path.as_path(), fm.internal_imports, fm.external_imports))
+            .collect();
+        // main.nr: `crate::`, `super::` and the declared `utils` module are internal; `dep::` and both
+        // `std::` imports (one spans several lines) are external.
+        assert_eq!(
+            counts,
+            [
+                (Path::new("src/main.nr"), 3, 3),
+                (Path::new("src/utils.nr"), 1, 1),
+            ]
+        );
+        assert_eq!(report.totals.internal_imports, 4);
+        assert_eq!(report.totals.external_imports, 4);
+    }
+
+    #[test]
+    fn pub_use_reexports_are_counted_separately_from_imports() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/reexports"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let lib = report
+            .files
+            .iter()
+            .find(|fm| fm.path.ends_with("src/lib.nr"))
+            .expect("lib.nr should be listed");
+        // Two `pub use` re-exports among five `use` declarations; `pub(crate) use` does not count.
+        assert_eq!(lib.reexports, 2);
+        assert_eq!(lib.internal_imports + lib.external_imports, 5);
+
+        assert_eq!(report.totals.reexports, 3);
+    }
+
+    #[test]
+    fn reports_the_deepest_and_widest_directories() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/nested_dirs"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        assert_eq!(report.max_directory_depth, 4);
+        assert_eq!(report.widest_directory, Some((PathBuf::from("src/a"), 3)));
+    }
+
+    #[test]
+    fn files_without_tests_lists_uncovered_source_files() {
+        let mut project = Project::from_root(PathBuf::from("tests/fixtures/test_files"))
+            .expect("project should be valid");
+        project.walk.all = true;
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        // `hash.nr` has a sibling `hash_test.nr`, `merkle.nr` has `tests/merkle.nr`; `main.nr` has neither.
+        assert_eq!(
+            files_without_tests(&report.files),
+            [PathBuf::from("src/main.nr")]
+        );
+    }
+
+    #[test]
+    fn commented_out_attributes_are_disabled_not_tests() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/disabled_attributes"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        assert_eq!(report.totals.disabled_attributes, 2);
+        assert_eq!(report.totals.test_functions, 1);
+        let skipped = report.files[0]
+            .function_metrics
+            .iter()
+            .find(|f| f.name == "test_skipped")
+            .expect("test_skipped should be listed");
+        assert!(!skipped.is_test);
+    }
+
+    #[test]
+    fn reports_the_functions_per_file_distribution() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/project_metrics"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let max = report
+            .files
+            .iter()
+            .map(|fm| fm.functions)
+            .max()
+            .expect("fixture has files");
+        let first_max = report
+            .files
+            .iter()
+            .find(|fm| fm.functions == max)
+            .expect("a file has the most functions");
+        assert_eq!(
+            report.totals.max_functions_in_file,
+            Some((first_max.path.clone(), max))
+        );
+        assert_eq!(
+            report.totals.mean_functions_per_file,
+            report.totals.functions as f64 / report.files.len() as f64
+        );
+
+        let empty = compute_totals(&[]).expect("no files should not overflow");
+        assert_eq!(empty.mean_functions_per_file, 0.0);
+        assert_eq!(empty.max_functions_in_file, None);
+
+        let no_functions = compute_totals(&[FileMetrics {
+            path: PathBuf::from("src/consts.nr"),
+            ..Default::default()
+        }])
+        .expect("totals should compute");
+        assert_eq!(no_functions.max_functions_in_file, None);
+    }
+
+    #[test]
+    fn parallel_sink_streams_files_in_path_order() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/project_metrics"))
+            .expect("project should be valid");
+        let sequential = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let paths: Vec<PathBuf> = sequential.files.iter().map(|fm| fm.path.clone()).collect();
+        for threads in [2, 3, 16] {
+            let options = AnalysisOptions {
+                parallel: Some(threads),
+                ..Default::default()
+            };
+            let mut streamed = Vec::new();
+            let totals = analyze_project_with_sink(&project, &options, |fm| {
+                streamed.push(fm.path);
+                Ok(())
+            })
+            .expect("parallel analysis should succeed");
+            assert_eq!(streamed, paths, "threads = {threads}");
+            assert_eq!(totals.code_lines, sequential.totals.code_lines);
+        }
+
+        let options = AnalysisOptions {
+            parallel: Some(2),
+            ..Default::default()
+        };
+
+        let mut seen = 0;
+        let err = analyze_project_with_sink(&project, &options, |_| {
+            seen += 1;
+            bail!("sink full")
+        })
+        .expect_err("a sink error should stop the analysis");
+        assert_eq!(err.to_string(), "sink full");
+        assert_eq!(seen, 1);
+    }
+
+    #[test]
+    fn grouped_totals_sum_back_to_the_combined_totals() {
+        let mut project = Project::from_root(PathBuf::from("tests/fixtures/inline_tests"))
+            .expect("project should be valid");
+        project.walk.all = true;
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let (production, tests) =
+            grouped_totals(&report.files).expect("totals should not overflow");
+
+        assert_eq!((production.files, tests.files), (2, 1));
+        assert!(production.code_lines > 0 && tests.code_lines > 0);
+        assert_eq!(
+            production.code_lines + tests.code_lines,
+            report.totals.code_lines
+        );
+        assert_eq!(
+            production.test_lines + tests.test_lines,
+            report.totals.test_lines
+        );
+    }
+
+    #[test]
+    fn empty_files_are_listed_and_counted() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/empty_files"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let empty: Vec<(&Path, bool)> = report
+            .files
+            .iter()
+            .map(|fm| (fm.path.as_path(), fm.is_empty))
+            .collect();
+        assert_eq!(
+            empty,
+            [
+                (Path::new("src/blank.nr"), true),
+                (Path::new("src/empty.nr"), true),
+                (Path::new("src/main.nr"), false),
+            ]
+        );
+        assert_eq!(report.totals.empty_files, 2);
+        assert_eq!(report.totals.files, 3);
+    }
+
+    #[test]
+    fn ignored_files_do_not_contribute_to_totals() {
+        let root = PathBuf::from("tests/fixtures/ignore_file");
+        let project = Project::from_root(root).expect("project should be valid");
+
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let ignored = report
+            .files
+            .iter()
+            .find(|fm| fm.path.ends_with("src/generated.nr"))
+            .expect("ignored file should still be listed");
+        assert!(ignored.ignored, "expected src/generated.nr to be ignored");
+
+        let main = report
+            .files
+            .iter()
+            .find(|fm| fm.path.ends_with("src/main.nr"))
+            .expect("src/main.nr should be listed");
+        assert!(!main.ignored, "expected src/main.nr not to be ignored");
+
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.totals.files, 1);
+        assert_eq!(report.totals.ignored_files, 1);
+        assert_eq!(report.totals.total_lines, main.total_lines as u64);
+        assert_eq!(report.totals.code_lines, main.code_lines as u64);
+        assert_eq!(report.totals.functions, main.functions as u64);
+        assert_eq!(report.totals.todo_count, main.todo_count as u64);
+    }
+
+    #[test]
+    fn markdown_noir_fences_are_analyzed_when_enabled() {
+        let root = PathBuf::from("tests/fixtures/markdown_docs");
+        let project = Project::from_root(root).expect("project should be valid");
+
+        let default = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+        assert_eq!(default.files.len(), 1, "Markdown is skipped by default");
+
+        let options = AnalysisOptions {
+            scan_markdown: true,
+            ..Default::default()
+        };
+        let report = analyze_project(&project, &options).expect("analyze_project should succeed");
+
+        let guide = report
+            .files
+            .iter()
+            .find(|fm| fm.path.ends_with("docs/guide.md"))
+            .expect("docs/guide.md should be listed");
+        assert!(guide.is_embedded);
+        assert_eq!(guide.code_lines, 3);
+        assert_eq!(guide.comment_lines, 1);
+        assert_eq!(guide.functions, 1);
+        assert!(guide.has_main);
+
+        assert_eq!(report.totals.files, 1);
+        assert_eq!(report.totals.embedded_files, 1);
+        assert_eq!(report.totals.code_lines, 6);
+    }
+
+    #[test]
+    fn totals_are_summed_past_the_32_bit_boundary() {
+        let near_max = u32::MAX as usize - 1;
+        let file = |path: &str| FileMetrics {
+            path: PathBuf::from(path),
+            total_lines: near_max,
+            code_lines: near_max,
+            ..Default::default()
+        };
+
+        let totals = compute_totals(&[file("src/a.nr"), file("src/b.nr"), file("src/c.nr")])
+            .expect("u64 totals should not overflow");
+
+        assert_eq!(totals.files, 3);
+        assert_eq!(totals.total_lines, 3 * (u32::MAX as u64 - 1));
+        assert_eq!(totals.code_lines, 3 * (u32::MAX as u64 - 1));
+        assert!(totals.total_lines > u32::MAX as u64);
+    }
+
+    #[test]
+    fn overflowing_totals_are_an_error() {
+        let mut total = u64::MAX - 1;
+        assert!(add(&mut total, 1, "code_lines").is_ok());
+        assert_eq!(total, u64::MAX);
+
+        let err = add(&mut total, 1, "code_lines").unwrap_err();
+        assert!(err.to_string().contains("code_lines"));
+    }
+
+    #[test]
+    fn counts_distinct_modules_and_detects_lib() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/modules"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        // lib.nr, foo.nr, bar/mod.nr, bar/foo.nr, baz.nr, foo_test.nr -> lib, foo, bar, baz
+        assert_eq!(report.totals.files, 6);
+        assert_eq!(report.totals.module_count, 4);
+        assert!(report.totals.has_lib_or_main);
+
+        let no_lib = Project::from_root(PathBuf::from("tests/fixtures/file_metrics"))
+            .expect("project should be valid");
+        let report = analyze_project(&no_lib, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+        assert!(
+            !report.totals.has_lib_or_main,
+            "file_metrics only has src/metrics.nr"
+        );
+    }
+
+    #[test]
+    fn identical_files_are_grouped_as_duplicates() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/duplicates"))
+            .expect("project should be valid");
+        let options = AnalysisOptions {
+            content_hash: true,
+            ..Default::default()
+        };
+        let report = analyze_project(&project, &options).expect("analyze_project should succeed");
+
+        assert_eq!(
+            duplicate_groups(&report.files),
+            vec![vec![
+                PathBuf::from("src/copy.nr"),
+                PathBuf::from("src/original.nr"),
+            ]]
+        );
+    }
+
+    #[test]
+    fn inline_tests_are_flagged_only_in_source_files() {
+        let mut project = Project::from_root(PathBuf::from("tests/fixtures/inline_tests"))
+            .expect("project should be valid");
+        // `tests/` lies outside the source directory.
+        project.walk.all = true;
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let flag = |path: &str| {
+            report
+                .files
+                .iter()
+                .find(|fm| fm.path.ends_with(path))
+                .map(|fm| fm.has_inline_tests)
+                .unwrap_or_else(|| panic!("{path} should be listed"))
+        };
+
+        assert!(flag("src/main.nr"), "main.nr has a co-located test");
+        assert!(!flag("src/helpers.nr"), "helpers.nr has no tests");
+        assert!(!flag("tests/helpers_test.nr"), "test files are not inline");
+        assert_eq!(report.totals.files_with_inline_tests, 1);
+    }
+
+    #[test]
+    fn paths_can_be_made_relative_to_an_ancestor() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/inline_tests"))
+            .expect("project should be valid");
+        let mut report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        report
+            .make_paths_relative_to(Path::new("tests"))
+            .expect("tests/ contains the fixture");
+        assert_eq!(
+            report.files[0].path,
+            Path::new("fixtures/inline_tests/src/helpers.nr")
+        );
+
+        let err = report
+            .make_paths_relative_to(Path::new("tests/fixtures/simple_noir"))
+            .expect_err("a sibling directory does not contain the files");
+        assert!(err.to_string().contains("is not under"), "{err}");
+    }
+
+    #[test]
+    fn rewrite_paths_strips_only_matching_prefixes() {
+        let mut project = Project::from_root(PathBuf::from("tests/fixtures/inline_tests"))
+            .expect("project should be valid");
+        // `tests/` lies outside the source directory.
+        project.walk.all = true;
+        let mut report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        report.rewrite_paths(Some(Path::new("src")), Some(Path::new("pkg")));
+
+        let paths: Vec<&Path> = report.files.iter().map(|fm| fm.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("pkg/helpers.nr"),
+                Path::new("pkg/main.nr"),
+                Path::new("pkg/tests/helpers_test.nr"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_names_tests_and_should_fail() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/named_tests"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let tests = test_list(&report.files);
+        let summary: Vec<(&str, &str, bool)> = tests
+            .iter()
+            .map(|t| (t.path.to_str().unwrap(), t.name.as_str(), t.should_fail))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("src/main.nr", "test_double", false),
+                ("src/main.nr", "test_double_is_not_one", true),
+                ("src/main.nr", "test_with_reason", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_percentage_is_clamped_for_inconsistent_metrics() {
+        let inconsistent = FileMetrics {
+            path: PathBuf::from("src/odd.nr"),
+            code_lines: 4,
+            test_lines: 6,
+            ..Default::default()
+        };
+
+        let totals = compute_totals(&[inconsistent]).expect("totals should compute");
+        assert_eq!(totals.test_code_percentage, 100.0);
+
+        assert_eq!(test_code_percentage(0, 0), 0.0);
+        assert_eq!(test_code_percentage(1, 4), 25.0);
+    }
 }
@@ -0,0 +1,203 @@
+use crate::analysis::file::FileMetrics;
+use anyhow::{Context, Result, bail};
+use glob::Pattern;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the per-file budget definitions, looked up in the project root.
+pub const BUDGETS_FILE_NAME: &str = "budgets.toml";
+
+/// Per-file metric ceilings read from `budgets.toml`.
+///
+/// ```toml
+/// [[budget]]
+/// glob = "src/**/*.nr"
+/// max_code_lines = 300
+/// max_function_complexity = 10
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Budgets {
+    /// Budgets in declaration order; a file matching several globs must satisfy all of them.
+    pub budget: Vec<Budget>,
+}
+
+/// Ceilings for the files matching one glob.
+///
+/// Every limit is optional; only configured limits are checked.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    /// Glob matched against each file's reported path (e.g. `src/**/*.nr`).
+    pub glob: String,
+
+    /// Maximum [`FileMetrics::code_lines`].
+    pub max_code_lines: Option<usize>,
+
+    /// Maximum [`FileMetrics::complexity`] (sum over the file's functions).
+    pub max_complexity: Option<usize>,
+
+    /// Maximum [`FileMetrics::max_function_complexity`].
+    pub max_function_complexity: Option<usize>,
+}
+
+/// A file metric that exceeds its budget.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BudgetViolation {
+    /// Path of the offending file.
+    pub path: PathBuf,
+
+    /// Glob of the budget that was exceeded.
+    pub glob: String,
+
+    /// Limit name as written in the budget (e.g. `max_code_lines`).
+    pub metric: String,
+
+    /// Configured limit.
+    pub limit: usize,
+
+    /// Actual value measured for the file.
+    pub actual: usize,
+}
+
+impl Budgets {
+    /// Parse budgets from TOML source, validating every glob.
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let budgets: Budgets = toml::from_str(source)?;
+        for budget in &budgets.budget {
+            Pattern::new(&budget.glob)
+                .with_context(|| format!("invalid glob `{}`", budget.glob))?;
+        }
+        Ok(budgets)
+    }
+
+    /// Load the budgets for a project.
+    ///
+    /// - If `explicit` is given, that file must exist and is used.
+    /// - Otherwise `budgets.toml` in `project_root` is used when present.
+    /// - Otherwise `None` is returned and no budgets are checked.
+    pub fn load(project_root: &Path, explicit: Option<&Path>) -> Result<Option<Self>> {
+        let path = match explicit {
+            Some(path) => {
+                if !path.is_file() {
+                    bail!("Budget file {} does not exist", path.display());
+                }
+                path.to_path_buf()
+            }
+            None => {
+                let path = project_root.join(BUDGETS_FILE_NAME);
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                path
+            }
+        };
+
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read budgets {}", path.display()))?;
+        let budgets = Budgets::from_toml(&source)
+            .with_context(|| format!("invalid budgets {}", path.display()))?;
+        Ok(Some(budgets))
+    }
+}
+
+/// Check every file against every budget whose glob matches its path.
+///
+/// Violations are ordered by file, then by budget and limit declaration order. Ignored files are skipped.
+pub fn evaluate_budgets(files: &[FileMetrics], budgets: &Budgets) -> Vec<BudgetViolation> {
+    let patterns: Vec<Pattern> = budgets
+        .budget
+        .iter()
+        .filter_map(|b| Pattern::new(&b.glob).ok())
+        .collect();
+
+    let mut violations = Vec::new();
+
+    for fm in files.iter().filter(|fm| !fm.ignored) {
+        for (budget, pattern) in budgets.budget.iter().zip(&patterns) {
+            if !pattern.matches_path(&fm.path) {
+                continue;
+            }
+
+            let mut check = |metric: &str, limit: Option<usize>, actual: usize| {
+                if let Some(limit) = limit
+                    && actual > limit
+                {
+                    violations.push(BudgetViolation {
+                        path: fm.path.clone(),
+                        glob: budget.glob.clone(),
+                        metric: metric.to_string(),
+                        limit,
+                        actual,
+                    });
+                }
+            };
+
+            check("max_code_lines", budget.max_code_lines, fm.code_lines);
+            check("max_complexity", budget.max_complexity, fm.complexity);
+            check(
+                "max_function_complexity",
+                budget.max_function_complexity,
+                fm.max_function_complexity,
+            );
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::options::AnalysisOptions;
+    use crate::analysis::project::analyze_project;
+    use crate::project::Project;
+
+    #[test]
+    fn reports_files_over_budget() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/project_metrics"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        let main = report
+            .files
+            .iter()
+            .find(|fm| fm.path.ends_with("src/main.nr"))
+            .expect("main.nr should be listed");
+
+        let budgets = Budgets::from_toml(&format!(
+            r#"
+            [[budget]]
+            glob = "src/main.nr"
+            max_code_lines = {}
+
+            [[budget]]
+            glob = "src/*.nr"
+            max_code_lines = 1000
+            "#,
+            main.code_lines - 1
+        ))
+        .expect("budgets should parse");
+
+        let violations = evaluate_budgets(&report.files, &budgets);
+        assert_eq!(
+            violations,
+            vec![BudgetViolation {
+                path: PathBuf::from("src/main.nr"),
+                glob: "src/main.nr".to_string(),
+                metric: "max_code_lines".to_string(),
+                limit: main.code_lines - 1,
+                actual: main.code_lines,
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_globs_and_unknown_keys() {
+        assert!(Budgets::from_toml("[[budget]]\nglob = \"src/[\"\n").is_err());
+        assert!(Budgets::from_toml("[[budget]]\nglob = \"*\"\nmax_lines = 1\n").is_err());
+    }
+}
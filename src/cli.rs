@@ -1,18 +1,78 @@
-use clap::{Parser, ValueEnum};
+use crate::analysis::options::{AnalysisOptions, AnalysisPreset, CountRegex};
+use crate::diff::BaselineFormat;
+use crate::output::{ColorTheme, FileField, HumanOptions, JsonOptions};
+use crate::project::{DEFAULT_MAX_FILES, WalkOptions};
+use anyhow::{Result, bail};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 /// Command-line arguments for noir-metrics.
 ///
 /// Example:
-///   noir-metrics . --format json --output metrics.json
+///   noir-metrics analyze . --format json --output metrics.json
+///
+/// Without a subcommand the arguments are those of `analyze`, so `noir-metrics <PROJECT_ROOT> [FLAGS]` keeps
+/// working.
 #[derive(Debug, Parser)]
-#[command(name = "noir-metrics")]
+#[command(name = "noir-metrics", args_conflicts_with_subcommands = true)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub analyze: AnalyzeArgs,
+}
+
+/// Subcommands of noir-metrics.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Analyze a Noir project (the default when no subcommand is given)
+    Analyze(Box<AnalyzeArgs>),
+
+    /// Compare two saved reports (`--format json` or `--format csv`) file by file
+    Diff {
+        /// Baseline report
+        old: PathBuf,
+
+        /// Current report
+        new: PathBuf,
+
+        /// Output format (`human` or `json`)
+        #[arg(long, value_enum, value_name = "FORMAT", default_value = "human")]
+        format: OutputFormat,
+
+        /// Ignore regressions of at most PCT percent of the old value, per metric
+        #[arg(long, value_name = "PCT", default_value_t = 0.0)]
+        baseline_tolerance: f64,
+
+        /// Compare reports whose schema_version differs from this tool's (best effort)
+        #[arg(long)]
+        allow_schema_mismatch: bool,
+    },
+
+    /// Print the JSON Schema of the `--format json` report
+    Schema,
+
+    /// Merge saved `--format json` reports into one JSON report (paths are namespaced by project root)
+    Merge {
+        /// Reports to merge
+        #[arg(required = true, value_name = "FILE")]
+        files: Vec<PathBuf>,
+
+        /// Write the merged report to this file instead of stdout
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Arguments of `analyze` (and of the bare `noir-metrics` invocation).
+#[derive(Debug, Clone, Args)]
+pub struct AnalyzeArgs {
     /// Path to the Noir project root (default: current directory)
     #[arg(value_name = "PROJECT_ROOT", default_value = ".")]
     pub project_root: PathBuf,
 
-    /// Output format (`human` or `json`)
+    /// Output format
     #[arg(long, value_enum, value_name = "FORMAT")]
     pub format: Option<OutputFormat>,
 
@@ -20,17 +80,432 @@ pub struct Cli {
     #[arg(long, hide = true)]
     pub json: bool,
 
-    /// Write JSON output to this file instead of stdout
+    /// Only emit the `tool` and `totals` blocks in JSON output (omit `files` and all per-file data)
+    #[arg(long)]
+    pub totals_only: bool,
+
+    /// Only keep matching metric fields in JSON `totals` and `files[*]` (glob, repeatable; overrides `[fields] allow`)
+    #[arg(long, value_name = "GLOB")]
+    pub allow_field: Vec<String>,
+
+    /// Drop matching metric fields from JSON `totals` and `files[*]` (glob, repeatable; overrides `[fields] deny`)
+    #[arg(long, value_name = "GLOB")]
+    pub deny_field: Vec<String>,
+
+    /// Decimal places for percentages and ratios in human and JSON output (default: 2 in human output, full
+    /// precision in JSON)
+    #[arg(long, value_name = "DIGITS", value_parser = clap::value_parser!(u32).range(0..=15))]
+    pub round: Option<u32>,
+
+    /// Stamp JSON output with a `KEY=VALUE` tag in `meta.tags` (repeatable; e.g. the git SHA or branch)
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_tag)]
+    pub tag: Vec<(String, String)>,
+
+    /// Free-form run label, stored as the `label` tag in `meta.tags` (overrides `--tag label=...`)
+    #[arg(long, value_name = "LABEL")]
+    pub label: Option<String>,
+
+    /// Keep running and rewrite `--output` (atomically) whenever a `.nr` file or `Nargo.toml` changes
+    #[arg(long, requires = "output")]
+    pub watch: bool,
+
+    /// Write machine-readable output to this file instead of stdout
     #[arg(long)]
     pub output: Option<PathBuf>,
 
+    /// List the N longest functions project-wide with their line spans
+    #[arg(long, value_name = "N")]
+    pub longest_functions: Option<usize>,
+
+    /// Compare per-file metrics against a previously written JSON or CSV report
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<PathBuf>,
+
+    /// Compare against `.noir-metrics-baseline.json` in the project root when it exists (report only otherwise)
+    #[arg(long, conflicts_with = "baseline")]
+    pub baseline_auto: bool,
+
+    /// With --baseline-auto, write the report to `.noir-metrics-baseline.json` when no baseline exists yet
+    #[arg(long, requires = "baseline_auto")]
+    pub baseline_auto_write: bool,
+
+    /// Exit with an error if any file regressed against the baseline (more TODOs or fewer test lines)
+    #[arg(long)]
+    pub fail_on_regression: bool,
+
+    /// With --fail-on-regression, ignore regressions of at most PCT percent of the baseline value, per metric
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = 0.0,
+        requires = "fail_on_regression"
+    )]
+    pub baseline_tolerance: f64,
+
+    /// Only list files whose metrics differ from the baseline (totals still cover every file)
+    #[arg(long)]
+    pub only_changed: bool,
+
+    /// Format of the `--baseline` file (`auto` picks CSV for `.csv` files, JSON otherwise)
+    #[arg(long, value_enum, default_value_t = BaselineFormat::Auto)]
+    pub baseline_format: BaselineFormat,
+
+    /// Compare against a JSON baseline whose `schema_version` differs from this tool's (warn instead of failing)
+    #[arg(long)]
+    pub allow_schema_mismatch: bool,
+
+    /// Only analyze `.nr` files directly in the project root (do not descend into subdirectories)
+    #[arg(long)]
+    pub no_recursive: bool,
+
+    /// Group digits in human output with thousands separators (e.g. `1,234,567`)
+    #[arg(long)]
+    pub human_numbers: bool,
+
+    /// Start human output with a `# format_version: N` line identifying the summary layout
+    #[arg(long)]
+    pub stable_human: bool,
+
+    /// Print the human output format version and exit
+    #[arg(long, hide = true)]
+    pub print_format_version: bool,
+
+    /// Attribute code lines to authors via `git blame` (slow; requires a git repository)
+    #[arg(long)]
+    pub by_author: bool,
+
+    /// Count commits touching each file via `git log` (slow; skipped with a warning outside a git repository)
+    #[arg(long)]
+    pub git_churn: bool,
+
+    /// Only count commits more recent than DATE for --git-churn (any `git log --since` value, e.g. `6.months`)
+    #[arg(long, value_name = "DATE", requires = "git_churn")]
+    pub since: Option<String>,
+
+    /// Print the JSON Schema of the `--format json` report and exit
+    #[arg(long)]
+    pub print_schema: bool,
+
+    /// Validate a previously written JSON report against the current schema and exit
+    #[arg(long, value_name = "FILE")]
+    pub validate_schema: Option<PathBuf>,
+
+    /// Walk the whole project root instead of only the source directory (`src/` or `[package] src` in Nargo.toml)
+    #[arg(long)]
+    pub all: bool,
+
+    /// Include hidden (dot-prefixed) files and directories in the walk (skipped by default)
+    #[arg(long)]
+    pub include_hidden: bool,
+
+    /// Also analyze vendored path dependencies declared in `Nargo.toml` (skipped by default)
+    #[arg(long)]
+    pub include_deps: bool,
+
+    /// Analyze files on N threads (0 = one per CPU, 1 = sequential, the default); output order does not depend on N
+    #[arg(long, value_name = "N")]
+    pub parallel: Option<usize>,
+
+    /// Fail if more than N `.nr` files are found (0 = unlimited)
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_FILES)]
+    pub max_files: usize,
+
+    /// Configuration file (default: `noir-metrics.toml` in the project root, if present)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Path to the project's `Nargo.toml`; its directory is analyzed instead of PROJECT_ROOT
+    #[arg(long, value_name = "FILE", conflicts_with = "project_root")]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Exit with an error if any target of the configured `[profile]` is not met
+    #[arg(long)]
+    pub fail_on_profile: bool,
+
+    /// Exit with an error if the project has no `#[test]` functions
+    #[arg(long)]
+    pub fail_if_no_tests: bool,
+
+    /// Exit with an error if a source file with functions has no `<stem>_test.nr` sibling or `tests/<stem>.nr`
+    #[arg(long)]
+    pub require_test_files: bool,
+
+    /// Exit with an error if the given check has findings (repeatable)
+    #[arg(long, value_enum, value_name = "CHECK")]
+    pub deny: Vec<DenyCheck>,
+
+    /// Collect the names of public items (`pub fn`, `pub struct`, ...) per file
+    #[arg(long)]
+    pub list_public: bool,
+
+    /// Flag single-line `assert_eq(...)` calls with other than 2 or 3 arguments per file (heuristic)
+    #[arg(long)]
+    pub lint_asserts: bool,
+
+    /// Tally which traits are derived on structs (`#[derive(...)]`) per file and in totals
+    #[arg(long)]
+    pub list_derives: bool,
+
+    /// Tally primitive types (`Field`, `u1`..`u64`, `i8`..`i64`, `bool`, `str`) used on code lines per file and in totals
+    #[arg(long)]
+    pub type_usage: bool,
+
+    /// Also analyze Noir code in ```noir / ```nr fences of Markdown (`.md`) files
+    #[arg(long)]
+    pub scan_markdown: bool,
+
+    /// Print the per-line classification (blank/comment/code/test) and brace depth of a single `.nr` file
+    #[arg(long, value_name = "FILE")]
+    pub explain: Option<PathBuf>,
+
+    /// Print each file's contribution to a total (a per-file metric such as `code_lines`), largest first with a
+    /// cumulative percentage, instead of the report
+    #[arg(long, value_name = "METRIC", conflicts_with_all = ["format", "json", "output"])]
+    pub explain_totals: Option<String>,
+
+    /// Comma-separated per-file columns for human output, e.g. `path,code,test_pct,todos` (default: all but `test_pct`)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FIELDS")]
+    pub fields: Vec<FileField>,
+
+    /// Sort per-file human/table output by this column (`path`/`indent` ascending, others descending)
+    #[arg(long, value_enum, value_name = "FIELD")]
+    pub sort: Option<FileField>,
+
+    /// Only list the first N files in human/table output (after `--sort`)
+    #[arg(long, value_name = "N")]
+    pub top: Option<usize>,
+
+    /// When to color human output (`auto` colors a terminal unless `NO_COLOR` is set)
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Color palette for human output
+    #[arg(long, value_enum, value_name = "THEME", default_value_t = ColorTheme::Dark)]
+    pub color_theme: ColorTheme,
+
+    /// Test-code percentage below which human output colors it yellow
+    #[arg(long, value_name = "PCT", default_value_t = 50.0)]
+    pub warn_test_pct: f64,
+
+    /// Test-code percentage below which human output colors it red
+    #[arg(long, value_name = "PCT", default_value_t = 20.0)]
+    pub bad_test_pct: f64,
+
+    /// Hash file contents and report groups of identical files
+    #[arg(long)]
+    pub find_duplicates: bool,
+
+    /// Count code lines matching a regex as a custom metric (repeatable), e.g. `asserts=^\s*assert`
+    #[arg(long, value_name = "NAME=PATTERN")]
+    pub count_regex: Vec<CountRegex>,
+
+    /// Word counted as a TODO marker (repeatable, case-insensitive; default: `todo`, `fixme`)
+    #[arg(long, value_name = "WORD")]
+    pub todo_marker: Vec<String>,
+
+    /// Also report totals over non-test files and over test files (`totals_production`, `totals_tests`)
+    #[arg(long)]
+    pub group_tests: bool,
+
+    /// List every test function with its file and whether it is `should_fail`
+    #[arg(long)]
+    pub list_tests: bool,
+
+    /// Merge saved `--format json` reports instead of analyzing a project (paths are namespaced by project root)
+    #[arg(long, num_args = 1.., value_name = "FILE")]
+    pub merge: Vec<PathBuf>,
+
+    /// Report file paths relative to this directory (e.g. the repository root) instead of the project root
+    #[arg(long, value_name = "DIR")]
+    pub relative_to: Option<PathBuf>,
+
+    /// Remove this leading directory from reported file paths (paths outside it are kept as-is)
+    #[arg(long, value_name = "DIR")]
+    pub path_prefix_strip: Option<PathBuf>,
+
+    /// Prepend this directory to reported file paths (applied after `--path-prefix-strip`)
+    #[arg(long, value_name = "DIR")]
+    pub path_prefix_add: Option<PathBuf>,
+
+    /// Count lines longer than N characters in `long_line_count` (default: 100)
+    #[arg(long, value_name = "N")]
+    pub max_line_length: Option<usize>,
+
+    /// Also print the human summary to stderr when writing JSON/CSV (stdout stays machine-readable)
+    #[arg(long)]
+    pub also_human: bool,
+
+    /// Also print compact one-line JSON totals (`files`, `code_lines`, `test_code_percentage`) to stderr
+    #[arg(long)]
+    pub summary_json: bool,
+
+    /// Append a one-line JSON record (timestamp, schema version, totals) to FILE on each run (NDJSON time series)
+    #[arg(long, value_name = "FILE")]
+    pub json_stream_to: Option<PathBuf>,
+
+    /// Add `todos_per_kloc`, `complexity_per_kloc` and `asserts_per_kloc` densities (per 1000 code lines) to files and totals
+    #[arg(long)]
+    pub normalize: bool,
+
+    /// Stream one JSON line per file as it is analyzed, then a final totals line (`{"file": ...}`, `{"totals": ...}`)
+    #[arg(long, conflicts_with_all = ["format", "json", "merge", "discover", "output"])]
+    pub ndjson_per_file: bool,
+
+    /// Analyze `.nr` source read from stdin as a single file (no `Nargo.toml` needed)
+    #[arg(long, conflicts_with_all = ["merge", "discover", "manifest_path", "ndjson_per_file"])]
+    pub stdin: bool,
+
+    /// Path reported for the `--stdin` file (default: `stdin.nr`); also drives test-file detection
+    #[arg(long, value_name = "PATH", requires = "stdin")]
+    pub name: Option<PathBuf>,
+
+    /// Analyze every directory matching this glob that contains a `Nargo.toml` (e.g. `packages/*`)
+    #[arg(long, value_name = "GLOB")]
+    pub discover: Option<String>,
+
+    /// Per-file budget file (default: `budgets.toml` in the project root, if present); exits non-zero on violations
+    #[arg(long, value_name = "FILE")]
+    pub budgets: Option<PathBuf>,
+
+    /// Analysis preset: `fast` (core counts, no complexity), `full` (all general metrics) or `audit` (security counts)
+    #[arg(long, value_enum, value_name = "PRESET")]
+    pub profile: Option<AnalysisPreset>,
+
     /// Verbose logging
     #[arg(short, long)]
     pub verbose: bool,
 }
 
+impl AnalyzeArgs {
+    /// Project root to analyze: the directory of `--manifest-path` when given, otherwise PROJECT_ROOT.
+    ///
+    /// Fails if the manifest is not named `Nargo.toml` or does not exist.
+    pub fn project_root(&self) -> Result<PathBuf> {
+        let Some(manifest) = &self.manifest_path else {
+            return Ok(self.project_root.clone());
+        };
+
+        if manifest.file_name().is_none_or(|name| name != "Nargo.toml") {
+            bail!(
+                "--manifest-path must point to a Nargo.toml file, got {}",
+                manifest.display()
+            );
+        }
+        if !manifest.is_file() {
+            bail!("Manifest {} does not exist", manifest.display());
+        }
+
+        Ok(match manifest.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        })
+    }
+
+    /// Build analysis options from the parsed flags.
+    pub fn analysis_options(&self) -> AnalysisOptions {
+        let mut options = self
+            .profile
+            .map(AnalysisOptions::preset)
+            .unwrap_or_default();
+
+        options.walk = WalkOptions {
+            recursive: !self.no_recursive,
+            all: self.all,
+            include_hidden: self.include_hidden,
+            include_deps: self.include_deps,
+            max_files: self.max_files,
+        };
+        options.list_public |= self.list_public;
+        options.lint_asserts |= self.lint_asserts;
+        options.list_derives |= self.list_derives;
+        options.type_usage |= self.type_usage;
+        options.scan_markdown |= self.scan_markdown;
+        options.content_hash |= self.find_duplicates;
+        options
+            .count_regexes
+            .extend(self.count_regex.iter().cloned());
+        options.todo_markers = self.todo_marker.clone();
+        options.max_line_length = self.max_line_length;
+        options.parallel = self.parallel;
+        options
+    }
+
+    /// Build JSON output options from the parsed flags.
+    pub fn json_options(&self) -> JsonOptions {
+        JsonOptions {
+            totals_only: self.totals_only,
+            allow_fields: self.allow_field.clone(),
+            deny_fields: self.deny_field.clone(),
+            round: self.round,
+            tags: self
+                .tag
+                .iter()
+                .cloned()
+                .chain(self.label.clone().map(|label| ("label".to_string(), label)))
+                .collect(),
+        }
+    }
+
+    /// Build human summary options from the parsed flags; `is_terminal` tells whether the output stream is
+    /// a terminal (for `--color auto`).
+    pub fn human_options(&self, is_terminal: bool) -> HumanOptions {
+        HumanOptions {
+            human_numbers: self.human_numbers,
+            format_version: self.stable_human,
+            fields: self.fields.clone(),
+            sort: self.sort,
+            top: self.top,
+            color: match self.color {
+                ColorChoice::Always => true,
+                ColorChoice::Never => false,
+                ColorChoice::Auto => is_terminal && std::env::var_os("NO_COLOR").is_none(),
+            },
+            color_theme: self.color_theme,
+            warn_test_pct: self.warn_test_pct,
+            bad_test_pct: self.bad_test_pct,
+            round: self.round,
+        }
+    }
+}
+
+/// Parse a `--tag KEY=VALUE` argument; the key must be non-empty, the value may be.
+fn parse_tag(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got `{s}`")),
+    }
+}
+
+/// When to color human output (`--color`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is unset
+    Auto,
+    Always,
+    Never,
+}
+
+/// Findings that `--deny` turns into a failing exit status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DenyCheck {
+    /// Debug print calls (`println`, `print`, `dbg`) in any file
+    DebugPrints,
+    /// Empty or all-blank `.nr` files
+    EmptyFiles,
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum OutputFormat {
     Human,
     Json,
+    /// JSON flattened to a single-level object with dotted keys (e.g. `totals.code_lines`, `files[0].path`)
+    FlatJson,
+    /// Per-file metrics as a column-aligned table (honors `--fields`, `--sort` and `--top`)
+    Table,
+    /// Per-file metrics as CSV (one row per file, usable as a `--baseline`)
+    Csv,
+    /// SARIF 2.1.0 log of budget, profile and TODO findings (e.g. for GitHub code scanning)
+    Sarif,
+    /// Per-file code lines as flamegraph folded stacks (`src;gadgets;hash.nr 120`)
+    Folded,
 }
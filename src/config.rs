@@ -0,0 +1,118 @@
+use crate::profile::Profile;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// File name of the project-level configuration, looked up in the project root.
+pub const CONFIG_FILE_NAME: &str = "noir-metrics.toml";
+
+/// Project-level configuration read from `noir-metrics.toml`.
+///
+/// Every section is optional; a missing file behaves like an empty one.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Target profile to evaluate the report against (`[profile]` table).
+    pub profile: Option<Profile>,
+
+    /// Words counted as TODO markers (`todo_markers = ["@todo", "NOTE:"]`); `--todo-marker` takes precedence.
+    pub todo_markers: Option<Vec<String>>,
+
+    /// JSON field filters (`[fields]` table); `--allow-field`/`--deny-field` take precedence.
+    pub fields: Option<FieldFilters>,
+}
+
+/// Metric fields to keep or drop in JSON output, as glob patterns (e.g. `todo_*`).
+///
+/// Applied to `totals` and to every `files[*]` entry.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FieldFilters {
+    /// Only keep these fields (empty keeps all).
+    pub allow: Vec<String>,
+
+    /// Always drop these fields.
+    pub deny: Vec<String>,
+}
+
+impl Config {
+    /// Parse a configuration from TOML source.
+    pub fn from_toml(source: &str) -> Result<Self> {
+        Ok(toml::from_str(source)?)
+    }
+
+    /// Load the configuration for a project.
+    ///
+    /// - If `explicit` is given, that file must exist and is used.
+    /// - Otherwise `noir-metrics.toml` in `project_root` is used when present.
+    /// - Otherwise the default (empty) configuration is returned.
+    pub fn load(project_root: &Path, explicit: Option<&Path>) -> Result<Self> {
+        let path = match explicit {
+            Some(path) => {
+                if !path.is_file() {
+                    bail!("Config file {} does not exist", path.display());
+                }
+                path.to_path_buf()
+            }
+            None => {
+                let path = project_root.join(CONFIG_FILE_NAME);
+                if !path.is_file() {
+                    return Ok(Config::default());
+                }
+                path
+            }
+        };
+
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read config {}", path.display()))?;
+        Config::from_toml(&source).with_context(|| format!("invalid config {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_profile_table() {
+        let config = Config::from_toml(
+            r#"
+            [profile]
+            min_test_code_percentage = 50.0
+            max_function_complexity = 10
+            "#,
+        )
+        .expect("config should parse");
+
+        let profile = config.profile.expect("profile should be present");
+        assert_eq!(profile.min_test_code_percentage, Some(50.0));
+        assert_eq!(profile.max_function_complexity, Some(10));
+        assert_eq!(profile.min_comment_density, None);
+    }
+
+    #[test]
+    fn parses_todo_markers() {
+        let config = Config::from_toml("todo_markers = [\"@todo\", \"JIRA-\"]\n")
+            .expect("config should parse");
+        assert_eq!(
+            config.todo_markers,
+            Some(vec!["@todo".to_string(), "JIRA-".to_string()])
+        );
+    }
+
+    #[test]
+    fn parses_field_filters() {
+        let config = Config::from_toml("[fields]\ndeny = [\"blank_lines\", \"*_per_kloc\"]\n")
+            .expect("config should parse");
+        let fields = config.fields.expect("fields should be present");
+        assert!(fields.allow.is_empty());
+        assert_eq!(fields.deny, ["blank_lines", "*_per_kloc"]);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(Config::from_toml("[profile]\nmin_tests = 1\n").is_err());
+        assert!(Config::from_toml("unknown = true\n").is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::analysis::file::FileMetrics;
+use crate::analysis::project::{MetricsReport, compute_totals};
+use anyhow::{Context, Result, bail};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Per-file columns written by [`to_csv`], in order.
+///
+/// [`read_csv_report`] locates columns through the header row, so their order may differ in input.
+const COLUMNS: &[&str] = &[
+    "path",
+    "is_test_file",
+    "has_inline_tests",
+    "is_embedded",
+    "is_empty",
+    "total_lines",
+    "blank_lines",
+    "comment_lines",
+    "line_comment_lines",
+    "block_comment_lines",
+    "commented_code_lines",
+    "disabled_attributes",
+    "doc_comment_lines",
+    "inner_doc_lines",
+    "outer_doc_lines",
+    "code_lines",
+    "statement_count",
+    "closers_with_trailing_comma",
+    "closers_without_trailing_comma",
+    "mut_binding_count",
+    "array_total_declared_len",
+    "test_functions",
+    "test_lines",
+    "non_test_lines",
+    "functions",
+    "pub_functions",
+    "non_test_functions",
+    "spec_functions",
+    "functions_with_return",
+    "where_clause_count",
+    "internal_imports",
+    "external_imports",
+    "reexports",
+    "unsafe_blocks",
+    "unsafe_lines",
+    "contract_modules",
+    "inline_modules",
+    "public_functions",
+    "private_functions",
+    "has_main",
+    "todo_count",
+    "debug_print_count",
+    "derive_count",
+    "complexity",
+    "max_function_complexity",
+    "leading_blank_lines",
+    "trailing_blank_lines",
+    "max_line_length",
+    "long_line_count",
+    "ignored",
+];
+
+/// Every [`COLUMNS`] value of a file, in column order.
+pub(crate) fn csv_row(fm: &FileMetrics) -> Vec<String> {
+    COLUMNS
+        .iter()
+        .map(|column| field_value(fm, column))
+        .collect()
+}
+
+/// Columns holding a per-file count (every column except `path` and the boolean flags).
+pub(crate) fn numeric_columns() -> impl Iterator<Item = &'static str> {
+    let empty = FileMetrics::default();
+    COLUMNS
+        .iter()
+        .copied()
+        .filter(move |column| field_value(&empty, column).parse::<u64>().is_ok())
+}
+
+/// The value of a [`numeric_columns`] column for a file (`None` for other columns).
+pub(crate) fn numeric_value(fm: &FileMetrics, column: &str) -> Option<u64> {
+    if column == "path" {
+        return None;
+    }
+    field_value(fm, column).parse().ok()
+}
+
+/// Render the per-file metrics of a report as CSV: a header row followed by one row per file.
+///
+/// Project totals are not written; they are recomputed when the CSV is read back.
+pub fn to_csv(report: &MetricsReport) -> String {
+    let mut out = COLUMNS.join(",");
+    out.push('\n');
+
+    for fm in &report.files {
+        let row: Vec<String> = COLUMNS
+            .iter()
+            .map(|column| escape(&field_value(fm, column)))
+            .collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Parse a CSV export (see [`to_csv`]) back into a report with recomputed totals.
+///
+/// Columns are matched by header name; unknown columns are ignored and missing ones default to zero/false.
+/// A `path` column is required. Per-function metrics are not part of the CSV, so derived totals such as
+/// `average_function_length` are zero.
+pub fn read_csv_report(csv: &str) -> Result<MetricsReport> {
+    let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+
+    let Some(header) = lines.next() else {
+        bail!("CSV is empty");
+    };
+    let header = split_row(header);
+    if !header.iter().any(|column| column == "path") {
+        bail!("CSV header has no `path` column");
+    }
+
+    let mut files = Vec::new();
+    for (i, line) in lines.enumerate() {
+        let row = split_row(line);
+        let mut fm = FileMetrics::default();
+
+        for (column, value) in header.iter().zip(&row) {
+            set_field(&mut fm, column, value)
+                .with_context(|| format!("CSV row {}, column `{column}`", i + 2))?;
+        }
+
+        files.push(fm);
+    }
+
+    let totals = compute_totals(&files)?;
+
+    Ok(MetricsReport {
+        totals,
+        files,
+        ..Default::default()
+    })
+}
+
+/// The CSV cell for `column` (one of [`COLUMNS`]).
+fn field_value(fm: &FileMetrics, column: &str) -> String {
+    match column {
+        "path" => fm.path.display().to_string(),
+        "is_test_file" => fm.is_test_file.to_string(),
+        "has_inline_tests" => fm.has_inline_tests.to_string(),
+        "is_embedded" => fm.is_embedded.to_string(),
+        "is_empty" => fm.is_empty.to_string(),
+        "total_lines" => fm.total_lines.to_string(),
+        "blank_lines" => fm.blank_lines.to_string(),
+        "comment_lines" => fm.comment_lines.to_string(),
+        "line_comment_lines" => fm.line_comment_lines.to_string(),
+        "block_comment_lines" => fm.block_comment_lines.to_string(),
+        "commented_code_lines" => fm.commented_code_lines.to_string(),
+        "disabled_attributes" => fm.disabled_attributes.to_string(),
+        "doc_comment_lines" => fm.doc_comment_lines.to_string(),
+        "inner_doc_lines" => fm.inner_doc_lines.to_string(),
+        "outer_doc_lines" => fm.outer_doc_lines.to_string(),
+        "code_lines" => fm.code_lines.to_string(),
+        "statement_count" => fm.statement_count.to_string(),
+        "closers_with_trailing_comma" => fm.closers_with_trailing_comma.to_string(),
+        "closers_without_trailing_comma" => fm.closers_without_trailing_comma.to_string(),
+        "mut_binding_count" => fm.mut_binding_count.to_string(),
+        "array_total_declared_len" => fm.array_total_declared_len.to_string(),
+        "test_functions" => fm.test_functions.to_string(),
+        "test_lines" => fm.test_lines.to_string(),
+        "non_test_lines" => fm.non_test_lines.to_string(),
+        "functions" => fm.functions.to_string(),
+        "pub_functions" => fm.pub_functions.to_string(),
+        "non_test_functions" => fm.non_test_functions.to_string(),
+        "spec_functions" => fm.spec_functions.to_string(),
+        "functions_with_return" => fm.functions_with_return.to_string(),
+        "where_clause_count" => fm.where_clause_count.to_string(),
+        "internal_imports" => fm.internal_imports.to_string(),
+        "external_imports" => fm.external_imports.to_string(),
+        "reexports" => fm.reexports.to_string(),
+        "unsafe_blocks" => fm.unsafe_blocks.to_string(),
+        "unsafe_lines" => fm.unsafe_lines.to_string(),
+        "contract_modules" => fm.contract_modules.to_string(),
+        "inline_modules" => fm.inline_modules.to_string(),
+        "public_functions" => fm.public_functions.to_string(),
+        "private_functions" => fm.private_functions.to_string(),
+        "has_main" => fm.has_main.to_string(),
+        "todo_count" => fm.todo_count.to_string(),
+        "debug_print_count" => fm.debug_print_count.to_string(),
+        "derive_count" => fm.derive_count.to_string(),
+        "complexity" => fm.complexity.to_string(),
+        "max_function_complexity" => fm.max_function_complexity.to_string(),
+        "leading_blank_lines" => fm.leading_blank_lines.to_string(),
+        "trailing_blank_lines" => fm.trailing_blank_lines.to_string(),
+        "max_line_length" => fm.max_line_length.to_string(),
+        "long_line_count" => fm.long_line_count.to_string(),
+        "ignored" => fm.ignored.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Set the field named by `column` from a CSV cell; unknown columns are ignored.
+fn set_field(fm: &mut FileMetrics, column: &str, value: &str) -> Result<()> {
+    match column {
+        "path" => fm.path = PathBuf::from(value),
+        "is_test_file" => fm.is_test_file = parse(value)?,
+        "has_inline_tests" => fm.has_inline_tests = parse(value)?,
+        "is_embedded" => fm.is_embedded = parse(value)?,
+        "is_empty" => fm.is_empty = parse(value)?,
+        "total_lines" => fm.total_lines = parse(value)?,
+        "blank_lines" => fm.blank_lines = parse(value)?,
+        "comment_lines" => fm.comment_lines = parse(value)?,
+        "line_comment_lines" => fm.line_comment_lines = parse(value)?,
+        "block_comment_lines" => fm.block_comment_lines = parse(value)?,
+        "commented_code_lines" => fm.commented_code_lines = parse(value)?,
+        "disabled_attributes" => fm.disabled_attributes = parse(value)?,
+        "doc_comment_lines" => fm.doc_comment_lines = parse(value)?,
+        "inner_doc_lines" => fm.inner_doc_lines = parse(value)?,
+        "outer_doc_lines" => fm.outer_doc_lines = parse(value)?,
+        "code_lines" => fm.code_lines = parse(value)?,
+        "statement_count" => fm.statement_count = parse(value)?,
+        "closers_with_trailing_comma" => fm.closers_with_trailing_comma = parse(value)?,
+        "closers_without_trailing_comma" => fm.closers_without_trailing_comma = parse(value)?,
+        "mut_binding_count" => fm.mut_binding_count = parse(value)?,
+        "array_total_declared_len" => fm.array_total_declared_len = parse(value)?,
+        "test_functions" => fm.test_functions = parse(value)?,
+        "test_lines" => fm.test_lines = parse(value)?,
+        "non_test_lines" => fm.non_test_lines = parse(value)?,
+        "functions" => fm.functions = parse(value)?,
+        "pub_functions" => fm.pub_functions = parse(value)?,
+        "non_test_functions" => fm.non_test_functions = parse(value)?,
+        "spec_functions" => fm.spec_functions = parse(value)?,
+        "functions_with_return" => fm.functions_with_return = parse(value)?,
+        "where_clause_count" => fm.where_clause_count = parse(value)?,
+        "internal_imports" => fm.internal_imports = parse(value)?,
+        "external_imports" => fm.external_imports = parse(value)?,
+        "reexports" => fm.reexports = parse(value)?,
+        "unsafe_blocks" => fm.unsafe_blocks = parse(value)?,
+        "unsafe_lines" => fm.unsafe_lines = parse(value)?,
+        "contract_modules" => fm.contract_modules = parse(value)?,
+        "inline_modules" => fm.inline_modules = parse(value)?,
+        "public_functions" => fm.public_functions = parse(value)?,
+        "private_functions" => fm.private_functions = parse(value)?,
+        "has_main" => fm.has_main = parse(value)?,
+        "todo_count" => fm.todo_count = parse(value)?,
+        "debug_print_count" => fm.debug_print_count = parse(value)?,
+        "derive_count" => fm.derive_count = parse(value)?,
+        "complexity" => fm.complexity = parse(value)?,
+        "max_function_complexity" => fm.max_function_complexity = parse(value)?,
+        "leading_blank_lines" => fm.leading_blank_lines = parse(value)?,
+        "trailing_blank_lines" => fm.trailing_blank_lines = parse(value)?,
+        "max_line_length" => fm.max_line_length = parse(value)?,
+        "long_line_count" => fm.long_line_count = parse(value)?,
+        "ignored" => fm.ignored = parse(value)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn parse<T>(value: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid value `{value}`"))
+}
+
+/// Quote a cell if it contains a separator, quote, or line break (RFC 4180 style).
+fn escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Split one CSV row into cells, honoring double-quoted cells with `""` escapes.
+///
+/// Quoted cells spanning several lines are not supported.
+fn split_row(line: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => cells.push(std::mem::take(&mut cell)),
+            _ => cell.push(ch),
+        }
+    }
+    cells.push(cell);
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_round_trips_per_file_metrics() {
+        let report = MetricsReport {
+            files: vec![
+                FileMetrics {
+                    path: PathBuf::from("src/main.nr"),
+                    code_lines: 12,
+                    test_lines: 4,
+                    todo_count: 1,
+                    has_main: true,
+                    has_inline_tests: true,
+                    ..Default::default()
+                },
+                FileMetrics {
+                    path: PathBuf::from("src/odd,\"name\".nr"),
+                    code_lines: 3,
+                    ..Default::default()
+                },
+                FileMetrics {
+                    path: PathBuf::from("README.md"),
+                    code_lines: 2,
+                    is_embedded: true,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let parsed = read_csv_report(&to_csv(&report)).expect("CSV should parse");
+
+        assert_eq!(parsed.files.len(), 3);
+        assert_eq!(parsed.files[0].path, PathBuf::from("src/main.nr"));
+        assert_eq!(parsed.files[0].test_lines, 4);
+        assert!(parsed.files[0].has_main);
+        assert!(parsed.files[0].has_inline_tests);
+        assert_eq!(parsed.files[1].path, PathBuf::from("src/odd,\"name\".nr"));
+        assert!(parsed.files[2].is_embedded);
+        assert_eq!(parsed.totals.files, 2);
+        assert_eq!(parsed.totals.embedded_files, 1);
+        assert_eq!(parsed.totals.code_lines, 17);
+        assert_eq!(parsed.totals.todo_count, 1);
+    }
+
+    #[test]
+    fn columns_are_matched_by_header() {
+        let csv = "todo_count,extra,path,code_lines\n2,x,src/a.nr,7\n";
+        let parsed = read_csv_report(csv).expect("CSV should parse");
+
+        assert_eq!(parsed.files[0].path, PathBuf::from("src/a.nr"));
+        assert_eq!(parsed.files[0].code_lines, 7);
+        assert_eq!(parsed.files[0].todo_count, 2);
+
+        assert!(read_csv_report("code_lines\n1\n").is_err());
+        assert!(read_csv_report("path,code_lines\nsrc/a.nr,many\n").is_err());
+    }
+}
@@ -0,0 +1,372 @@
+use crate::JSON_SCHEMA_VERSION;
+use crate::analysis::file::FileMetrics;
+use crate::analysis::project::MetricsReport;
+use crate::csv::{csv_row, read_csv_report};
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the baseline used by `--baseline-auto`, looked up in the project root.
+pub const AUTO_BASELINE_FILE_NAME: &str = ".noir-metrics-baseline.json";
+
+/// How a file changed between a baseline report and the current report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FileDiffStatus {
+    /// The file only exists in the current report.
+    Added,
+    /// The file only exists in the baseline report.
+    Removed,
+    /// The file exists in both reports and at least one compared metric differs.
+    Changed,
+    /// The file exists in both reports and the compared metrics are equal.
+    Unchanged,
+}
+
+/// Per-file metric deltas between a baseline report and the current report.
+///
+/// Deltas are `current - baseline`; a missing side counts as zero.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FileDiff {
+    /// Path to the file, relative to the project root.
+    pub path: PathBuf,
+
+    /// Whether the file was added, removed, changed, or unchanged.
+    pub status: FileDiffStatus,
+
+    /// Change in [`FileMetrics::code_lines`].
+    pub code_lines_delta: i64,
+
+    /// Change in [`FileMetrics::test_lines`].
+    pub test_lines_delta: i64,
+
+    /// Change in [`FileMetrics::todo_count`].
+    pub todo_count_delta: i64,
+
+    /// Did this file get worse (more TODOs or fewer test lines) by more than the tolerance (see
+    /// [`diff_files`])?
+    pub regressed: bool,
+}
+
+/// File format of a baseline report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum BaselineFormat {
+    /// CSV when the file extension is `.csv`, JSON otherwise.
+    #[default]
+    Auto,
+    /// A `--format json` report.
+    Json,
+    /// A `--format csv` export.
+    Csv,
+}
+
+/// Load a previously written report to use as a baseline.
+///
+/// CSV baselines only carry per-file metrics; their totals are recomputed (see [`read_csv_report`]) and they
+/// have no schema version.
+///
+/// For JSON baselines the `tool.schema_version` is checked against [`JSON_SCHEMA_VERSION`]: a mismatch
+/// (or a missing version) is an error unless `allow_schema_mismatch` is set, in which case a warning is
+/// printed and the report is compared on a best-effort basis. The rest of the `tool` block is ignored.
+///
+/// Returns the report and the baseline's schema version, if known.
+pub fn load_baseline(
+    path: &Path,
+    format: BaselineFormat,
+    allow_schema_mismatch: bool,
+) -> Result<(MetricsReport, Option<u32>)> {
+    let s = fs::read_to_string(path)
+        .with_context(|| format!("failed to read baseline {}", path.display()))?;
+
+    let is_csv = match format {
+        BaselineFormat::Auto => path.extension().is_some_and(|ext| ext == "csv"),
+        BaselineFormat::Json => false,
+        BaselineFormat::Csv => true,
+    };
+
+    if is_csv {
+        let report = read_csv_report(&s)
+            .with_context(|| format!("failed to parse baseline {}", path.display()))?;
+        return Ok((report, None));
+    }
+
+    let value: Value = serde_json::from_str(&s)
+        .with_context(|| format!("failed to parse baseline {}", path.display()))?;
+
+    let schema_version = value["tool"]["schema_version"]
+        .as_u64()
+        .and_then(|v| u32::try_from(v).ok());
+
+    if schema_version != Some(JSON_SCHEMA_VERSION) {
+        let found = schema_version.map_or_else(|| "none".to_string(), |v| v.to_string());
+        let message = format!(
+            "baseline {} has schema_version {found}, expected {JSON_SCHEMA_VERSION}",
+            path.display()
+        );
+        if !allow_schema_mismatch {
+            bail!("{message} (pass --allow-schema-mismatch to compare anyway)");
+        }
+        eprintln!("warning: {message}; comparing on a best-effort basis");
+    }
+
+    let report = serde_json::from_value(value)
+        .with_context(|| format!("failed to parse baseline {}", path.display()))?;
+    Ok((report, schema_version))
+}
+
+/// Compare two reports file by file.
+///
+/// Files are matched by their relative [`FileMetrics::path`]. The result contains one entry per path
+/// present in either report, sorted by path.
+///
+/// A worsening metric only flags [`FileDiff::regressed`] when its change relative to the baseline value
+/// exceeds `tolerance_pct` percent (`--baseline-tolerance`; `0.0` flags any worsening). The tolerance applies
+/// to each metric separately; a metric that worsens from zero (e.g. a first TODO) is always a regression,
+/// since its relative change is unbounded.
+pub fn diff_files(old: &MetricsReport, new: &MetricsReport, tolerance_pct: f64) -> Vec<FileDiff> {
+    let mut paths: BTreeMap<&Path, (Option<&FileMetrics>, Option<&FileMetrics>)> = BTreeMap::new();
+
+    for fm in &old.files {
+        paths.entry(&fm.path).or_default().0 = Some(fm);
+    }
+    for fm in &new.files {
+        paths.entry(&fm.path).or_default().1 = Some(fm);
+    }
+
+    paths
+        .into_iter()
+        .map(|(path, (old_fm, new_fm))| {
+            let code_lines_delta = delta(old_fm, new_fm, |fm| fm.code_lines);
+            let test_lines_delta = delta(old_fm, new_fm, |fm| fm.test_lines);
+            let todo_count_delta = delta(old_fm, new_fm, |fm| fm.todo_count);
+
+            let status = match (old_fm, new_fm) {
+                (None, _) => FileDiffStatus::Added,
+                (_, None) => FileDiffStatus::Removed,
+                _ if code_lines_delta == 0 && test_lines_delta == 0 && todo_count_delta == 0 => {
+                    FileDiffStatus::Unchanged
+                }
+                _ => FileDiffStatus::Changed,
+            };
+
+            FileDiff {
+                path: path.to_path_buf(),
+                status,
+                code_lines_delta,
+                test_lines_delta,
+                todo_count_delta,
+                regressed: exceeds_tolerance(
+                    old_fm.map_or(0, |fm| fm.todo_count),
+                    todo_count_delta,
+                    tolerance_pct,
+                ) || exceeds_tolerance(
+                    old_fm.map_or(0, |fm| fm.test_lines),
+                    -test_lines_delta,
+                    tolerance_pct,
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Paths of the files in `new` whose metrics differ from `old` at all (`--only-changed`).
+///
+/// Files missing from `old` count as changed. When both sides carry a
+/// [`content_hash`](FileMetrics::content_hash) the hashes are compared; otherwise every per-file CSV column
+/// is, so CSV and JSON baselines behave the same.
+pub fn changed_files(old: &MetricsReport, new: &MetricsReport) -> BTreeSet<PathBuf> {
+    let old: BTreeMap<&Path, &FileMetrics> =
+        old.files.iter().map(|fm| (fm.path.as_path(), fm)).collect();
+
+    new.files
+        .iter()
+        .filter(|fm| match old.get(fm.path.as_path()) {
+            None => true,
+            Some(old_fm) => match (&old_fm.content_hash, &fm.content_hash) {
+                (Some(a), Some(b)) => a != b,
+                _ => csv_row(old_fm) != csv_row(fm),
+            },
+        })
+        .map(|fm| fm.path.clone())
+        .collect()
+}
+
+/// Is `worsening` (positive when a metric got worse) more than `tolerance_pct` percent of the baseline value?
+fn exceeds_tolerance(baseline: usize, worsening: i64, tolerance_pct: f64) -> bool {
+    worsening > 0 && (baseline == 0 || worsening as f64 / baseline as f64 * 100.0 > tolerance_pct)
+}
+
+/// Difference of a metric between two optional files (missing files count as zero).
+fn delta(
+    old: Option<&FileMetrics>,
+    new: Option<&FileMetrics>,
+    metric: impl Fn(&FileMetrics) -> usize,
+) -> i64 {
+    let old = old.map(&metric).unwrap_or(0) as i64;
+    let new = new.map(&metric).unwrap_or(0) as i64;
+    new - old
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::project::ProjectTotals;
+
+    fn file(path: &str, code_lines: usize, test_lines: usize, todo_count: usize) -> FileMetrics {
+        FileMetrics {
+            path: PathBuf::from(path),
+            code_lines,
+            test_lines,
+            todo_count,
+            ..Default::default()
+        }
+    }
+
+    fn report(files: Vec<FileMetrics>) -> MetricsReport {
+        MetricsReport {
+            project_root: PathBuf::from("."),
+            totals: ProjectTotals::default(),
+            files,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diff_files_flags_regressed_and_improved_files() {
+        let old = report(vec![
+            file("src/improved.nr", 20, 5, 2),
+            file("src/regressed.nr", 10, 8, 0),
+            file("src/removed.nr", 4, 0, 0),
+            file("src/same.nr", 3, 0, 0),
+        ]);
+        let new = report(vec![
+            file("src/added.nr", 6, 0, 1),
+            file("src/improved.nr", 18, 9, 0),
+            file("src/regressed.nr", 14, 4, 1),
+            file("src/same.nr", 3, 0, 0),
+        ]);
+
+        let diffs = diff_files(&old, &new, 0.0);
+
+        let summary: Vec<(&str, FileDiffStatus, i64, i64, i64, bool)> = diffs
+            .iter()
+            .map(|d| {
+                (
+                    d.path.to_str().unwrap(),
+                    d.status,
+                    d.code_lines_delta,
+                    d.test_lines_delta,
+                    d.todo_count_delta,
+                    d.regressed,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("src/added.nr", FileDiffStatus::Added, 6, 0, 1, true),
+                ("src/improved.nr", FileDiffStatus::Changed, -2, 4, -2, false),
+                ("src/regressed.nr", FileDiffStatus::Changed, 4, -4, 1, true),
+                ("src/removed.nr", FileDiffStatus::Removed, -4, 0, 0, false),
+                ("src/same.nr", FileDiffStatus::Unchanged, 0, 0, 0, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn regressions_within_the_tolerance_are_ignored() {
+        let old = report(vec![
+            file("src/a.nr", 200, 100, 0),
+            file("src/b.nr", 200, 100, 0),
+            file("src/c.nr", 200, 100, 0),
+        ]);
+        // 1% and 5% fewer test lines, and a first TODO.
+        let new = report(vec![
+            file("src/a.nr", 200, 99, 0),
+            file("src/b.nr", 200, 95, 0),
+            file("src/c.nr", 200, 100, 1),
+        ]);
+
+        let regressed = |tolerance: f64| -> Vec<bool> {
+            diff_files(&old, &new, tolerance)
+                .iter()
+                .map(|d| d.regressed)
+                .collect()
+        };
+
+        assert_eq!(regressed(0.0), [true, true, true]);
+        assert_eq!(regressed(2.0), [false, true, true]);
+        assert_eq!(regressed(5.0), [false, false, true]);
+    }
+
+    #[test]
+    fn changed_files_compares_hashes_then_every_column() {
+        let hashed = |path: &str, hash: &str| FileMetrics {
+            content_hash: Some(hash.to_string()),
+            ..file(path, 3, 0, 0)
+        };
+        let old = report(vec![
+            file("src/same.nr", 3, 0, 0),
+            FileMetrics {
+                blank_lines: 1,
+                ..file("src/blanks.nr", 3, 0, 0)
+            },
+            hashed("src/hashed.nr", "aa"),
+            hashed("src/rehashed.nr", "aa"),
+        ]);
+        let new = report(vec![
+            file("src/added.nr", 1, 0, 0),
+            file("src/same.nr", 3, 0, 0),
+            FileMetrics {
+                blank_lines: 2,
+                ..file("src/blanks.nr", 3, 0, 0)
+            },
+            hashed("src/hashed.nr", "aa"),
+            hashed("src/rehashed.nr", "bb"),
+        ]);
+
+        let changed: Vec<PathBuf> = changed_files(&old, &new).into_iter().collect();
+        assert_eq!(
+            changed,
+            ["src/added.nr", "src/blanks.nr", "src/rehashed.nr"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn load_baseline_rejects_schema_version_mismatch() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("noir_metrics_baseline_schema_{unique}.json"));
+
+        let mut v = serde_json::to_value(crate::output::json_report(&report(vec![file(
+            "src/main.nr",
+            3,
+            0,
+            0,
+        )])))
+        .unwrap();
+        v["tool"]["schema_version"] = Value::from(JSON_SCHEMA_VERSION + 1);
+        fs::write(&path, v.to_string()).unwrap();
+
+        let err = load_baseline(&path, BaselineFormat::Auto, false).unwrap_err();
+        assert!(
+            err.to_string().contains("schema_version"),
+            "unexpected error: {err}"
+        );
+
+        let (baseline, version) = load_baseline(&path, BaselineFormat::Auto, true)
+            .expect("mismatch should be allowed on request");
+        assert_eq!(version, Some(JSON_SCHEMA_VERSION + 1));
+        assert_eq!(baseline.files.len(), 1);
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::analysis::options::AnalysisOptions;
+use crate::analysis::project::{MetricsReport, ProjectTotals};
+use crate::analyze_path_with;
+use crate::merge::merge_reports;
+use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Reports for every project found by `--discover`, plus combined totals.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct DiscoveryReport {
+    /// Per-project reports, keyed by the matched directory as spelled by the glob.
+    pub projects: BTreeMap<String, MetricsReport>,
+
+    /// Totals over the files of all projects.
+    pub totals: ProjectTotals,
+}
+
+/// Find all directories matching a glob pattern that contain a `Nargo.toml`.
+///
+/// Matches that are files or directories without a manifest are skipped. The result is sorted.
+pub fn discover_projects(pattern: &str) -> Result<Vec<PathBuf>> {
+    let paths = glob::glob(pattern).with_context(|| format!("invalid glob `{pattern}`"))?;
+
+    let mut roots = Vec::new();
+    for path in paths {
+        let path = path.with_context(|| format!("failed to read a match of `{pattern}`"))?;
+        if path.join("Nargo.toml").is_file() {
+            roots.push(path);
+        }
+    }
+
+    roots.sort();
+    Ok(roots)
+}
+
+/// Analyze every project matched by `pattern` (see [`discover_projects`]).
+///
+/// Combined totals are computed as if all files belonged to one project (see
+/// [`merge_reports`](crate::merge_reports)). Fails when the glob matches no project.
+pub fn analyze_discovered(pattern: &str, options: &AnalysisOptions) -> Result<DiscoveryReport> {
+    let roots = discover_projects(pattern)?;
+    if roots.is_empty() {
+        bail!("no directories with a Nargo.toml match `{pattern}`");
+    }
+
+    let mut projects = BTreeMap::new();
+    for root in roots {
+        let report = analyze_path_with(&root, options)
+            .with_context(|| format!("failed to analyze {}", root.display()))?;
+        projects.insert(root.display().to_string(), report);
+    }
+
+    let totals = merge_reports(projects.values().cloned().collect())?.totals;
+
+    Ok(DiscoveryReport { projects, totals })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovers_only_directories_with_a_manifest() {
+        let roots = discover_projects("tests/fixtures/workspace/*").expect("glob should be valid");
+        assert_eq!(
+            roots,
+            vec![
+                PathBuf::from("tests/fixtures/workspace/pkg_a"),
+                PathBuf::from("tests/fixtures/workspace/pkg_b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn combined_totals_sum_all_projects() {
+        let report = analyze_discovered("tests/fixtures/workspace/*", &AnalysisOptions::default())
+            .expect("analysis should succeed");
+
+        assert_eq!(report.projects.len(), 2);
+        let code_lines: u64 = report.projects.values().map(|r| r.totals.code_lines).sum();
+        assert_eq!(report.totals.code_lines, code_lines);
+        assert_eq!(report.totals.files, 2);
+    }
+
+    #[test]
+    fn glob_without_projects_is_an_error() {
+        let err = analyze_discovered(
+            "tests/fixtures/workspace/nothing*",
+            &AnalysisOptions::default(),
+        )
+        .expect_err("no projects should match");
+        assert!(err.to_string().contains("no directories"), "{err}");
+    }
+}
@@ -0,0 +1,243 @@
+use crate::analysis::file::FileMetrics;
+use anyhow::{Result, bail};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+/// Attribute code lines to authors using `git blame --line-porcelain`.
+///
+/// Each non-ignored file is blamed individually; lines are classified with the same blank/comment rules as
+/// [`analyze_file`](crate::analysis::file::analyze_file) and only code lines are counted. Files that git cannot
+/// blame (e.g. untracked files) are skipped. Uncommitted changes are attributed to git's
+/// `Not Committed Yet` pseudo-author.
+///
+/// This spawns one `git` process per file and is therefore opt-in (`--by-author`).
+pub fn code_lines_by_author(root: &Path, files: &[FileMetrics]) -> Result<BTreeMap<String, u64>> {
+    if !is_inside_work_tree(root)? {
+        bail!(
+            "--by-author requires a git repository, but {} is not inside one",
+            root.display()
+        );
+    }
+
+    let mut by_author = BTreeMap::new();
+
+    for fm in files.iter().filter(|fm| !fm.ignored) {
+        let blame = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["blame", "--line-porcelain", "--"])
+            .arg(&fm.path)
+            .output()?;
+
+        if !blame.status.success() {
+            continue;
+        }
+
+        let porcelain = String::from_utf8_lossy(&blame.stdout);
+        let mut in_block_comment = false;
+        for (author, line) in blame_lines(&porcelain) {
+            if is_code_line(line, &mut in_block_comment) {
+                *by_author.entry(author.to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    Ok(by_author)
+}
+
+/// Set [`FileMetrics::commit_count`] from `git log --oneline -- <path>` for each non-ignored file.
+///
+/// `since` is passed to `git log --since` (e.g. `6.months`, `2024-01-01`). Callers should check
+/// [`is_inside_work_tree`] first; like `--by-author` this spawns one `git` process per file (`--git-churn`).
+pub fn add_commit_counts(
+    root: &Path,
+    files: &mut [FileMetrics],
+    since: Option<&str>,
+) -> Result<()> {
+    for fm in files.iter_mut().filter(|fm| !fm.ignored) {
+        let mut cmd = Command::new("git");
+        cmd.arg("-C").arg(root).args(["log", "--oneline"]);
+        if let Some(since) = since {
+            cmd.arg(format!("--since={since}"));
+        }
+        let log = cmd.arg("--").arg(&fm.path).output()?;
+
+        if !log.status.success() {
+            bail!(
+                "git log failed for {}: {}",
+                fm.path.display(),
+                String::from_utf8_lossy(&log.stderr).trim()
+            );
+        }
+
+        fm.commit_count = Some(String::from_utf8_lossy(&log.stdout).lines().count());
+    }
+
+    Ok(())
+}
+
+/// Is `root` inside a git work tree? (`false` when git is missing or reports otherwise.)
+pub fn is_inside_work_tree(root: &Path) -> Result<bool> {
+    let inside = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output();
+
+    Ok(inside.is_ok_and(|out| out.status.success()))
+}
+
+/// Extract `(author, line content)` pairs from `git blame --line-porcelain` output.
+fn blame_lines(porcelain: &str) -> Vec<(&str, &str)> {
+    let mut lines = Vec::new();
+    let mut author = "";
+
+    for line in porcelain.lines() {
+        if let Some(name) = line.strip_prefix("author ") {
+            author = name;
+        } else if let Some(content) = line.strip_prefix('\t') {
+            lines.push((author, content));
+        }
+    }
+
+    lines
+}
+
+/// Classify a single line as code, tracking block-comment state across calls.
+fn is_code_line(line: &str, in_block_comment: &mut bool) -> bool {
+    let trimmed = line.trim();
+
+    if *in_block_comment {
+        if trimmed.contains("*/") {
+            *in_block_comment = false;
+        }
+        return false;
+    }
+
+    if trimmed.starts_with("/*") {
+        *in_block_comment = !trimmed.contains("*/");
+        return false;
+    }
+
+    !trimmed.is_empty() && !trimmed.starts_with("//")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .status()
+            .expect("git should run");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn commit_as(dir: &Path, name: &str, message: &str) {
+        git(dir, &["add", "-A"]);
+        git(
+            dir,
+            &[
+                "-c",
+                &format!("user.name={name}"),
+                "-c",
+                &format!("user.email={name}@example.com"),
+                "commit",
+                "-q",
+                "-m",
+                message,
+            ],
+        );
+    }
+
+    #[test]
+    fn attributes_code_lines_to_authors() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("noir_metrics_blame_{unique}"));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("Nargo.toml"), "[package]\nname = \"blame\"\n").unwrap();
+
+        git(&dir, &["init", "-q"]);
+
+        let main = dir.join("src/main.nr");
+        fs::write(&main, "// alice's circuit\nfn main(x: Field) {\n}\n").unwrap();
+        commit_as(&dir, "alice", "initial");
+
+        fs::write(
+            &main,
+            "// alice's circuit\nfn main(x: Field) {\n    assert(x != 0);\n\n}\n",
+        )
+        .unwrap();
+        commit_as(&dir, "bob", "add assert");
+
+        let files = vec![FileMetrics {
+            path: PathBuf::from("src/main.nr"),
+            ..Default::default()
+        }];
+        let by_author = code_lines_by_author(&dir, &files).expect("blame should succeed");
+
+        assert_eq!(by_author.get("alice"), Some(&2));
+        assert_eq!(by_author.get("bob"), Some(&1));
+        assert_eq!(by_author.len(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn counts_commits_touching_each_file() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("noir_metrics_churn_{unique}"));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("Nargo.toml"), "[package]\nname = \"churn\"\n").unwrap();
+
+        git(&dir, &["init", "-q"]);
+        fs::write(dir.join("src/main.nr"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("src/lib.nr"), "fn helper() {}\n").unwrap();
+        commit_as(&dir, "alice", "initial");
+
+        fs::write(dir.join("src/main.nr"), "fn main(x: Field) {}\n").unwrap();
+        commit_as(&dir, "alice", "add input");
+        fs::write(dir.join("src/new.nr"), "fn later() {}\n").unwrap();
+
+        let mut files: Vec<FileMetrics> = ["src/main.nr", "src/lib.nr", "src/new.nr"]
+            .into_iter()
+            .map(|path| FileMetrics {
+                path: PathBuf::from(path),
+                ..Default::default()
+            })
+            .collect();
+        assert!(is_inside_work_tree(&dir).unwrap());
+        add_commit_counts(&dir, &mut files, None).expect("git log should succeed");
+
+        let counts: Vec<Option<usize>> = files.iter().map(|fm| fm.commit_count).collect();
+        assert_eq!(counts, [Some(2), Some(1), Some(0)]);
+
+        add_commit_counts(&dir, &mut files, Some("2099-01-01")).expect("git log should succeed");
+        assert_eq!(files[0].commit_count, Some(0));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn is_code_line_skips_comments_and_blanks() {
+        let mut in_block = false;
+        assert!(!is_code_line("", &mut in_block));
+        assert!(!is_code_line("  // note", &mut in_block));
+        assert!(!is_code_line("/* start", &mut in_block));
+        assert!(!is_code_line("let x = 1;", &mut in_block));
+        assert!(!is_code_line("end */", &mut in_block));
+        assert!(is_code_line("let y = 2;", &mut in_block));
+    }
+}
@@ -29,43 +29,231 @@
 //! [`JSON_SCHEMA_VERSION`].
 
 mod analysis;
+mod budget;
 mod cli;
+mod config;
+mod csv;
+mod diff;
+mod discover;
+mod git;
+mod merge;
 mod output;
+mod profile;
 mod project;
+mod sarif;
+mod schema;
+mod watch;
 
-use crate::analysis::project::analyze_project;
-use crate::cli::{Cli, OutputFormat};
-use crate::output::{print_human_summary, write_json};
+use crate::analysis::project::{analyze_files as analyze_in_memory, analyze_project};
+use crate::cli::{AnalyzeArgs, Cli, Command, DenyCheck, OutputFormat};
+use crate::diff::{changed_files, diff_files, load_baseline};
+use crate::git::{add_commit_counts, code_lines_by_author, is_inside_work_tree};
+use crate::merge::load_report;
+use crate::output::{
+    append_json_record, print_discovery_summary, print_explain_totals, print_file_diffs,
+    print_human_summary, print_line_trace, print_table, totals_json, write_csv,
+    write_discovery_json, write_flat_json, write_folded, write_json, write_ndjson_record,
+    write_sarif,
+};
 use crate::project::Project;
-use anyhow::{Result, bail};
+use crate::schema::validate_report_file;
+use crate::watch::Watcher;
+use anyhow::{Context, Result, bail};
 use clap::Parser;
-use std::path::Path;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
 
-pub use crate::analysis::file::FileMetrics;
-pub use crate::analysis::project::{MetricsReport, ProjectTotals};
+pub use crate::analysis::file::{
+    FileMetrics, IndentStyle, LineEnding, LineKind, LineTrace, PRIMITIVE_TYPES, SuspiciousAssert,
+    analyze_source, analyze_source_with_trace,
+};
+pub use crate::analysis::function::FunctionMetrics;
+pub use crate::analysis::project::{
+    InvariantViolation, LongestFunction, MetricsReport, ProjectTotals, TestInfo, TotalsAccumulator,
+    analyze_project_with_sink, directory_shape, duplicate_groups, files_without_tests,
+    grouped_totals, longest_functions, test_list,
+};
+
+pub use crate::diff::{AUTO_BASELINE_FILE_NAME, BaselineFormat, FileDiff, FileDiffStatus};
+pub use crate::discover::{DiscoveryReport, analyze_discovered, discover_projects};
+pub use crate::merge::merge_reports;
+
+pub use crate::analysis::options::{
+    AnalysisOptions, AnalysisPreset, CountRegex, DEFAULT_MAX_LINE_LENGTH, DEFAULT_TODO_MARKERS,
+};
+pub use crate::output::{
+    ColorTheme, FileField, HumanOptions, JsonOptions, format_human_summary, format_table,
+};
+pub use crate::project::{DEFAULT_MAX_FILES, WalkOptions};
+
+pub use crate::budget::{BUDGETS_FILE_NAME, Budget, BudgetViolation, Budgets, evaluate_budgets};
+pub use crate::config::{CONFIG_FILE_NAME, Config};
+pub use crate::profile::{Profile, ProfileResult, TargetResult, evaluate_profile};
+pub use crate::schema::{report_schema, validate_report};
 
 /// Noir project handle (re-export of the internal [`project::Project`] type).
 pub use crate::project::Project as NoirProject;
 
 /// JSON schema version for the noir-metrics report format.
 ///
-/// Bump this when making breaking changes to the JSON layout.
+/// Bump this when making breaking changes to the JSON layout. The top-level, `tool` and `totals` keys of
+/// each version are recorded in `tests/golden/schema_v<N>_keys.txt` and checked by the test suite.
 pub const JSON_SCHEMA_VERSION: u32 = 1;
 
+/// Layout version of the human-readable summary, for scripts that scrape it.
+///
+/// Bump this whenever a line of the human summary is added, removed, reordered or reformatted. It is printed
+/// by `--print-format-version` and as a `# format_version: N` first line under `--stable-human`.
+pub const HUMAN_FORMAT_VERSION: u32 = 5;
+
 /// Analyze a Noir project at the given root path.
 ///
 /// This is the main entry point for *library* users.
 pub fn analyze_path(root: &Path) -> Result<MetricsReport> {
-    let project = Project::from_root(root.to_path_buf())?;
-    analyze_project(&project)
+    analyze_path_with(root, &AnalysisOptions::default())
+}
+
+/// Analyze a Noir project at the given root path using custom [`AnalysisOptions`].
+pub fn analyze_path_with(root: &Path, options: &AnalysisOptions) -> Result<MetricsReport> {
+    let mut project = Project::from_root(root.to_path_buf())?;
+    project.walk = options.walk.clone();
+    analyze_project(&project, options)
+}
+
+/// Analyze in-memory `(path, contents)` pairs without touching the filesystem (e.g. for WASM builds).
+///
+/// Paths are reported relative to `root` when they lie below it. Uses the default [`AnalysisOptions`].
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+///
+/// let files = vec![
+///     (PathBuf::from("src/main.nr"), "fn main(x: Field) {\n    assert(x != 0);\n}\n".to_string()),
+///     (PathBuf::from("src/lib.nr"), "// helper\npub fn one() -> Field {\n    1\n}\n".to_string()),
+/// ];
+///
+/// let report = noir_metrics::analyze_files(files, Path::new(".")).unwrap();
+/// assert_eq!(report.totals.files, 2);
+/// assert_eq!(report.totals.code_lines, 6);
+/// assert_eq!(report.totals.comment_lines, 1);
+/// assert_eq!(report.totals.functions, 2);
+/// assert_eq!(report.files[0].path, Path::new("src/lib.nr"));
+/// ```
+pub fn analyze_files<I>(files: I, root: &Path) -> Result<MetricsReport>
+where
+    I: IntoIterator<Item = (PathBuf, String)>,
+{
+    analyze_files_with(files, root, &AnalysisOptions::default())
+}
+
+/// Analyze in-memory `(path, contents)` pairs using custom [`AnalysisOptions`] (see [`analyze_files`]).
+pub fn analyze_files_with<I>(
+    files: I,
+    root: &Path,
+    options: &AnalysisOptions,
+) -> Result<MetricsReport>
+where
+    I: IntoIterator<Item = (PathBuf, String)>,
+{
+    analyze_in_memory(files, root, options)
 }
 
 /// Entry point used by the binary.
 ///
-/// Parses CLI args, calls `analyze_path`, and then either prints a human
-/// summary or writes JSON (and optionally saves it to a file).
+/// Parses CLI args and dispatches on the subcommand; without one, `analyze` runs.
 pub fn run() -> Result<()> {
-    let args = Cli::parse();
+    let cli = Cli::parse();
+
+    match cli.command {
+        None => run_analyze_or_watch(cli.analyze),
+        Some(Command::Analyze(args)) => run_analyze_or_watch(*args),
+        Some(Command::Diff {
+            old,
+            new,
+            format,
+            baseline_tolerance,
+            allow_schema_mismatch,
+        }) => {
+            let (old, schema_version) =
+                load_baseline(&old, BaselineFormat::Auto, allow_schema_mismatch)?;
+            let (new, _) = load_baseline(&new, BaselineFormat::Auto, allow_schema_mismatch)?;
+            let diffs = diff_files(&old, &new, baseline_tolerance);
+            match format {
+                OutputFormat::Human => {
+                    print_file_diffs(&diffs, schema_version, &mut io::stdout().lock())?
+                }
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&diffs)?),
+                _ => bail!("diff supports --format human or json"),
+            }
+            Ok(())
+        }
+        Some(Command::Schema) => {
+            println!("{}", serde_json::to_string_pretty(&report_schema())?);
+            Ok(())
+        }
+        Some(Command::Merge { files, output }) => {
+            let reports = files
+                .iter()
+                .map(|path| load_report(path))
+                .collect::<Result<Vec<_>>>()?;
+            write_json(
+                &merge_reports(reports)?,
+                output.as_deref(),
+                &JsonOptions::default(),
+            )
+        }
+    }
+}
+
+/// Run `analyze` once, or with `--watch` again after every change to the project's files until interrupted.
+///
+/// Under `--watch`, a failing run (including a failed `--fail-on-*` gate) is reported on stderr and watching
+/// continues; `--output` is rewritten atomically, so readers never see a partial file.
+fn run_analyze_or_watch(args: AnalyzeArgs) -> Result<()> {
+    if !args.watch {
+        return run_analyze(args);
+    }
+
+    let mut project = Project::from_root(args.project_root()?)?;
+    project.walk = args.analysis_options().walk;
+    let mut watcher = Watcher::new(project)?;
+
+    loop {
+        if let Err(err) = run_analyze(args.clone()) {
+            eprintln!("error: {err:#}");
+        }
+        watcher.wait_for_change(watch::DEBOUNCE)?;
+    }
+}
+
+/// Run `analyze`: analyze the project (or merge reports), then either print a human summary or write a
+/// machine-readable format (optionally to a file).
+fn run_analyze(args: AnalyzeArgs) -> Result<()> {
+    if args.print_format_version {
+        println!("{HUMAN_FORMAT_VERSION}");
+        return Ok(());
+    }
+
+    if args.print_schema {
+        println!("{}", serde_json::to_string_pretty(&report_schema())?);
+        return Ok(());
+    }
+
+    if let Some(path) = &args.validate_schema {
+        validate_report_file(path)?;
+        println!("{}: ok", path.display());
+        return Ok(());
+    }
+
+    if let Some(path) = &args.explain {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut trace = Vec::new();
+        analyze_source_with_trace(&source, path, &args.analysis_options(), Some(&mut trace));
+        print_line_trace(&source, &trace);
+        return Ok(());
+    }
 
     let format = match (args.format, args.json) {
         (Some(f), false) => f,
@@ -74,13 +262,64 @@ pub fn run() -> Result<()> {
         (None, false) => OutputFormat::Human,
     };
 
-    if args.output.is_some() && !matches!(format, OutputFormat::Json) {
-        bail!("--output requires JSON output (use --format json)");
+    if args.output.is_some()
+        && !matches!(
+            format,
+            OutputFormat::Json
+                | OutputFormat::FlatJson
+                | OutputFormat::Csv
+                | OutputFormat::Sarif
+                | OutputFormat::Folded
+        )
+    {
+        bail!(
+            "--output requires a machine-readable format (use --format json, flat-json, csv, sarif or folded)"
+        );
+    }
+
+    if args.also_human
+        && !matches!(
+            format,
+            OutputFormat::Json
+                | OutputFormat::FlatJson
+                | OutputFormat::Csv
+                | OutputFormat::Sarif
+                | OutputFormat::Folded
+        )
+    {
+        bail!(
+            "--also-human requires a machine-readable format (use --format json, flat-json, csv, sarif or folded)"
+        );
+    }
+
+    if args.bad_test_pct > args.warn_test_pct {
+        bail!(
+            "--bad-test-pct ({}) must not exceed --warn-test-pct ({})",
+            args.bad_test_pct,
+            args.warn_test_pct
+        );
+    }
+
+    if args.only_changed && args.baseline.is_none() && !args.baseline_auto {
+        bail!("--only-changed requires --baseline or --baseline-auto");
     }
 
+    if args.fail_on_regression && args.baseline.is_none() && !args.baseline_auto {
+        bail!("--fail-on-regression requires --baseline or --baseline-auto");
+    }
+
+    if args.baseline_tolerance < 0.0 {
+        bail!(
+            "--baseline-tolerance must not be negative (got {})",
+            args.baseline_tolerance
+        );
+    }
+
+    let project_root = args.project_root()?;
+
     if args.verbose {
         eprintln!("noir-metrics");
-        eprintln!("  project_root: {}", args.project_root.display());
+        eprintln!("  project_root: {}", project_root.display());
         eprintln!("  format: {:?}", format);
         eprintln!(
             "  output: {}",
@@ -91,11 +330,291 @@ pub fn run() -> Result<()> {
         );
     }
 
-    let report = analyze_path(&args.project_root)?;
+    let config = Config::load(&project_root, args.config.as_deref())?;
+
+    let mut options = args.analysis_options();
+    if options.todo_markers.is_empty()
+        && let Some(markers) = &config.todo_markers
+    {
+        options.todo_markers = markers.clone();
+    }
+
+    if let Some(pattern) = &args.discover {
+        let discovered = analyze_discovered(pattern, &options)?;
+        match format {
+            OutputFormat::Json => write_discovery_json(&discovered, args.output.as_deref())?,
+            OutputFormat::Human => print_discovery_summary(
+                &discovered,
+                &args.human_options(io::stdout().is_terminal()),
+                &mut io::stdout().lock(),
+            )?,
+            _ => bail!("--discover supports --format human or json"),
+        }
+        return Ok(());
+    }
+
+    if args.ndjson_per_file {
+        let mut project = Project::from_root(project_root)?;
+        project.walk = options.walk.clone();
+
+        let mut out = io::stdout().lock();
+        let totals = analyze_project_with_sink(&project, &options, |fm| {
+            write_ndjson_record(&mut out, "file", &fm)
+        })?;
+        write_ndjson_record(&mut out, "totals", &totals)?;
+        return Ok(());
+    }
+
+    let mut report = if args.stdin {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .context("failed to read stdin")?;
+        let name = args
+            .name
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("stdin.nr"));
+        analyze_in_memory([(name, source)], &project_root, &options)?
+    } else if args.merge.is_empty() {
+        analyze_path_with(&project_root, &options)?
+    } else {
+        let reports = args
+            .merge
+            .iter()
+            .map(|path| load_report(path))
+            .collect::<Result<Vec<_>>>()?;
+        merge_reports(reports)?
+    };
+
+    // `git blame`/`git log` need the on-disk paths, so run them before paths are rewritten.
+    if args.by_author {
+        report.by_author = code_lines_by_author(&report.project_root, &report.files)?;
+    }
+    if args.git_churn {
+        if is_inside_work_tree(&report.project_root)? {
+            add_commit_counts(
+                &report.project_root,
+                &mut report.files,
+                args.since.as_deref(),
+            )?;
+        } else {
+            eprintln!(
+                "warning: --git-churn requires a git repository, but {} is not inside one; skipping",
+                report.project_root.display()
+            );
+        }
+    }
+
+    if let Some(dir) = &args.relative_to {
+        report.make_paths_relative_to(dir)?;
+    }
+    report.rewrite_paths(
+        args.path_prefix_strip.as_deref(),
+        args.path_prefix_add.as_deref(),
+    );
+    (report.max_directory_depth, report.widest_directory) = directory_shape(&report.files);
+
+    if args.normalize {
+        report.add_per_kloc();
+    }
+
+    if let Some(n) = args.longest_functions {
+        report.longest_functions = longest_functions(&report.files, n);
+    }
+
+    if args.list_tests {
+        report.tests = test_list(&report.files);
+    }
+
+    if args.group_tests {
+        let (production, tests) = grouped_totals(&report.files)?;
+        report.totals_production = Some(production);
+        report.totals_tests = Some(tests);
+    }
+
+    let auto_baseline = project_root.join(AUTO_BASELINE_FILE_NAME);
+    let baseline = match &args.baseline {
+        Some(path) => Some(path.as_path()),
+        None if args.baseline_auto && auto_baseline.is_file() => Some(auto_baseline.as_path()),
+        None => None,
+    };
+    let mut changed = None;
+    if let Some(path) = baseline {
+        let (baseline, schema_version) =
+            load_baseline(path, args.baseline_format, args.allow_schema_mismatch)?;
+        report.file_diffs = diff_files(&baseline, &report, args.baseline_tolerance);
+        if args.only_changed {
+            changed = Some(changed_files(&baseline, &report));
+        }
+        report.baseline_schema_version = schema_version;
+    }
+
+    if options.content_hash {
+        report.duplicate_groups = duplicate_groups(&report.files);
+    }
+
+    if let Some(profile) = &config.profile {
+        report.profile = Some(evaluate_profile(&report, profile));
+    }
+
+    if let Some(budgets) = Budgets::load(&report.project_root, args.budgets.as_deref())? {
+        report.budget_violations = evaluate_budgets(&report.files, &budgets);
+    }
+
+    if let Some(metric) = &args.explain_totals {
+        print_explain_totals(&report, metric, &mut io::stdout().lock())?;
+        return Ok(());
+    }
+
+    let mut json_options = args.json_options();
+    if let Some(fields) = &config.fields {
+        if json_options.allow_fields.is_empty() {
+            json_options.allow_fields = fields.allow.clone();
+        }
+        if json_options.deny_fields.is_empty() {
+            json_options.deny_fields = fields.deny.clone();
+        }
+    }
+
+    // `--only-changed` narrows the rendered file list only; derived sections and the gates below still
+    // cover every file.
+    let only_changed;
+    let rendered = match &changed {
+        Some(changed) => {
+            let mut filtered = report.clone();
+            filtered.files.retain(|fm| changed.contains(&fm.path));
+            only_changed = filtered;
+            &only_changed
+        }
+        None => &report,
+    };
 
     match format {
-        OutputFormat::Json => write_json(&report, args.output.as_deref())?,
-        OutputFormat::Human => print_human_summary(&report)?,
+        OutputFormat::Json => write_json(rendered, args.output.as_deref(), &json_options)?,
+        OutputFormat::FlatJson => write_flat_json(rendered, args.output.as_deref(), &json_options)?,
+        OutputFormat::Csv => write_csv(rendered, args.output.as_deref())?,
+        OutputFormat::Sarif => write_sarif(rendered, args.output.as_deref())?,
+        OutputFormat::Folded => write_folded(rendered, args.output.as_deref())?,
+        OutputFormat::Human => {
+            let opts = args.human_options(io::stdout().is_terminal());
+            print_human_summary(rendered, &opts, &mut io::stdout().lock())?
+        }
+        OutputFormat::Table => print_table(rendered, &args.human_options(false))?,
+    }
+
+    if args.also_human {
+        let opts = args.human_options(io::stderr().is_terminal());
+        print_human_summary(rendered, &opts, &mut io::stderr().lock())?;
+    }
+
+    if args.baseline_auto_write && baseline.is_none() {
+        write_json(&report, Some(&auto_baseline), &JsonOptions::default())?;
+    }
+
+    if args.summary_json {
+        eprintln!("{}", totals_json(&report.totals)?);
+    }
+
+    if let Some(path) = &args.json_stream_to {
+        append_json_record(&report, path)?;
+    }
+
+    if args.fail_on_profile
+        && let Some(result) = &report.profile
+        && !result.passed()
+    {
+        let failed: Vec<&str> = result
+            .targets
+            .iter()
+            .filter(|t| !t.passed)
+            .map(|t| t.name.as_str())
+            .collect();
+        bail!("profile targets not met: {}", failed.join(", "));
+    }
+
+    if args.fail_if_no_tests && report.totals.test_functions == 0 {
+        bail!(
+            "no #[test] functions found in {} file(s)",
+            report.totals.files
+        );
+    }
+
+    if args.require_test_files {
+        let uncovered = files_without_tests(&report.files);
+        if !uncovered.is_empty() {
+            let files: Vec<String> = uncovered.iter().map(|p| p.display().to_string()).collect();
+            bail!(
+                "{} source file(s) without a test file: {}",
+                uncovered.len(),
+                files.join(", ")
+            );
+        }
+    }
+
+    if args.fail_on_regression {
+        let regressed: Vec<String> = report
+            .file_diffs
+            .iter()
+            .filter(|d| d.regressed)
+            .map(|d| d.path.display().to_string())
+            .collect();
+        if !regressed.is_empty() {
+            bail!(
+                "{} file(s) regressed against the baseline: {}",
+                regressed.len(),
+                regressed.join(", ")
+            );
+        }
+    }
+
+    if args.deny.contains(&DenyCheck::EmptyFiles) && report.totals.empty_files > 0 {
+        let files: Vec<String> = report
+            .files
+            .iter()
+            .filter(|fm| fm.is_empty && !fm.ignored)
+            .map(|fm| fm.path.display().to_string())
+            .collect();
+        bail!(
+            "{} empty file(s) denied: {}",
+            report.totals.empty_files,
+            files.join(", ")
+        );
+    }
+
+    if args.deny.contains(&DenyCheck::DebugPrints) && report.totals.debug_print_count > 0 {
+        let files: Vec<String> = report
+            .files
+            .iter()
+            .filter(|fm| fm.debug_print_count > 0 && !fm.ignored)
+            .map(|fm| format!("{} ({})", fm.path.display(), fm.debug_print_count))
+            .collect();
+        bail!(
+            "{} debug print(s) denied: {}",
+            report.totals.debug_print_count,
+            files.join(", ")
+        );
+    }
+
+    if !report.budget_violations.is_empty() {
+        let details: Vec<String> = report
+            .budget_violations
+            .iter()
+            .map(|v| {
+                format!(
+                    "{}: {} {} > {} (budget `{}`)",
+                    v.path.display(),
+                    v.metric,
+                    v.actual,
+                    v.limit,
+                    v.glob
+                )
+            })
+            .collect();
+        bail!(
+            "{} budget violation(s):\n  {}",
+            details.len(),
+            details.join("\n  ")
+        );
     }
 
     Ok(())
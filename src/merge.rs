@@ -0,0 +1,145 @@
+use crate::JSON_SCHEMA_VERSION;
+use crate::analysis::project::{MetricsReport, compute_totals, directory_shape};
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Load a saved `--format json` report for merging.
+///
+/// The report's `tool.schema_version` must match [`JSON_SCHEMA_VERSION`].
+pub fn load_report(path: &Path) -> Result<MetricsReport> {
+    let s =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let value: Value =
+        serde_json::from_str(&s).with_context(|| format!("failed to parse {}", path.display()))?;
+
+    let schema_version = value["tool"]["schema_version"].as_u64();
+    if schema_version != Some(u64::from(JSON_SCHEMA_VERSION)) {
+        let found = schema_version.map_or_else(|| "none".to_string(), |v| v.to_string());
+        bail!(
+            "{} has schema_version {found}, expected {JSON_SCHEMA_VERSION}",
+            path.display()
+        );
+    }
+
+    serde_json::from_value(value).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Combine several reports into one.
+///
+/// The merged `project_root` is the deepest directory containing every report's root. Each file path is
+/// namespaced by its report's root relative to that directory (e.g. `pkg_a/src/main.nr`), so packages with
+/// the same relative layout do not collide. Files are sorted by path and `totals` and the directory shape are
+/// recomputed from them; all other report sections are dropped.
+///
+/// Fails when no reports are given or when two files still map to the same path (e.g. the same report
+/// merged twice).
+pub fn merge_reports(reports: Vec<MetricsReport>) -> Result<MetricsReport> {
+    let Some(project_root) = common_ancestor(reports.iter().map(|r| r.project_root.as_path()))
+    else {
+        bail!("no reports to merge");
+    };
+
+    let mut seen = BTreeSet::new();
+    let mut files = Vec::new();
+
+    for report in reports {
+        let namespace = report
+            .project_root
+            .strip_prefix(&project_root)
+            .unwrap_or(&report.project_root)
+            .to_path_buf();
+
+        for mut fm in report.files {
+            fm.path = namespace.join(&fm.path);
+            if !seen.insert(fm.path.clone()) {
+                bail!(
+                    "duplicate path {} in merged reports (were the same reports passed twice?)",
+                    fm.path.display()
+                );
+            }
+            files.push(fm);
+        }
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let totals = compute_totals(&files)?;
+    let (max_directory_depth, widest_directory) = directory_shape(&files);
+
+    Ok(MetricsReport {
+        project_root,
+        totals,
+        files,
+        max_directory_depth,
+        widest_directory,
+        ..Default::default()
+    })
+}
+
+/// Longest common leading path of `paths` (`None` for no paths).
+fn common_ancestor<'a>(mut paths: impl Iterator<Item = &'a Path>) -> Option<PathBuf> {
+    let first = paths.next()?.to_path_buf();
+
+    Some(paths.fold(first, |common, path| {
+        common
+            .components()
+            .zip(path.components())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a)
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::options::AnalysisOptions;
+    use crate::analysis::project::analyze_project;
+    use crate::project::Project;
+
+    fn report(fixture: &str) -> MetricsReport {
+        let project = Project::from_root(PathBuf::from("tests/fixtures").join(fixture))
+            .expect("project should be valid");
+        analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed")
+    }
+
+    #[test]
+    fn merged_totals_are_the_sum_of_the_inputs() {
+        let a = report("simple_noir");
+        let b = report("derives");
+        let (ta, tb) = (a.totals.clone(), b.totals.clone());
+
+        let merged = merge_reports(vec![a, b]).expect("merge should succeed");
+
+        assert_eq!(merged.totals.files, ta.files + tb.files);
+        assert_eq!(merged.totals.total_lines, ta.total_lines + tb.total_lines);
+        assert_eq!(merged.totals.code_lines, ta.code_lines + tb.code_lines);
+        assert_eq!(merged.totals.test_lines, ta.test_lines + tb.test_lines);
+        assert_eq!(merged.totals.functions, ta.functions + tb.functions);
+        assert_eq!(merged.totals.todo_count, ta.todo_count + tb.todo_count);
+
+        // Both fixtures contain `src/main.nr`; paths are namespaced by project root.
+        assert!(merged.project_root.ends_with("tests/fixtures"));
+        let paths: Vec<&Path> = merged.files.iter().map(|fm| fm.path.as_path()).collect();
+        assert!(
+            paths.contains(&Path::new("derives/src/main.nr")),
+            "{paths:?}"
+        );
+        assert!(
+            paths.contains(&Path::new("simple_noir/src/main.nr")),
+            "{paths:?}"
+        );
+    }
+
+    #[test]
+    fn merging_the_same_report_twice_fails() {
+        let err = merge_reports(vec![report("simple_noir"), report("simple_noir")])
+            .expect_err("duplicate paths should be rejected");
+        assert!(err.to_string().contains("duplicate path"), "{err}");
+
+        assert!(merge_reports(Vec::new()).is_err());
+    }
+}
@@ -1,95 +1,1208 @@
-use crate::JSON_SCHEMA_VERSION;
-use crate::analysis::project::MetricsReport;
-use anyhow::Result;
+use crate::analysis::file::{FileMetrics, IndentStyle, LineKind, LineTrace};
+use crate::analysis::project::{MetricsReport, ProjectTotals, test_code_percentage};
+use crate::csv::{numeric_columns, numeric_value, to_csv};
+use crate::diff::{FileDiff, FileDiffStatus};
+use crate::discover::DiscoveryReport;
+use crate::sarif::to_sarif;
+use crate::{HUMAN_FORMAT_VERSION, JSON_SCHEMA_VERSION};
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use glob::Pattern;
+use schemars::JsonSchema;
 use serde::Serialize;
-use std::fs::File;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, Write};
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Metadata about this tool and the JSON schema version.
-#[derive(Debug, Serialize)]
-struct ToolMeta {
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ToolMeta {
     name: &'static str,
     version: &'static str,
     schema_version: u32,
 }
 
 /// JSON representation of a metrics report including tool metadata.
-#[derive(Debug, Serialize)]
-struct JsonReport<'a> {
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct JsonReport<'a> {
     tool: ToolMeta,
     #[serde(flatten)]
     report: &'a MetricsReport,
 }
 
-/// Print a human-readable summary to stdout.
-pub fn print_human_summary(report: &MetricsReport) -> Result<()> {
-    println!("Project: {}", report.project_root.display());
-    println!("Files: {}", report.totals.files);
-    println!(
-        "Lines: total={}, code={}, comments={}, blanks={}, test={}, non-test={}, test_functions={}, test_code={:.2}%",
-        report.totals.total_lines,
-        report.totals.code_lines,
-        report.totals.comment_lines,
-        report.totals.blank_lines,
-        report.totals.test_lines,
-        report.totals.non_test_lines,
-        report.totals.test_functions,
-        report.totals.test_code_percentage,
-    );
-    println!(
-        "Functions: total={}, pub={}, non-test={}, files_with_main={}, TODOs={}",
-        report.totals.functions,
-        report.totals.pub_functions,
-        report.totals.non_test_functions,
-        report.totals.files_with_main,
-        report.totals.todo_count,
-    );
-    println!();
-
-    println!("Per-file metrics:");
-    for file in &report.files {
-        println!(
-            "- {} (total={}, code={}, comments={}, blanks={}, tests={}, non-test={}, test_functions={}, fns={}, pub_fns={}, todos={}, is_test_file={})",
-            file.path.display(),
-            file.total_lines,
-            file.code_lines,
-            file.comment_lines,
-            file.blank_lines,
-            file.test_lines,
-            file.non_test_lines,
-            file.test_functions,
-            file.functions,
-            file.pub_functions,
-            file.todo_count,
-            file.is_test_file,
-        );
+/// JSON representation of a `--discover` report including tool metadata.
+#[derive(Debug, Serialize)]
+struct JsonDiscoveryReport<'a> {
+    tool: ToolMeta,
+    #[serde(flatten)]
+    report: &'a DiscoveryReport,
+}
+
+/// Options for the human-readable summary.
+#[derive(Debug, Clone)]
+pub struct HumanOptions {
+    /// Group digits of counts with thousands separators (e.g. `1,234,567`).
+    ///
+    /// Percentages and other fractional values are not affected.
+    pub human_numbers: bool,
+
+    /// Start the summary with a `# format_version: N` line (see [`HUMAN_FORMAT_VERSION`]).
+    pub format_version: bool,
+
+    /// Columns shown on each per-file line, in order; empty means [`FileField::ALL`].
+    pub fields: Vec<FileField>,
+
+    /// Order per-file lines by this column instead of by path.
+    pub sort: Option<FileField>,
+
+    /// Only list the first N files (after sorting).
+    pub top: Option<usize>,
+
+    /// Color the test-code percentage with ANSI escapes (green, yellow below `warn_test_pct`, red below
+    /// `bad_test_pct`).
+    pub color: bool,
+
+    /// Palette used when [`HumanOptions::color`] is set.
+    pub color_theme: ColorTheme,
+
+    /// Test-code percentage below which it is colored yellow (default: 50).
+    pub warn_test_pct: f64,
+
+    /// Test-code percentage below which it is colored red (default: 20).
+    pub bad_test_pct: f64,
+
+    /// Decimal places for percentages and ratios (`--round`); `None` keeps the default of 2.
+    pub round: Option<u32>,
+}
+
+impl Default for HumanOptions {
+    fn default() -> Self {
+        HumanOptions {
+            human_numbers: false,
+            format_version: false,
+            fields: Vec::new(),
+            sort: None,
+            top: None,
+            color: false,
+            color_theme: ColorTheme::default(),
+            warn_test_pct: 50.0,
+            bad_test_pct: 20.0,
+            round: None,
+        }
+    }
+}
+
+/// Palette for colored human output (`--color-theme`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorTheme {
+    /// Bright colors, readable on dark backgrounds.
+    #[default]
+    Dark,
+    /// Regular-intensity colors, readable on light backgrounds.
+    Light,
+}
+
+impl ColorTheme {
+    /// ANSI SGR codes for good, warning and bad values.
+    fn codes(self) -> [&'static str; 3] {
+        match self {
+            ColorTheme::Dark => ["92", "93", "91"],
+            ColorTheme::Light => ["32", "33", "31"],
+        }
+    }
+}
+
+/// A column of the per-file line in human output (`--fields`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FileField {
+    #[value(name = "path")]
+    Path,
+    #[value(name = "total")]
+    Total,
+    #[value(name = "code")]
+    Code,
+    /// Code lines as a percentage of the project's `totals.code_lines`.
+    #[value(name = "pct_of_code")]
+    PctOfCode,
+    #[value(name = "comments")]
+    Comments,
+    #[value(name = "blanks")]
+    Blanks,
+    #[value(name = "tests")]
+    Tests,
+    #[value(name = "non-test")]
+    NonTest,
+    /// Test lines as a percentage of code lines.
+    #[value(name = "test_pct")]
+    TestPct,
+    #[value(name = "test_functions")]
+    TestFunctions,
+    #[value(name = "fns")]
+    Fns,
+    #[value(name = "pub_fns")]
+    PubFns,
+    #[value(name = "todos")]
+    Todos,
+    #[value(name = "unsafe_lines")]
+    UnsafeLines,
+    #[value(name = "indent")]
+    Indent,
+    #[value(name = "is_test_file")]
+    IsTestFile,
+    #[value(name = "ignored")]
+    Ignored,
+}
+
+impl FileField {
+    /// The default column set (every column except `test_pct`).
+    pub const ALL: &'static [FileField] = &[
+        FileField::Path,
+        FileField::Total,
+        FileField::Code,
+        FileField::PctOfCode,
+        FileField::Comments,
+        FileField::Blanks,
+        FileField::Tests,
+        FileField::NonTest,
+        FileField::TestFunctions,
+        FileField::Fns,
+        FileField::PubFns,
+        FileField::Todos,
+        FileField::UnsafeLines,
+        FileField::Indent,
+        FileField::IsTestFile,
+        FileField::Ignored,
+    ];
+
+    /// Label used in `name=value` pairs (the same as the `--fields` name).
+    fn name(self) -> &'static str {
+        match self {
+            FileField::Path => "path",
+            FileField::Total => "total",
+            FileField::Code => "code",
+            FileField::PctOfCode => "pct_of_code",
+            FileField::Comments => "comments",
+            FileField::Blanks => "blanks",
+            FileField::Tests => "tests",
+            FileField::NonTest => "non-test",
+            FileField::TestPct => "test_pct",
+            FileField::TestFunctions => "test_functions",
+            FileField::Fns => "fns",
+            FileField::PubFns => "pub_fns",
+            FileField::Todos => "todos",
+            FileField::UnsafeLines => "unsafe_lines",
+            FileField::Indent => "indent",
+            FileField::IsTestFile => "is_test_file",
+            FileField::Ignored => "ignored",
+        }
     }
+}
 
+/// Write a human-readable summary to `out` (stdout for `--format human`, stderr for `--also-human`).
+pub fn print_human_summary(
+    report: &MetricsReport,
+    opts: &HumanOptions,
+    out: &mut impl Write,
+) -> Result<()> {
+    out.write_all(format_human_summary(report, opts).as_bytes())?;
     Ok(())
 }
 
-/// Write the metrics report as pretty JSON to either stdout or a file.
+/// Render the human-readable summary (as printed by `--format human`) to a `String`, e.g. for embedding.
 ///
-/// The JSON includes a `tool` block with name, version, and schema_version.
-pub fn write_json(report: &MetricsReport, output: Option<&Path>) -> Result<()> {
-    let meta = ToolMeta {
+/// ```
+/// use noir_metrics::{HumanOptions, analyze_files, format_human_summary};
+/// use std::path::{Path, PathBuf};
+///
+/// let files = vec![(PathBuf::from("src/main.nr"), "fn main() {}\n".to_string())];
+/// let report = analyze_files(files, Path::new(".")).unwrap();
+///
+/// let summary = format_human_summary(&report, &HumanOptions::default());
+/// assert!(summary.contains("Files:"));
+/// ```
+pub fn format_human_summary(report: &MetricsReport, opts: &HumanOptions) -> String {
+    let mut buf = Vec::new();
+    write_human_summary(report, opts, &mut buf).expect("writing to a Vec cannot fail");
+    String::from_utf8(buf).expect("the summary is built from UTF-8 strings")
+}
+
+fn write_human_summary(
+    report: &MetricsReport,
+    opts: &HumanOptions,
+    out: &mut impl Write,
+) -> Result<()> {
+    let n = |value: u64| format_count(value, opts);
+
+    if opts.format_version {
+        writeln!(out, "# format_version: {HUMAN_FORMAT_VERSION}")?;
+    }
+    writeln!(out, "Project: {}", report.project_root.display())?;
+    writeln!(
+        out,
+        "Maintainability: {} ({})",
+        format_decimal(report.totals.maintainability_index, opts),
+        maintainability_band(report.totals.maintainability_index),
+    )?;
+    writeln!(
+        out,
+        "Files: {} (ignored={}, embedded={}, empty={})",
+        n(report.totals.files),
+        n(report.totals.ignored_files),
+        n(report.totals.embedded_files),
+        n(report.totals.empty_files)
+    )?;
+    writeln!(
+        out,
+        "Lines: total={}, code={}, comments={}, blanks={}, test={}, non-test={}, test_functions={}, test_code={}",
+        n(report.totals.total_lines),
+        n(report.totals.code_lines),
+        n(report.totals.comment_lines),
+        n(report.totals.blank_lines),
+        n(report.totals.test_lines),
+        n(report.totals.non_test_lines),
+        n(report.totals.test_functions),
+        format_test_pct(report.totals.test_code_percentage, opts),
+    )?;
+    for (label, totals) in [
+        ("Production", &report.totals_production),
+        ("Tests", &report.totals_tests),
+    ] {
+        if let Some(totals) = totals {
+            writeln!(
+                out,
+                "{label}: files={}, code={}, test={}, functions={}, test_code={}",
+                n(totals.files),
+                n(totals.code_lines),
+                n(totals.test_lines),
+                n(totals.functions),
+                format_test_pct(totals.test_code_percentage, opts),
+            )?;
+        }
+    }
+    writeln!(
+        out,
+        "Functions: total={}, pub={}, non-test={}, files_with_main={}, TODOs={}, debug_prints={}, complexity={}, max_complexity={}, avg_length={}",
+        n(report.totals.functions),
+        n(report.totals.pub_functions),
+        n(report.totals.non_test_functions),
+        n(report.totals.files_with_main),
+        n(report.totals.todo_count),
+        n(report.totals.debug_print_count),
+        n(report.totals.complexity),
+        n(report.totals.max_function_complexity),
+        format_decimal(report.totals.average_function_length, opts),
+    )?;
+    let max_in_file = match &report.totals.max_functions_in_file {
+        Some((path, count)) => format!("{} ({})", path.display(), n(*count as u64)),
+        None => "none".to_string(),
+    };
+    writeln!(
+        out,
+        "Functions per file: mean={}, max={max_in_file}",
+        format_decimal(report.totals.mean_functions_per_file, opts),
+    )?;
+    match &report.widest_directory {
+        Some((dir, count)) => writeln!(
+            out,
+            "Directories: max_depth={}, widest={} ({} files)",
+            n(report.max_directory_depth as u64),
+            dir.display(),
+            n(*count as u64),
+        )?,
+        None => writeln!(
+            out,
+            "Directories: max_depth={}, widest=none",
+            n(report.max_directory_depth as u64)
+        )?,
+    }
+    writeln!(
+        out,
+        "Modules: distinct={}, has_lib_or_main={}",
+        n(report.totals.module_count),
+        report.totals.has_lib_or_main,
+    )?;
+    writeln!(
+        out,
+        "Unsafe: blocks={}, lines={}",
+        n(report.totals.unsafe_blocks),
+        n(report.totals.unsafe_lines),
+    )?;
+    writeln!(
+        out,
+        "Contracts: modules={}, public_fns={}, private_fns={}",
+        n(report.totals.contract_modules),
+        n(report.totals.public_functions),
+        n(report.totals.private_functions),
+    )?;
+    writeln!(
+        out,
+        "Indentation: {}",
+        report
+            .totals
+            .indent_styles
+            .iter()
+            .map(|(style, count)| format!("{}={}", indent_style_name(*style), n(*count)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )?;
+    writeln!(
+        out,
+        "Line endings: mixed_files={}",
+        n(report.totals.files_with_mixed_eol)
+    )?;
+    writeln!(out)?;
+
+    writeln!(out, "Per-file metrics:")?;
+    let fields = selected_fields(opts);
+    for file in listed_files(report, opts) {
+        writeln!(
+            out,
+            "{}",
+            format_file_line(file, fields, report.totals.code_lines, opts)
+        )?;
+
+        if !file.public_items.is_empty() {
+            writeln!(out, "    public: {}", file.public_items.join(", "))?;
+        }
+        for a in &file.suspicious_asserts {
+            writeln!(
+                out,
+                "    suspicious assert_eq at line {}: {}",
+                a.line, a.snippet
+            )?;
+        }
+    }
+
+    if !report.longest_functions.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "Longest functions:")?;
+        for (rank, f) in report.longest_functions.iter().enumerate() {
+            writeln!(
+                out,
+                "{}. {}:{}..{} {} ({} lines)",
+                rank + 1,
+                f.path.display(),
+                f.start_line,
+                f.end_line,
+                f.name,
+                f.lines,
+            )?;
+        }
+    }
+
+    if !report.tests.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "Tests:")?;
+        for t in &report.tests {
+            let should_fail = if t.should_fail { " (should_fail)" } else { "" };
+            writeln!(out, "  {}::{}{}", t.path.display(), t.name, should_fail)?;
+        }
+    }
+
+    if !report.duplicate_groups.is_empty() {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "Duplicates: {} duplicate groups",
+            report.duplicate_groups.len()
+        )?;
+        for group in &report.duplicate_groups {
+            let paths: Vec<String> = group.iter().map(|p| p.display().to_string()).collect();
+            writeln!(out, "- {}", paths.join(", "))?;
+        }
+    }
+
+    if let Some(profile) = &report.profile {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "Profile: {}",
+            if profile.passed() { "pass" } else { "FAIL" }
+        )?;
+        for t in &profile.targets {
+            writeln!(
+                out,
+                "- {} {}: target={}, actual={}",
+                if t.passed { "pass" } else { "FAIL" },
+                t.name,
+                t.target,
+                format_decimal(t.actual, opts),
+            )?;
+        }
+    }
+
+    if !report.budget_violations.is_empty() {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "Budget violations: {}",
+            n(report.budget_violations.len() as u64)
+        )?;
+        for v in &report.budget_violations {
+            writeln!(
+                out,
+                "- {}: {}={} (limit {}, budget `{}`)",
+                v.path.display(),
+                v.metric,
+                v.actual,
+                v.limit,
+                v.glob,
+            )?;
+        }
+    }
+
+    if !report.by_author.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "Code lines by author:")?;
+        for (author, lines) in &report.by_author {
+            writeln!(out, "- {author}: {}", n(*lines))?;
+        }
+    }
+
+    if !report.file_diffs.is_empty() {
+        writeln!(out)?;
+        print_file_diffs(&report.file_diffs, report.baseline_schema_version, out)?;
+    }
+
+    Ok(())
+}
+
+/// Write a baseline diff table: a `Baseline diff (...)` header, then one line per added, removed or changed
+/// file with its code, test and TODO deltas (also used by `noir-metrics diff`).
+pub fn print_file_diffs(
+    diffs: &[FileDiff],
+    baseline_schema_version: Option<u32>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let unchanged = diffs
+        .iter()
+        .filter(|d| d.status == FileDiffStatus::Unchanged)
+        .count();
+
+    let schema = baseline_schema_version
+        .map(|v| format!(", baseline schema_version {v}"))
+        .unwrap_or_default();
+    writeln!(out, "Baseline diff ({unchanged} unchanged{schema}):")?;
+    writeln!(
+        out,
+        "  {:<9} {:>6} {:>6} {:>6}  PATH",
+        "STATUS", "CODE", "TESTS", "TODOS"
+    )?;
+    for d in diffs {
+        if d.status == FileDiffStatus::Unchanged {
+            continue;
+        }
+        writeln!(
+            out,
+            "  {:<9} {:>+6} {:>+6} {:>+6}  {}{}",
+            diff_status_name(d.status),
+            d.code_lines_delta,
+            d.test_lines_delta,
+            d.todo_count_delta,
+            d.path.display(),
+            if d.regressed { "  (regressed)" } else { "" },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Render one per-file line: `- <path> (name=value, ...)` restricted to `fields`.
+fn format_file_line(
+    file: &FileMetrics,
+    fields: &[FileField],
+    total_code: u64,
+    opts: &HumanOptions,
+) -> String {
+    let mut line = String::from("-");
+    let mut pairs = Vec::new();
+
+    for field in fields {
+        let value = field_value(file, *field, total_code, opts);
+        if *field == FileField::Path {
+            line.push(' ');
+            line.push_str(&value);
+        } else {
+            pairs.push(format!("{}={}", field.name(), value));
+        }
+    }
+
+    if !pairs.is_empty() {
+        line.push_str(&format!(" ({})", pairs.join(", ")));
+    }
+
+    line
+}
+
+/// Display value of one per-file column; `total_code` is the project's `totals.code_lines`.
+fn field_value(
+    file: &FileMetrics,
+    field: FileField,
+    total_code: u64,
+    opts: &HumanOptions,
+) -> String {
+    let n = |value: usize| format_count(value as u64, opts);
+
+    match field {
+        FileField::Path => file.path.display().to_string(),
+        FileField::TestPct => format!("{}%", format_decimal(test_pct(file), opts)),
+        FileField::PctOfCode => format!("{}%", format_decimal(pct_of_code(file, total_code), opts)),
+        FileField::Indent => indent_style_name(file.indent_style).to_string(),
+        FileField::IsTestFile => file.is_test_file.to_string(),
+        FileField::Ignored => file.ignored.to_string(),
+        count => n(count_value(file, count)),
+    }
+}
+
+/// Raw value of a count column (0 for non-count columns).
+fn count_value(file: &FileMetrics, field: FileField) -> usize {
+    match field {
+        FileField::Total => file.total_lines,
+        FileField::Code | FileField::PctOfCode => file.code_lines,
+        FileField::Comments => file.comment_lines,
+        FileField::Blanks => file.blank_lines,
+        FileField::Tests => file.test_lines,
+        FileField::NonTest => file.non_test_lines,
+        FileField::TestFunctions => file.test_functions,
+        FileField::Fns => file.functions,
+        FileField::PubFns => file.pub_functions,
+        FileField::Todos => file.todo_count,
+        FileField::UnsafeLines => file.unsafe_lines,
+        FileField::IsTestFile => usize::from(file.is_test_file),
+        FileField::Ignored => usize::from(file.ignored),
+        FileField::Path | FileField::TestPct | FileField::Indent => 0,
+    }
+}
+
+/// Test lines as a percentage of code lines (0.0 without code), clamped to `[0, 100]`.
+fn test_pct(file: &FileMetrics) -> f64 {
+    test_code_percentage(file.test_lines as u64, file.code_lines as u64)
+}
+
+/// The file's code lines as a percentage of `total_code` (0.0 when the project has no code).
+///
+/// Ignored files are excluded from `total_code`, so their share is 0.0.
+fn pct_of_code(file: &FileMetrics, total_code: u64) -> f64 {
+    if total_code == 0 || file.ignored {
+        0.0
+    } else {
+        file.code_lines as f64 * 100.0 / total_code as f64
+    }
+}
+
+/// Files listed per file in human and table output, honoring [`HumanOptions::sort`] and [`HumanOptions::top`].
+///
+/// Sorting by `path` or `indent` is ascending; every other column sorts descending (largest first). Ties
+/// keep path order.
+fn listed_files<'a>(report: &'a MetricsReport, opts: &HumanOptions) -> Vec<&'a FileMetrics> {
+    let mut files: Vec<&FileMetrics> = report.files.iter().collect();
+
+    if let Some(field) = opts.sort {
+        files.sort_by(|a, b| {
+            let order = match field {
+                FileField::Path => a.path.cmp(&b.path),
+                FileField::Indent => {
+                    indent_style_name(a.indent_style).cmp(indent_style_name(b.indent_style))
+                }
+                FileField::TestPct => test_pct(b).total_cmp(&test_pct(a)),
+                count => count_value(b, count).cmp(&count_value(a, count)),
+            };
+            order.then_with(|| a.path.cmp(&b.path))
+        });
+    }
+
+    if let Some(top) = opts.top {
+        files.truncate(top);
+    }
+
+    files
+}
+
+/// Print the per-file metrics as a column-aligned table (`--format table`).
+pub fn print_table(report: &MetricsReport, opts: &HumanOptions) -> Result<()> {
+    print!("{}", format_table(report, opts));
+    Ok(())
+}
+
+/// Render the per-file table (`--format table`) to a `String`: an upper-case header row, then one row per
+/// listed file.
+///
+/// Column widths are derived from the content, so output is deterministic. `path` and `indent` are
+/// left-aligned; all other columns are right-aligned.
+pub fn format_table(report: &MetricsReport, opts: &HumanOptions) -> String {
+    let fields = selected_fields(opts);
+
+    let header: Vec<String> = fields.iter().map(|f| f.name().to_uppercase()).collect();
+    let rows: Vec<Vec<String>> = listed_files(report, opts)
+        .into_iter()
+        .map(|file| {
+            fields
+                .iter()
+                .map(|f| field_value(file, *f, report.totals.code_lines, opts))
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..fields.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([header[i].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(fields)
+            .zip(&widths)
+            .map(|((cell, field), width)| match field {
+                FileField::Path | FileField::Indent => format!("{cell:<width$}"),
+                _ => format!("{cell:>width$}"),
+            })
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Columns selected with `--fields`, or [`FileField::ALL`].
+fn selected_fields(opts: &HumanOptions) -> &[FileField] {
+    if opts.fields.is_empty() {
+        FileField::ALL
+    } else {
+        &opts.fields
+    }
+}
+
+/// Print each source line with the classification and brace depth recorded in `trace`.
+pub fn print_line_trace(source: &str, trace: &[LineTrace]) {
+    for (text, t) in source.lines().zip(trace) {
+        let row = format!(
+            "{:>5}  {:<7}  depth={:<3} {}",
+            t.line,
+            line_kind_name(t.kind),
+            t.brace_depth,
+            text,
+        );
+        println!("{}", row.trim_end());
+    }
+}
+
+/// Format a count, grouping thousands when [`HumanOptions::human_numbers`] is set.
+fn format_count(value: u64, opts: &HumanOptions) -> String {
+    if opts.human_numbers {
+        group_thousands(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Format a percentage or ratio with [`HumanOptions::round`] decimal places (2 by default).
+fn format_decimal(value: f64, opts: &HumanOptions) -> String {
+    format!("{value:.*}", opts.round.unwrap_or(2) as usize)
+}
+
+/// Format a test-code percentage, colored by the [`HumanOptions`] thresholds when color is enabled.
+fn format_test_pct(pct: f64, opts: &HumanOptions) -> String {
+    let text = format!("{}%", format_decimal(pct, opts));
+    if !opts.color {
+        return text;
+    }
+
+    let [good, warn, bad] = opts.color_theme.codes();
+    let code = if pct < opts.bad_test_pct {
+        bad
+    } else if pct < opts.warn_test_pct {
+        warn
+    } else {
+        good
+    };
+    format!("\x1b[{code}m{text}\x1b[0m")
+}
+
+/// Insert `,` separators every three digits (e.g. `1234567` -> `1,234,567`).
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(ch);
+    }
+
+    grouped
+}
+
+/// Lowercase display name for a file diff status (matches its JSON form).
+fn diff_status_name(status: FileDiffStatus) -> &'static str {
+    match status {
+        FileDiffStatus::Added => "added",
+        FileDiffStatus::Removed => "removed",
+        FileDiffStatus::Changed => "changed",
+        FileDiffStatus::Unchanged => "unchanged",
+    }
+}
+
+/// Qualitative band for a maintainability index: `good` (>= 70), `ok` (>= 40), otherwise `poor`.
+fn maintainability_band(score: f64) -> &'static str {
+    if score >= 70.0 {
+        "good"
+    } else if score >= 40.0 {
+        "ok"
+    } else {
+        "poor"
+    }
+}
+
+/// Lowercase display name for a line classification.
+fn line_kind_name(kind: LineKind) -> &'static str {
+    match kind {
+        LineKind::Blank => "blank",
+        LineKind::Comment => "comment",
+        LineKind::Code => "code",
+        LineKind::Test => "test",
+    }
+}
+
+/// Lowercase display name for an indentation style (matches its JSON form).
+fn indent_style_name(style: IndentStyle) -> &'static str {
+    match style {
+        IndentStyle::None => "none",
+        IndentStyle::Tabs => "tabs",
+        IndentStyle::Spaces => "spaces",
+        IndentStyle::Mixed => "mixed",
+    }
+}
+
+/// The `tool` metadata block used by all JSON output.
+fn tool_meta() -> ToolMeta {
+    ToolMeta {
         name: "noir-metrics",
         version: env!("CARGO_PKG_VERSION"),
         schema_version: JSON_SCHEMA_VERSION,
+    }
+}
+
+/// Wrap a report with the `tool` metadata block used by all JSON output.
+pub(crate) fn json_report(report: &MetricsReport) -> JsonReport<'_> {
+    JsonReport {
+        tool: tool_meta(),
+        report,
+    }
+}
+
+/// Write a `--discover` report as pretty JSON to either stdout or a file.
+pub fn write_discovery_json(report: &DiscoveryReport, output: Option<&Path>) -> Result<()> {
+    let doc = JsonDiscoveryReport {
+        tool: tool_meta(),
+        report,
+    };
+    write_pretty(&doc, output)
+}
+
+/// Write a human-readable `--discover` summary: one line per project, then the combined totals.
+pub fn print_discovery_summary(
+    report: &DiscoveryReport,
+    opts: &HumanOptions,
+    out: &mut impl Write,
+) -> Result<()> {
+    let n = |value: u64| format_count(value, opts);
+    let line = |totals: &ProjectTotals| {
+        format!(
+            "files={}, code={}, test={}, functions={}, test_code={}%",
+            n(totals.files),
+            n(totals.code_lines),
+            n(totals.test_lines),
+            n(totals.functions),
+            format_decimal(totals.test_code_percentage, opts),
+        )
+    };
+
+    writeln!(out, "Projects: {}", report.projects.len())?;
+    for (root, project) in &report.projects {
+        writeln!(out, "- {root}: {}", line(&project.totals))?;
+    }
+    writeln!(out, "Combined: {}", line(&report.totals))?;
+
+    Ok(())
+}
+
+/// Options for JSON output (`--format json` and `flat-json`).
+#[derive(Debug, Clone, Default)]
+pub struct JsonOptions {
+    /// Only emit the `tool` and `totals` blocks (`--totals-only`).
+    ///
+    /// The result is a subset of the full report and does not validate against [`report_schema`](crate::report_schema).
+    pub totals_only: bool,
+
+    /// Only keep these metric fields in `totals` and each `files[*]` entry (glob patterns such as `todo_*`);
+    /// empty keeps every field. A file's `path` is always kept.
+    pub allow_fields: Vec<String>,
+
+    /// Drop these metric fields from `totals` and each `files[*]` entry (glob patterns), after `allow_fields`.
+    pub deny_fields: Vec<String>,
+
+    /// Round every fractional value (percentages and ratios) to this many decimal places (`--round`);
+    /// `None` keeps full precision.
+    pub round: Option<u32>,
+
+    /// Run metadata emitted as a `meta.tags` block next to `tool` (`--tag`, `--label`); omitted when empty.
+    pub tags: BTreeMap<String, String>,
+}
+
+/// The JSON document for `report`, restricted according to `opts`.
+///
+/// Field filtering makes the document a subset of the full report, which no longer validates against
+/// [`report_schema`](crate::report_schema) when required fields are dropped.
+fn json_value(report: &MetricsReport, opts: &JsonOptions) -> Result<Value> {
+    let mut value = serde_json::to_value(json_report(report))?;
+
+    if opts.totals_only
+        && let Value::Object(map) = &mut value
+    {
+        map.retain(|key, _| key == "tool" || key == "totals");
+    }
+
+    if !opts.tags.is_empty()
+        && let Value::Object(map) = &mut value
+    {
+        map.insert("meta".to_string(), serde_json::json!({ "tags": opts.tags }));
+    }
+
+    if let Some(Value::Array(files)) = value.get_mut("files") {
+        for (file, fm) in files.iter_mut().zip(&report.files) {
+            if let Value::Object(file) = file {
+                file.insert(
+                    "pct_of_code".to_string(),
+                    pct_of_code(fm, report.totals.code_lines).into(),
+                );
+            }
+        }
+    }
+
+    if !opts.allow_fields.is_empty() || !opts.deny_fields.is_empty() {
+        let allow = field_patterns(&opts.allow_fields)?;
+        let deny = field_patterns(&opts.deny_fields)?;
+        let keep = |key: &str| {
+            (allow.is_empty() || allow.iter().any(|p| p.matches(key)))
+                && !deny.iter().any(|p| p.matches(key))
+        };
+
+        if let Some(Value::Object(totals)) = value.get_mut("totals") {
+            totals.retain(|key, _| keep(key));
+        }
+        if let Some(Value::Array(files)) = value.get_mut("files") {
+            for file in files.iter_mut().filter_map(Value::as_object_mut) {
+                file.retain(|key, _| key == "path" || keep(key));
+            }
+        }
+    }
+
+    if let Some(digits) = opts.round {
+        round_floats(&mut value, digits);
+    }
+
+    Ok(value)
+}
+
+/// Round every non-integer number in `value` to `digits` decimal places, in place.
+///
+/// Counts serialize as integers and are left alone; every fractional field in the report is a percentage or
+/// ratio.
+fn round_floats(value: &mut Value, digits: u32) {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            let scale = 10f64.powi(digits as i32);
+            let rounded = number.as_f64().map(|x| (x * scale).round() / scale);
+            if let Some(rounded) = rounded.and_then(serde_json::Number::from_f64) {
+                *number = rounded;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| round_floats(item, digits)),
+        Value::Object(map) => map.values_mut().for_each(|item| round_floats(item, digits)),
+        _ => {}
+    }
+}
+
+/// Compile `--allow-field`/`--deny-field` globs.
+fn field_patterns(globs: &[String]) -> Result<Vec<Pattern>> {
+    globs
+        .iter()
+        .map(|g| Pattern::new(g).with_context(|| format!("invalid field pattern `{g}`")))
+        .collect()
+}
+
+/// Write the metrics report as pretty JSON to either stdout or a file.
+///
+/// The JSON includes a `tool` block with name, version, and schema_version.
+pub fn write_json(report: &MetricsReport, output: Option<&Path>, opts: &JsonOptions) -> Result<()> {
+    write_pretty(&json_value(report, opts)?, output)
+}
+
+/// Write the per-file metrics as CSV (`--format csv`) to either stdout or a file.
+///
+/// See [`to_csv`] for the column layout; the file can be used as a `--baseline`.
+pub fn write_csv(report: &MetricsReport, output: Option<&Path>) -> Result<()> {
+    let csv = to_csv(report);
+
+    match output {
+        Some(path) => write_atomic(path, csv.as_bytes())?,
+        None => io::stdout().lock().write_all(csv.as_bytes())?,
+    }
+
+    Ok(())
+}
+
+/// One file's share of a total (`--explain-totals`).
+#[derive(Debug, Clone, PartialEq)]
+struct Contribution<'a> {
+    path: &'a Path,
+    value: u64,
+    /// Share of the total, in percent.
+    percentage: f64,
+    /// Running share of this and all larger contributions, in percent.
+    cumulative: f64,
+}
+
+/// Per-file contributions to the total of `metric` (a per-file CSV column such as `code_lines`).
+///
+/// Ignored files are skipped, as they are for totals. Files are sorted by value descending, then by path;
+/// percentages are 0 when the total is 0.
+fn explain_totals<'a>(report: &'a MetricsReport, metric: &str) -> Result<Vec<Contribution<'a>>> {
+    if !numeric_columns().any(|column| column == metric) {
+        bail!(
+            "`{metric}` is not a per-file numeric metric (choose one of: {})",
+            numeric_columns().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    let mut values: Vec<(&Path, u64)> = report
+        .files
+        .iter()
+        .filter(|fm| !fm.ignored)
+        .map(|fm| (fm.path.as_path(), numeric_value(fm, metric).unwrap_or(0)))
+        .collect();
+    values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    let total: u64 = values.iter().map(|(_, v)| v).sum();
+    let percent = |v: u64| {
+        if total == 0 {
+            0.0
+        } else {
+            v as f64 * 100.0 / total as f64
+        }
     };
 
-    let wrapper = JsonReport { tool: meta, report };
+    let mut running = 0;
+    Ok(values
+        .into_iter()
+        .map(|(path, value)| {
+            running += value;
+            Contribution {
+                path,
+                value,
+                percentage: percent(value),
+                cumulative: percent(running),
+            }
+        })
+        .collect())
+}
+
+/// Print each file's contribution to the total of `metric`, largest first, with a running cumulative
+/// percentage (a Pareto view, `--explain-totals`).
+pub fn print_explain_totals(
+    report: &MetricsReport,
+    metric: &str,
+    out: &mut impl Write,
+) -> Result<()> {
+    let contributions = explain_totals(report, metric)?;
+    let total: u64 = contributions.iter().map(|c| c.value).sum();
+
+    writeln!(
+        out,
+        "{metric}: {total} across {} file(s)",
+        contributions.len()
+    )?;
+    writeln!(out, "{:>10} {:>7} {:>7}  path", "value", "share", "cumul")?;
+    for c in &contributions {
+        writeln!(
+            out,
+            "{:>10} {:>6.1}% {:>6.1}%  {}",
+            c.value,
+            c.percentage,
+            c.cumulative,
+            c.path.display()
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Write one NDJSON record `{"<kind>": <value>}` to `out` and flush it (`--ndjson-per-file`).
+pub fn write_ndjson_record<T: Serialize>(
+    out: &mut impl Write,
+    kind: &str,
+    value: &T,
+) -> Result<()> {
+    let mut record = serde_json::Map::new();
+    record.insert(kind.to_string(), serde_json::to_value(value)?);
+    serde_json::to_writer(&mut *out, &record)?;
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Headline totals printed by `--summary-json`.
+#[derive(Serialize)]
+struct TotalsSummary {
+    files: u64,
+    code_lines: u64,
+    test_code_percentage: f64,
+}
+
+/// Render headline totals as compact one-line JSON for wrapper scripts (`--summary-json`):
+/// `{"files":..,"code_lines":..,"test_code_percentage":..}`.
+pub fn totals_json(totals: &ProjectTotals) -> Result<String> {
+    Ok(serde_json::to_string(&TotalsSummary {
+        files: totals.files,
+        code_lines: totals.code_lines,
+        test_code_percentage: totals.test_code_percentage,
+    })?)
+}
+
+/// Append one NDJSON record for this run to `path` (`--json-stream-to`), creating the file if absent:
+/// `{"timestamp":<unix seconds>,"schema_version":N,"totals":{...}}`.
+///
+/// The whole line goes out in one write on a file opened in append mode, so records from concurrent runs
+/// do not interleave and every line parses on its own.
+pub fn append_json_record(report: &MetricsReport, path: &Path) -> Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let mut line = serde_json::to_string(&serde_json::json!({
+        "timestamp": timestamp,
+        "schema_version": JSON_SCHEMA_VERSION,
+        "totals": report.totals,
+    }))?;
+    line.push('\n');
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("failed to append to {}", path.display()))
+}
+
+/// Write per-file code lines in the folded stack format read by flamegraph tools (`--format folded`).
+///
+/// See [`to_folded`] for the line format.
+pub fn write_folded(report: &MetricsReport, output: Option<&Path>) -> Result<()> {
+    let folded = to_folded(report);
 
     match output {
-        Some(path) => {
-            let file = File::create(path)?;
-            serde_json::to_writer_pretty(file, &wrapper)?;
+        Some(path) => write_atomic(path, folded.as_bytes())?,
+        None => io::stdout().lock().write_all(folded.as_bytes())?,
+    }
+
+    Ok(())
+}
+
+/// Render one line per file: the path components joined by `;`, a space, and the file's code lines
+/// (e.g. `src;gadgets;hash.nr 120`), as rendered by `flamegraph.pl`.
+///
+/// Files are listed in report order; ignored files and files without code lines are skipped.
+fn to_folded(report: &MetricsReport) -> String {
+    let mut out = String::new();
+
+    for fm in report
+        .files
+        .iter()
+        .filter(|fm| !fm.ignored && fm.code_lines > 0)
+    {
+        let stack: Vec<String> = fm
+            .path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        out.push_str(&format!("{} {}\n", stack.join(";"), fm.code_lines));
+    }
+
+    out
+}
+
+/// Write the report's findings as a SARIF 2.1.0 log (`--format sarif`) to either stdout or a file.
+///
+/// See [`to_sarif`] for the reported rules.
+pub fn write_sarif(report: &MetricsReport, output: Option<&Path>) -> Result<()> {
+    write_pretty(&to_sarif(report), output)
+}
+
+/// Write the metrics report as a single-level JSON object with dotted keys (`--format flat-json`).
+///
+/// Flattening rules:
+/// - Object keys are joined with `.` (e.g. `totals.code_lines`, `tool.schema_version`).
+/// - Array elements are addressed by index in brackets (e.g. `files[0].path`).
+/// - Numbers, strings, booleans and `null` are kept as-is; empty objects and arrays produce no keys.
+///
+/// Keys are sorted.
+pub fn write_flat_json(
+    report: &MetricsReport,
+    output: Option<&Path>,
+    opts: &JsonOptions,
+) -> Result<()> {
+    let nested = json_value(report, opts)?;
+
+    let mut flat = BTreeMap::new();
+    flatten_json(&nested, String::new(), &mut flat);
+
+    write_pretty(&flat, output)
+}
+
+/// Recursively flatten `value` into `out`, using `prefix` as the key of `value` itself.
+fn flatten_json(value: &Value, prefix: String, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten_json(child, key, out);
+            }
         }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                flatten_json(child, format!("{prefix}[{i}]"), out);
+            }
+        }
+        scalar => {
+            out.insert(prefix, scalar.clone());
+        }
+    }
+}
+
+/// Serialize `value` as pretty JSON to a file, or to stdout followed by a newline.
+fn write_pretty<T: Serialize>(value: &T, output: Option<&Path>) -> Result<()> {
+    match output {
+        Some(path) => write_atomic(path, &serde_json::to_vec_pretty(value)?)?,
         None => {
             let stdout = io::stdout();
             let mut handle = stdout.lock();
-            serde_json::to_writer_pretty(&mut handle, &wrapper)?;
+            serde_json::to_writer_pretty(&mut handle, value)?;
             writeln!(handle)?; // newline at the end
         }
     }
@@ -97,9 +1210,41 @@ pub fn write_json(report: &MetricsReport, output: Option<&Path>) -> Result<()> {
     Ok(())
 }
 
+/// Write `bytes` to `path` so readers only ever see the old or the complete new content.
+///
+/// The data goes to a hidden temporary sibling (same directory, so the same filesystem), is synced to disk,
+/// and is then renamed over `path`. The temporary file is removed if any step fails.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("output path {} has no file name", path.display()))?;
+    let tmp = path.with_file_name(format!(
+        ".{}.tmp-{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = (|| -> Result<()> {
+        let mut file = File::create(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result.with_context(|| format!("failed to write {}", path.display()))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::write_json;
+    use super::{
+        ColorTheme, HumanOptions, JsonOptions, explain_totals, flatten_json, format_count,
+        format_test_pct, group_thousands, json_value, to_folded, totals_json, write_csv,
+        write_json,
+    };
     use crate::analysis::file::FileMetrics;
     use crate::analysis::project::{MetricsReport, ProjectTotals};
     use std::path::PathBuf;
@@ -133,8 +1278,9 @@ mod tests {
                 pub_functions: 0,
                 non_test_functions: 1,
                 has_main: true,
-                todo_count: 0,
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         // Write to a unique temp file.
@@ -148,7 +1294,8 @@ mod tests {
         // If something already exists (unlikely), remove it.
         let _ = std::fs::remove_file(&out_path);
 
-        write_json(&report, Some(&out_path)).expect("write_json should succeed");
+        write_json(&report, Some(&out_path), &JsonOptions::default())
+            .expect("write_json should succeed");
 
         let s = std::fs::read_to_string(&out_path).expect("expected output json file to exist");
 
@@ -168,4 +1315,272 @@ mod tests {
 
         let _ = std::fs::remove_file(&out_path);
     }
+
+    #[test]
+    fn output_files_are_replaced_without_leftover_temp_files() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("noir_metrics_atomic_{unique}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        let out_path = dir.join("report.json");
+
+        // An existing (e.g. truncated) file is replaced as a whole.
+        std::fs::write(&out_path, "{\"tool\":").unwrap();
+        let report = MetricsReport {
+            files: vec![FileMetrics::default()],
+            ..Default::default()
+        };
+        write_json(&report, Some(&out_path), &JsonOptions::default())
+            .expect("write_json should succeed");
+
+        let s = std::fs::read_to_string(&out_path).expect("output should exist");
+        let v: serde_json::Value = serde_json::from_str(&s).expect("output should parse");
+        assert!(v["files"].is_array());
+
+        write_csv(&report, Some(&dir.join("report.csv"))).expect("write_csv should succeed");
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["report.csv", "report.json"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn explain_totals_is_a_descending_pareto_view() {
+        let file = |path: &str, code_lines: usize| FileMetrics {
+            path: PathBuf::from(path),
+            code_lines,
+            ..Default::default()
+        };
+        let report = MetricsReport {
+            files: vec![
+                file("src/a.nr", 10),
+                file("src/b.nr", 60),
+                file("src/c.nr", 30),
+                FileMetrics {
+                    ignored: true,
+                    ..file("src/ignored.nr", 1000)
+                },
+            ],
+            ..Default::default()
+        };
+
+        let contributions = explain_totals(&report, "code_lines").expect("metric should exist");
+        let values: Vec<u64> = contributions.iter().map(|c| c.value).collect();
+        assert_eq!(values, [60, 30, 10]);
+
+        let shares: f64 = contributions.iter().map(|c| c.percentage).sum();
+        assert!((shares - 100.0).abs() < 1e-9);
+        let cumulative: Vec<f64> = contributions.iter().map(|c| c.cumulative).collect();
+        assert_eq!(cumulative, [60.0, 90.0, 100.0]);
+
+        assert!(explain_totals(&report, "path").is_err());
+        assert!(explain_totals(&report, "is_test_file").is_err());
+    }
+
+    #[test]
+    fn pct_of_code_shares_sum_to_one_hundred() {
+        let report = MetricsReport {
+            totals: ProjectTotals {
+                code_lines: 30,
+                ..Default::default()
+            },
+            files: [(10, false), (15, false), (40, true), (5, false)]
+                .into_iter()
+                .map(|(code_lines, ignored)| FileMetrics {
+                    code_lines,
+                    ignored,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let v = json_value(&report, &JsonOptions::default()).expect("report should serialize");
+        let shares: Vec<f64> = v["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| {
+                f["pct_of_code"]
+                    .as_f64()
+                    .expect("pct_of_code should be a number")
+            })
+            .collect();
+        assert!(
+            (shares.iter().sum::<f64>() - 100.0).abs() < 1e-9,
+            "{shares:?}"
+        );
+        assert!((shares[0] - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(shares[2], 0.0, "ignored files have no share");
+
+        let empty = MetricsReport {
+            files: vec![FileMetrics::default()],
+            ..Default::default()
+        };
+        let v = json_value(&empty, &JsonOptions::default()).expect("report should serialize");
+        assert_eq!(v["files"][0]["pct_of_code"], 0.0);
+    }
+
+    #[test]
+    fn group_thousands_inserts_separators() {
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1000), "1,000");
+        assert_eq!(group_thousands(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn format_count_groups_only_under_flag() {
+        let plain = HumanOptions::default();
+        let grouped = HumanOptions {
+            human_numbers: true,
+            ..Default::default()
+        };
+
+        assert_eq!(format_count(1234567, &plain), "1234567");
+        assert_eq!(format_count(1234567, &grouped), "1,234,567");
+    }
+
+    #[test]
+    fn test_pct_is_colored_by_threshold_and_theme() {
+        let plain = HumanOptions::default();
+        let colored = HumanOptions {
+            color: true,
+            ..Default::default()
+        };
+        let light = HumanOptions {
+            color: true,
+            color_theme: ColorTheme::Light,
+            ..Default::default()
+        };
+
+        assert_eq!(format_test_pct(10.0, &plain), "10.00%");
+        assert_eq!(format_test_pct(10.0, &colored), "\x1b[91m10.00%\x1b[0m");
+        assert_eq!(format_test_pct(35.0, &colored), "\x1b[93m35.00%\x1b[0m");
+        assert_eq!(format_test_pct(50.0, &colored), "\x1b[92m50.00%\x1b[0m");
+        assert_eq!(format_test_pct(10.0, &light), "\x1b[31m10.00%\x1b[0m");
+    }
+
+    #[test]
+    fn flatten_json_uses_dotted_keys_and_indices() {
+        let nested = serde_json::json!({
+            "totals": { "code_lines": 27 },
+            "files": [{ "path": "src/main.nr", "has_main": true }],
+            "by_author": {},
+        });
+
+        let mut flat = std::collections::BTreeMap::new();
+        flatten_json(&nested, String::new(), &mut flat);
+
+        assert_eq!(flat["totals.code_lines"], 27);
+        assert_eq!(flat["files[0].path"], "src/main.nr");
+        assert_eq!(flat["files[0].has_main"], true);
+        assert_eq!(flat.len(), 3);
+    }
+
+    #[test]
+    fn totals_only_keeps_tool_and_totals() {
+        let report = MetricsReport {
+            files: vec![FileMetrics::default()],
+            ..Default::default()
+        };
+        let opts = JsonOptions {
+            totals_only: true,
+            ..Default::default()
+        };
+
+        let v = json_value(&report, &opts).expect("report should serialize");
+        let keys: Vec<&String> = v.as_object().unwrap().keys().collect();
+
+        assert_eq!(keys, ["tool", "totals"]);
+    }
+
+    #[test]
+    fn to_folded_joins_path_components_with_semicolons() {
+        let file = |path: &str, code_lines: usize| FileMetrics {
+            path: PathBuf::from(path),
+            code_lines,
+            ..Default::default()
+        };
+        let report = MetricsReport {
+            files: vec![
+                file("src/gadgets/hash.nr", 120),
+                file("src/main.nr", 7),
+                file("src/empty.nr", 0),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            to_folded(&report),
+            "src;gadgets;hash.nr 120\nsrc;main.nr 7\n"
+        );
+    }
+
+    #[test]
+    fn totals_json_keeps_headline_totals() {
+        let totals = ProjectTotals {
+            files: 3,
+            code_lines: 40,
+            test_code_percentage: 25.0,
+            functions: 7,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            totals_json(&totals).unwrap(),
+            r#"{"files":3,"code_lines":40,"test_code_percentage":25.0}"#
+        );
+    }
+
+    #[test]
+    fn field_filters_apply_to_files_and_totals() {
+        let report = MetricsReport {
+            totals: ProjectTotals {
+                blank_lines: 2,
+                code_lines: 5,
+                ..Default::default()
+            },
+            files: vec![FileMetrics {
+                path: PathBuf::from("src/main.nr"),
+                blank_lines: 2,
+                code_lines: 5,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let denied = json_value(
+            &report,
+            &JsonOptions {
+                deny_fields: vec!["blank_lines".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(denied["totals"].get("blank_lines").is_none());
+        assert!(denied["files"][0].get("blank_lines").is_none());
+        assert_eq!(denied["totals"]["code_lines"], 5);
+        assert_eq!(denied["files"][0]["code_lines"], 5);
+
+        let allowed = json_value(
+            &report,
+            &JsonOptions {
+                allow_fields: vec!["code_*".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let keys: Vec<&String> = allowed["files"][0].as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["code_lines", "path"]);
+        let keys: Vec<&String> = allowed["totals"].as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["code_lines"]);
+    }
 }
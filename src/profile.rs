@@ -0,0 +1,152 @@
+use crate::analysis::project::MetricsReport;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Desired metric targets for a project (the `[profile]` table of `noir-metrics.toml`).
+///
+/// Every target is optional; only configured targets are evaluated.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Minimum [`ProjectTotals::test_code_percentage`](crate::ProjectTotals::test_code_percentage).
+    pub min_test_code_percentage: Option<f64>,
+
+    /// Maximum complexity of any single function.
+    pub max_function_complexity: Option<u64>,
+
+    /// Minimum comment density (`comment_lines / (comment_lines + code_lines)`, 0.0–1.0).
+    pub min_comment_density: Option<f64>,
+
+    /// Maximum number of TODO/FIXME markers.
+    pub max_todo_count: Option<u64>,
+
+    /// Minimum [`ProjectTotals::maintainability_index`](crate::ProjectTotals::maintainability_index).
+    pub min_maintainability_index: Option<f64>,
+}
+
+/// Outcome of evaluating a single profile target.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TargetResult {
+    /// Target name as written in the profile (e.g. `min_test_code_percentage`).
+    pub name: String,
+
+    /// Configured target value.
+    pub target: f64,
+
+    /// Actual value measured for the project.
+    pub actual: f64,
+
+    /// Did the actual value meet the target?
+    pub passed: bool,
+}
+
+/// Outcome of evaluating a whole [`Profile`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProfileResult {
+    /// One entry per configured target, in declaration order of [`Profile`].
+    pub targets: Vec<TargetResult>,
+}
+
+impl ProfileResult {
+    /// Did every configured target pass?
+    pub fn passed(&self) -> bool {
+        self.targets.iter().all(|t| t.passed)
+    }
+}
+
+/// Evaluate a report's totals against a profile.
+pub fn evaluate_profile(report: &MetricsReport, profile: &Profile) -> ProfileResult {
+    let totals = &report.totals;
+    let targets = [
+        min_target(
+            "min_test_code_percentage",
+            profile.min_test_code_percentage,
+            totals.test_code_percentage,
+        ),
+        max_target(
+            "max_function_complexity",
+            profile.max_function_complexity,
+            totals.max_function_complexity,
+        ),
+        min_target(
+            "min_comment_density",
+            profile.min_comment_density,
+            totals.comment_density(),
+        ),
+        max_target("max_todo_count", profile.max_todo_count, totals.todo_count),
+        min_target(
+            "min_maintainability_index",
+            profile.min_maintainability_index,
+            totals.maintainability_index,
+        ),
+    ];
+
+    ProfileResult {
+        targets: targets.into_iter().flatten().collect(),
+    }
+}
+
+/// A `min_*` target: passes when `actual` is at least `target`.
+fn min_target(name: &str, target: Option<f64>, actual: f64) -> Option<TargetResult> {
+    target.map(|target| TargetResult {
+        name: name.to_string(),
+        target,
+        actual,
+        passed: actual >= target,
+    })
+}
+
+/// A `max_*` target: passes when `actual` is at most `target`.
+fn max_target(name: &str, target: Option<u64>, actual: u64) -> Option<TargetResult> {
+    target.map(|target| TargetResult {
+        name: name.to_string(),
+        target: target as f64,
+        actual: actual as f64,
+        passed: actual <= target,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::options::AnalysisOptions;
+    use crate::analysis::project::analyze_project;
+    use crate::project::Project;
+    use std::path::PathBuf;
+
+    #[test]
+    fn evaluates_profile_with_one_failing_target() {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/project_metrics"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+
+        // Fixture: 66.67% test code, 1 TODO, max function complexity 1.
+        let profile = Profile {
+            min_test_code_percentage: Some(50.0),
+            max_function_complexity: Some(5),
+            min_comment_density: Some(0.0),
+            max_todo_count: Some(0),
+            ..Default::default()
+        };
+
+        let result = evaluate_profile(&report, &profile);
+
+        let summary: Vec<(&str, bool)> = result
+            .targets
+            .iter()
+            .map(|t| (t.name.as_str(), t.passed))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("min_test_code_percentage", true),
+                ("max_function_complexity", true),
+                ("min_comment_density", true),
+                ("max_todo_count", false),
+            ]
+        );
+        assert!(!result.passed());
+        assert_eq!(result.targets[3].actual, 1.0);
+    }
+}
@@ -1,8 +1,54 @@
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use walkdir::WalkDir;
 
+/// Default for [`WalkOptions::max_files`].
+pub const DEFAULT_MAX_FILES: usize = 100_000;
+
+/// Options controlling which `.nr` files [`Project::nr_files`] discovers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// Descend into subdirectories of the project root (default: `true`).
+    ///
+    /// When `false`, only `.nr` files directly inside the walked directory are found.
+    pub recursive: bool,
+
+    /// Walk the whole project root instead of only its source directory (see [`Project::source_dir`])
+    /// (default: `false`).
+    pub all: bool,
+
+    /// Include hidden (dot-prefixed) files and directories below the project root (default: `false`).
+    pub include_hidden: bool,
+
+    /// Include vendored path dependencies (see [`Project::dependency_dirs`]) (default: `false`).
+    pub include_deps: bool,
+
+    /// Fail the walk once more than this many matching files are found; `0` means unlimited
+    /// (default: [`DEFAULT_MAX_FILES`]).
+    ///
+    /// Guards against accidentally analyzing a huge tree such as `/`.
+    pub max_files: usize,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            recursive: true,
+            all: false,
+            include_hidden: false,
+            include_deps: false,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
 /// Represents a Noir project on disk.
+///
+/// The `.nr` file list is walked once and cached (see [`Project::nr_files`]). `Project` is `Send + Sync`:
+/// the cache sits behind a mutex, so a shared handle can be used from several threads and concurrent
+/// callers wait for a single walk.
 #[derive(Debug)]
 pub struct Project {
     /// Absolute path to the project root directory.
@@ -10,6 +56,12 @@ pub struct Project {
 
     /// Absolute path to `Nargo.toml` inside the project root.
     pub manifest_path: PathBuf,
+
+    /// Options used when discovering `.nr` files.
+    pub walk: WalkOptions,
+
+    /// `.nr` files found by the last walk, with the options that walk used.
+    nr_files_cache: Mutex<Option<(WalkOptions, Vec<PathBuf>)>>,
 }
 
 impl Project {
@@ -34,20 +86,162 @@ impl Project {
         Ok(Project {
             root,
             manifest_path,
+            walk: WalkOptions::default(),
+            nr_files_cache: Mutex::new(None),
         })
     }
 
-    /// Find all `.nr` files under the project root (recursively).
+    /// Find all `.nr` files in the project's source directory (see [`Project::source_dir`]), or under the
+    /// whole project root when [`WalkOptions::all`] is set or there is no source directory.
     ///
+    /// The walk is recursive unless [`WalkOptions::recursive`] is disabled, and skips hidden
+    /// (dot-prefixed) entries below the root unless [`WalkOptions::include_hidden`] is set.
+    /// Directories of vendored path dependencies are skipped unless [`WalkOptions::include_deps`] is set.
     /// Returned paths are sorted for stable output.
+    ///
+    /// The list is cached after the first call and reused until [`Project::refresh_files`] is called or
+    /// [`Project::walk`] changes; files added or removed on disk in between are not noticed.
     pub fn nr_files(&self) -> Result<Vec<PathBuf>> {
+        let mut cache = self
+            .nr_files_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some((walk, files)) = cache.as_ref()
+            && *walk == self.walk
+        {
+            return Ok(files.clone());
+        }
+
+        let dir = match self.source_dir()? {
+            Some(dir) if !self.walk.all => dir,
+            _ => self.root.clone(),
+        };
+        let files = self.files_with_extension(&dir, "nr")?;
+        *cache = Some((self.walk.clone(), files.clone()));
+        Ok(files)
+    }
+
+    /// Drop the cached `.nr` file list so the next [`Project::nr_files`] call walks the tree again.
+    pub fn refresh_files(&self) {
+        *self
+            .nr_files_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    /// Find all Markdown (`.md`) files under the project root, using the same walk rules as
+    /// [`Project::nr_files`].
+    ///
+    /// Markdown files are always searched under the whole project root, since READMEs usually live there.
+    pub fn markdown_files(&self) -> Result<Vec<PathBuf>> {
+        self.files_with_extension(&self.root, "md")
+    }
+
+    /// Directory holding the project's sources.
+    ///
+    /// - If the manifest sets `src = "..."` under `[package]`, that directory (it must exist under the
+    ///   project root).
+    /// - Otherwise `src/` when it exists.
+    /// - Otherwise `None`: the whole project root is the source directory.
+    pub fn source_dir(&self) -> Result<Option<PathBuf>> {
+        let manifest = self.manifest()?;
+
+        if let Some(src) = manifest
+            .get("package")
+            .and_then(|p| p.get("src"))
+            .and_then(|s| s.as_str())
+        {
+            let dir = self.root.join(src);
+            let dir = dir.canonicalize().with_context(|| {
+                format!(
+                    "source directory {} from {} does not exist",
+                    dir.display(),
+                    self.manifest_path.display()
+                )
+            })?;
+            if !dir.is_dir() || !dir.starts_with(&self.root) {
+                bail!(
+                    "source directory `{src}` in {} is not a directory under the project root",
+                    self.manifest_path.display()
+                );
+            }
+            return Ok(Some(dir));
+        }
+
+        let src = self.root.join("src");
+        Ok(src.is_dir().then_some(src))
+    }
+
+    /// Directories of path dependencies declared in the manifest's `[dependencies]` that live under the
+    /// project root but outside `src/` (e.g. `helper = { path = "deps/helper" }`).
+    ///
+    /// Git dependencies are fetched outside the project and never returned. Paths that do not exist are
+    /// ignored.
+    pub fn dependency_dirs(&self) -> Result<Vec<PathBuf>> {
+        let manifest = self.manifest()?;
+
+        let Some(dependencies) = manifest.get("dependencies").and_then(|d| d.as_table()) else {
+            return Ok(Vec::new());
+        };
+
+        let src = self.root.join("src");
+        let mut dirs: Vec<PathBuf> = dependencies
+            .values()
+            .filter_map(|dep| dep.get("path")?.as_str())
+            .filter_map(|path| self.root.join(path).canonicalize().ok())
+            .filter(|dir| {
+                dir.starts_with(&self.root) && *dir != self.root && !dir.starts_with(&src)
+            })
+            .collect();
+
+        dirs.sort();
+        dirs.dedup();
+        Ok(dirs)
+    }
+
+    fn manifest(&self) -> Result<toml::Table> {
+        let manifest = fs::read_to_string(&self.manifest_path)
+            .with_context(|| format!("failed to read {}", self.manifest_path.display()))?;
+        toml::from_str(&manifest)
+            .with_context(|| format!("failed to parse {}", self.manifest_path.display()))
+    }
+
+    fn files_with_extension(&self, dir: &Path, extension: &str) -> Result<Vec<PathBuf>> {
         let mut files = Vec::new();
 
-        for entry in WalkDir::new(&self.root).into_iter().filter_map(Result::ok) {
+        let dependency_dirs = if self.walk.include_deps {
+            Vec::new()
+        } else {
+            self.dependency_dirs()?
+        };
+
+        let mut walker = WalkDir::new(dir);
+        if !self.walk.recursive {
+            walker = walker.max_depth(1);
+        }
+
+        let include_hidden = self.walk.include_hidden;
+        let entries = walker.into_iter().filter_entry(|e| {
+            let hidden = e.depth() > 0 && is_hidden(e.path());
+            let dependency = dependency_dirs.iter().any(|dir| e.path().starts_with(dir));
+            (include_hidden || !hidden) && !dependency
+        });
+
+        for entry in entries.filter_map(Result::ok) {
             let path = entry.path();
 
-            if path.is_file() && is_nr_file(path) {
+            if path.is_file() && has_extension(path, extension) {
                 files.push(path.to_path_buf());
+
+                if self.walk.max_files > 0 && files.len() > self.walk.max_files {
+                    bail!(
+                        "Found more than {} .{extension} files under {}; pass a narrower project root, or raise \
+                         the limit with --max-files (0 = unlimited)",
+                        self.walk.max_files,
+                        dir.display()
+                    );
+                }
             }
         }
 
@@ -56,10 +250,17 @@ impl Project {
     }
 }
 
-fn is_nr_file(path: &Path) -> bool {
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with('.'))
+        .unwrap_or(false)
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
     path.extension()
         .and_then(|ext| ext.to_str())
-        .map(|ext| ext == "nr")
+        .map(|ext| ext == extension)
         .unwrap_or(false)
 }
 
@@ -85,4 +286,142 @@ mod tests {
             joined_paths,
         );
     }
+
+    #[test]
+    fn nr_files_are_cached_until_refreshed() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("noir_metrics_file_cache_{unique}"));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("Nargo.toml"), "[package]\nname = \"cache\"\n").unwrap();
+        fs::write(dir.join("src/main.nr"), "fn main() {}\n").unwrap();
+
+        let project = Project::from_root(dir.clone()).expect("project should be valid");
+        let first = project.nr_files().expect("nr_files should succeed");
+        assert_eq!(first.len(), 1);
+
+        // A file added after the first walk is not seen until the cache is refreshed.
+        fs::write(dir.join("src/lib.nr"), "fn helper() {}\n").unwrap();
+        assert_eq!(project.nr_files().expect("nr_files should succeed"), first);
+
+        project.refresh_files();
+        let refreshed = project.nr_files().expect("nr_files should succeed");
+        assert_eq!(refreshed.len(), 2, "got: {refreshed:?}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn source_dir_comes_from_the_manifest_or_defaults_to_src() {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("noir_metrics_source_dir_{unique}"));
+        fs::create_dir_all(dir.join("circuits")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("Nargo.toml"),
+            "[package]\nname = \"custom\"\nsrc = \"circuits\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("circuits/main.nr"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("src/old.nr"), "fn old() {}\n").unwrap();
+
+        let mut project = Project::from_root(dir.clone()).expect("project should be valid");
+        assert_eq!(
+            project.source_dir().expect("manifest should parse"),
+            Some(project.root.join("circuits"))
+        );
+        let files = project.nr_files().expect("nr_files should succeed");
+        assert_eq!(files, [project.root.join("circuits/main.nr")]);
+
+        project.walk.all = true;
+        assert_eq!(
+            project.nr_files().expect("nr_files should succeed").len(),
+            2
+        );
+
+        fs::write(dir.join("Nargo.toml"), "[package]\nname = \"custom\"\n").unwrap();
+        assert_eq!(
+            project.source_dir().expect("manifest should parse"),
+            Some(project.root.join("src"))
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn walk_fails_above_max_files() {
+        let root = PathBuf::from("tests/fixtures/nested_noir");
+        let mut project = Project::from_root(root).expect("project should be valid");
+        project.walk.all = true;
+
+        project.walk.max_files = 2;
+        let err = project.nr_files().expect_err("3 files exceed the limit");
+        assert!(err.to_string().contains("more than 2 .nr files"), "{err}");
+
+        project.walk.max_files = 0;
+        assert_eq!(project.nr_files().expect("0 means unlimited").len(), 3);
+    }
+
+    #[test]
+    fn non_recursive_walk_only_finds_top_level_files() {
+        let root = PathBuf::from("tests/fixtures/nested_noir");
+        let mut project = Project::from_root(root).expect("project should be valid");
+        project.walk.all = true;
+
+        let all = project.nr_files().expect("nr_files should succeed");
+        assert_eq!(all.len(), 3, "expected recursive walk to find 3 files");
+
+        project.walk.recursive = false;
+        let top_level = project.nr_files().expect("nr_files should succeed");
+
+        let rel: Vec<PathBuf> = top_level
+            .iter()
+            .map(|p| p.strip_prefix(&project.root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(rel, vec![PathBuf::from("top.nr")]);
+    }
+
+    #[test]
+    fn hidden_directories_are_skipped_unless_included() {
+        let root = PathBuf::from("tests/fixtures/hidden_dir");
+        let mut project = Project::from_root(root).expect("project should be valid");
+        project.walk.all = true;
+
+        let has_hidden = |files: &[PathBuf]| files.iter().any(|p| p.ends_with(".hidden/foo.nr"));
+
+        let default_files = project.nr_files().expect("nr_files should succeed");
+        assert!(!has_hidden(&default_files), "got: {default_files:?}");
+        assert!(default_files.iter().any(|p| p.ends_with("src/main.nr")));
+
+        project.walk.include_hidden = true;
+        let all_files = project.nr_files().expect("nr_files should succeed");
+        assert!(has_hidden(&all_files), "got: {all_files:?}");
+    }
+
+    #[test]
+    fn path_dependencies_are_skipped_unless_included() {
+        let root = PathBuf::from("tests/fixtures/path_dep");
+        let mut project = Project::from_root(root).expect("project should be valid");
+        project.walk.all = true;
+
+        assert_eq!(
+            project.dependency_dirs().expect("manifest should parse"),
+            vec![project.root.join("deps/helper")]
+        );
+
+        let is_dep = |p: &PathBuf| p.starts_with(project.root.join("deps"));
+
+        let default_files = project.nr_files().expect("nr_files should succeed");
+        assert!(!default_files.iter().any(is_dep), "got: {default_files:?}");
+        assert!(default_files.iter().any(|p| p.ends_with("src/main.nr")));
+
+        project.walk.include_deps = true;
+        let all_files = project.nr_files().expect("nr_files should succeed");
+        assert!(all_files.iter().any(is_dep), "got: {all_files:?}");
+    }
 }
@@ -0,0 +1,148 @@
+use crate::analysis::file::FileMetrics;
+use crate::analysis::project::MetricsReport;
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// Rules reported in SARIF output: `(ruleId, level, description)`.
+///
+/// Rule ids are stable; new rules are only ever appended.
+const RULES: &[(&str, &str, &str)] = &[
+    (
+        "noir-metrics/budget/max_code_lines",
+        "error",
+        "File exceeds its max_code_lines budget",
+    ),
+    (
+        "noir-metrics/budget/max_complexity",
+        "error",
+        "File exceeds its max_complexity budget",
+    ),
+    (
+        "noir-metrics/budget/max_function_complexity",
+        "error",
+        "A function exceeds the file's max_function_complexity budget",
+    ),
+    (
+        "noir-metrics/profile",
+        "error",
+        "A project-level [profile] target is not met",
+    ),
+    ("noir-metrics/todo", "note", "File contains TODO markers"),
+];
+
+/// Render a report's findings as a SARIF 2.1.0 log (`--format sarif`), e.g. for GitHub code scanning.
+///
+/// Findings:
+/// - each `budget_violations` entry, located at the file (and at the most complex function's `fn` line for
+///   `max_function_complexity`);
+/// - each failed `[profile]` target, located at `Nargo.toml`;
+/// - each file with `todo_count > 0`, located at the file (TODO lines are not tracked).
+///
+/// Locations are relative to the project root (`%SRCROOT%`).
+pub fn to_sarif(report: &MetricsReport) -> Value {
+    let mut results = Vec::new();
+
+    for v in &report.budget_violations {
+        let line = match v.metric.as_str() {
+            "max_function_complexity" => report
+                .files
+                .iter()
+                .find(|fm| fm.path == v.path)
+                .and_then(most_complex_function_line),
+            _ => None,
+        };
+        results.push(result(
+            &format!("noir-metrics/budget/{}", v.metric),
+            &format!(
+                "{} is {}, above the budget of {} for `{}`",
+                v.metric, v.actual, v.limit, v.glob
+            ),
+            &v.path,
+            line,
+        ));
+    }
+
+    if let Some(profile) = &report.profile {
+        for t in profile.targets.iter().filter(|t| !t.passed) {
+            results.push(result(
+                "noir-metrics/profile",
+                &format!(
+                    "profile target {} not met: target={}, actual={:.2}",
+                    t.name, t.target, t.actual
+                ),
+                Path::new("Nargo.toml"),
+                None,
+            ));
+        }
+    }
+
+    for fm in report.files.iter().filter(|fm| fm.todo_count > 0) {
+        results.push(result(
+            "noir-metrics/todo",
+            &format!("{} TODO marker(s)", fm.todo_count),
+            &fm.path,
+            None,
+        ));
+    }
+
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|(id, level, description)| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+                "defaultConfiguration": { "level": level },
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "noir-metrics",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/mutorium/noir-metrics",
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// A single SARIF `result` at `path` (relative to the project root), optionally at a 1-based line.
+fn result(rule_id: &str, message: &str, path: &Path, line: Option<usize>) -> Value {
+    let level = RULES
+        .iter()
+        .find(|(id, _, _)| *id == rule_id)
+        .map_or("warning", |(_, level, _)| level);
+
+    let mut location = json!({
+        "artifactLocation": {
+            "uri": path.to_string_lossy().replace('\\', "/"),
+            "uriBaseId": "%SRCROOT%",
+        }
+    });
+    if let Some(line) = line {
+        location["region"] = json!({ "startLine": line });
+    }
+
+    json!({
+        "ruleId": rule_id,
+        "level": level,
+        "message": { "text": message },
+        "locations": [{ "physicalLocation": location }],
+    })
+}
+
+/// `fn` line of the file's most complex function (the first one on ties).
+fn most_complex_function_line(fm: &FileMetrics) -> Option<usize> {
+    fm.function_metrics
+        .iter()
+        .rev()
+        .max_by_key(|f| f.complexity)
+        .map(|f| f.start_line)
+}
@@ -0,0 +1,96 @@
+use crate::output::JsonReport;
+use anyhow::{Context, Result, anyhow};
+use schemars::generate::SchemaSettings;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// JSON Schema (draft 2020-12) describing the `--format json` report layout.
+///
+/// The schema is generated from the serialized report types, so every field the tool always emits is
+/// marked as required.
+pub fn report_schema() -> Value {
+    let generator = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator();
+    generator
+        .into_root_schema_for::<JsonReport<'static>>()
+        .into()
+}
+
+/// Validate a JSON report against [`report_schema`].
+///
+/// Returns one message per violation (prefixed with the JSON pointer of the offending value).
+pub fn validate_report(report: &Value) -> Result<(), Vec<String>> {
+    let schema = report_schema();
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|e| vec![format!("invalid report schema: {e}")])?;
+
+    let errors: Vec<String> = validator
+        .iter_errors(report)
+        .map(|e| format!("{}: {e}", e.instance_path()))
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validate a JSON report file against [`report_schema`], failing with all violations listed.
+pub fn validate_report_file(path: &Path) -> Result<()> {
+    let s = fs::read_to_string(path)
+        .with_context(|| format!("failed to read report {}", path.display()))?;
+    let report: Value = serde_json::from_str(&s)
+        .with_context(|| format!("failed to parse report {}", path.display()))?;
+
+    validate_report(&report).map_err(|errors| {
+        anyhow!(
+            "{} does not match the report schema:\n  {}",
+            path.display(),
+            errors.join("\n  ")
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::options::AnalysisOptions;
+    use crate::analysis::project::analyze_project;
+    use crate::output::json_report;
+    use crate::project::Project;
+    use std::path::PathBuf;
+
+    fn fixture_report() -> Value {
+        let project = Project::from_root(PathBuf::from("tests/fixtures/project_metrics"))
+            .expect("project should be valid");
+        let report = analyze_project(&project, &AnalysisOptions::default())
+            .expect("analyze_project should succeed");
+        serde_json::to_value(json_report(&report)).expect("report should serialize")
+    }
+
+    #[test]
+    fn emitted_report_matches_schema() {
+        assert_eq!(validate_report(&fixture_report()), Ok(()));
+    }
+
+    #[test]
+    fn malformed_report_is_rejected() {
+        let mut v = fixture_report();
+        v["totals"]["files"] = Value::String("three".to_string());
+        v["files"][0].as_object_mut().unwrap().remove("code_lines");
+
+        let errors = validate_report(&v).expect_err("malformed report should fail validation");
+
+        assert!(
+            errors.iter().any(|e| e.starts_with("/totals/files")),
+            "errors: {errors:?}"
+        );
+        assert!(
+            errors.iter().any(|e| e.contains("code_lines")),
+            "errors: {errors:?}"
+        );
+    }
+}
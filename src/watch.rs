@@ -0,0 +1,72 @@
+use crate::project::Project;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often `--watch` checks the project's files for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long the files must stay unchanged before `--watch` re-runs, so a burst of saves triggers one run.
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Size and modification time of every watched file.
+type Fingerprint = BTreeMap<PathBuf, (u64, Option<SystemTime>)>;
+
+/// Polls a project's `.nr` files and its `Nargo.toml` for changes (`--watch`).
+///
+/// Polling keeps the tool free of platform-specific file notification APIs; the project is walked again on
+/// every poll, so added and removed files are noticed too.
+pub struct Watcher {
+    project: Project,
+    last: Fingerprint,
+}
+
+impl Watcher {
+    /// Start watching `project` from its current state.
+    pub fn new(project: Project) -> Result<Self> {
+        let last = fingerprint(&project)?;
+        Ok(Watcher { project, last })
+    }
+
+    /// Block until a watched file is added, removed or modified and then stays unchanged for `debounce`.
+    pub fn wait_for_change(&mut self, debounce: Duration) -> Result<()> {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let mut current = fingerprint(&self.project)?;
+            if current == self.last {
+                continue;
+            }
+
+            loop {
+                thread::sleep(debounce);
+                let next = fingerprint(&self.project)?;
+                if next == current {
+                    break;
+                }
+                current = next;
+            }
+            self.last = current;
+            return Ok(());
+        }
+    }
+}
+
+/// Fingerprint the project's `.nr` files (walked afresh) and its manifest.
+///
+/// A file removed between the walk and its `stat` is recorded without metadata rather than failing.
+fn fingerprint(project: &Project) -> Result<Fingerprint> {
+    project.refresh_files();
+    let mut paths = project.nr_files()?;
+    paths.push(project.manifest_path.clone());
+
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let stamp = fs::metadata(&path).map_or((0, None), |m| (m.len(), m.modified().ok()));
+            (path, stamp)
+        })
+        .collect())
+}
@@ -29,3 +29,384 @@ fn cli_human_output_contains_summary() {
     assert!(stdout.contains("TODOs=1"), "stdout: {stdout}");
     assert!(stdout.contains("pub_fns=1"), "stdout: {stdout}");
 }
+
+#[test]
+fn cli_human_fields_selects_per_file_columns() {
+    let fixture = PathBuf::from("tests/fixtures/project_metrics");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&fixture)
+        .args(["--fields", "path,code,test_pct,todos"]);
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8_lossy(&output);
+
+    let line = stdout
+        .lines()
+        .find(|l| l.starts_with("- src/pub_todo.nr"))
+        .unwrap_or_else(|| panic!("missing per-file line, stdout: {stdout}"));
+
+    assert!(line.contains("code="), "line: {line}");
+    assert!(line.contains("test_pct="), "line: {line}");
+    assert!(line.contains("todos=1"), "line: {line}");
+    assert!(!line.contains("total="), "line: {line}");
+    assert!(!line.contains("fns="), "line: {line}");
+    assert!(!line.contains("indent="), "line: {line}");
+}
+
+#[test]
+fn cli_stable_human_starts_with_the_format_version() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("--print-format-version");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let version = String::from_utf8_lossy(&output).trim().to_string();
+    assert_eq!(version, noir_metrics::HUMAN_FORMAT_VERSION.to_string());
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .arg("--stable-human");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8_lossy(&output);
+    assert_eq!(
+        stdout.lines().next(),
+        Some(format!("# format_version: {version}").as_str()),
+        "stdout: {stdout}"
+    );
+
+    // Without the flag the summary starts directly with the project line.
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert!(String::from_utf8_lossy(&output).starts_with("Project:"));
+}
+
+#[test]
+fn cli_color_marks_test_percentage_by_threshold() {
+    let test_code = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("noir-metrics");
+        cmd.arg("tests/fixtures/project_metrics").args(args);
+        let output = cmd.assert().success().get_output().stdout.clone();
+        let stdout = String::from_utf8_lossy(&output).to_string();
+        let line = stdout
+            .lines()
+            .find(|l| l.starts_with("Lines:"))
+            .unwrap_or_else(|| panic!("missing Lines: line, stdout: {stdout}"))
+            .to_string();
+        line[line.find("test_code=").unwrap()..].to_string()
+    };
+
+    // Thresholds above/below the fixture's percentage force red/green.
+    let red = test_code(&[
+        "--color",
+        "always",
+        "--warn-test-pct",
+        "100",
+        "--bad-test-pct",
+        "100",
+    ]);
+    assert!(red.starts_with("test_code=\x1b[91m"), "{red:?}");
+    let green = test_code(&[
+        "--color",
+        "always",
+        "--warn-test-pct",
+        "0",
+        "--bad-test-pct",
+        "0",
+    ]);
+    assert!(green.starts_with("test_code=\x1b[92m"), "{green:?}");
+    let light = test_code(&[
+        "--color",
+        "always",
+        "--color-theme",
+        "light",
+        "--bad-test-pct",
+        "100",
+        "--warn-test-pct",
+        "100",
+    ]);
+    assert!(light.starts_with("test_code=\x1b[31m"), "{light:?}");
+
+    let plain = test_code(&[
+        "--color",
+        "never",
+        "--bad-test-pct",
+        "100",
+        "--warn-test-pct",
+        "100",
+    ]);
+    assert!(!plain.contains('\x1b'), "{plain:?}");
+    assert!(plain.ends_with('%'), "{plain:?}");
+}
+
+#[test]
+fn cli_human_fields_rejects_unknown_names() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--fields", "path,bogus"]);
+
+    cmd.assert().failure();
+}
+
+#[test]
+fn cli_table_output_snapshot() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--format", "table"]);
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).expect("stdout is utf-8");
+
+    insta::assert_snapshot!(stdout);
+}
+
+#[test]
+fn cli_table_honors_sort_and_top() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics").args([
+        "--format",
+        "table",
+        "--fields",
+        "path,code",
+        "--sort",
+        "code",
+        "--top",
+        "2",
+    ]);
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8_lossy(&output);
+
+    assert_eq!(
+        stdout,
+        "PATH          CODE\nsrc/main2.nr    13\nsrc/main.nr     11\n"
+    );
+}
+
+#[test]
+fn cli_folded_output_lists_files_as_stacks() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/nested_noir")
+        .args(["--format", "folded"]);
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8_lossy(&output);
+
+    assert!(
+        stdout.lines().any(|l| l == "src;nested;deep.nr 3"),
+        "stdout: {stdout}"
+    );
+    for line in stdout.lines() {
+        let (_, code_lines) = line.rsplit_once(' ').expect("line should have a value");
+        assert!(code_lines.parse::<usize>().is_ok(), "line: {line}");
+    }
+}
+
+#[test]
+fn cli_also_human_writes_summary_to_stderr() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--format", "json", "--also-human"]);
+
+    let assert = cmd.assert().success();
+    let output = assert.get_output();
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be pure JSON");
+    assert_eq!(json["totals"]["files"], 3);
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Project:"), "stderr: {stderr}");
+    assert!(stderr.contains("Per-file metrics:"), "stderr: {stderr}");
+    assert!(stderr.contains("- src/main.nr"), "stderr: {stderr}");
+}
+
+#[test]
+fn cli_also_human_requires_machine_readable_format() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .arg("--also-human");
+    cmd.assert().failure();
+}
+
+#[test]
+fn cli_budgets_fail_on_violation() {
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let budgets = std::env::temp_dir().join(format!("noir_metrics_budgets_{unique}.toml"));
+    std::fs::write(
+        &budgets,
+        "[[budget]]\nglob = \"src/main.nr\"\nmax_code_lines = 1\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .arg("--budgets")
+        .arg(&budgets);
+
+    let assert = cmd.assert().failure();
+    let output = assert.get_output();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.contains("Budget violations: 1"), "stdout: {stdout}");
+    assert!(
+        stderr.contains("src/main.nr: max_code_lines"),
+        "stderr: {stderr}"
+    );
+
+    let _ = std::fs::remove_file(budgets);
+}
+
+#[test]
+fn cli_deny_debug_prints_fails_with_findings() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/debug_prints")
+        .args(["--deny", "debug-prints"]);
+    let stderr = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8_lossy(&stderr);
+    // `src/gen.nr` also calls `println` but is ignored, so it is neither counted nor blamed.
+    assert!(
+        stderr.contains("3 debug print(s) denied: src/main.nr (3)"),
+        "stderr: {stderr}"
+    );
+    assert!(!stderr.contains("gen.nr"), "stderr: {stderr}");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/simple_noir")
+        .args(["--deny", "debug-prints"]);
+    cmd.assert().success();
+}
+
+#[test]
+fn cli_deny_empty_files_fails_with_findings() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/empty_files");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8_lossy(&output);
+    assert!(stdout.contains("empty=2"), "stdout: {stdout}");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/empty_files")
+        .args(["--deny", "empty-files"]);
+    let stderr = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8_lossy(&stderr);
+    assert!(
+        stderr.contains("2 empty file(s) denied: src/blank.nr, src/empty.nr"),
+        "stderr: {stderr}"
+    );
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/simple_noir")
+        .args(["--deny", "empty-files"]);
+    cmd.assert().success();
+}
+
+#[test]
+fn cli_summary_json_prints_compact_totals_to_stderr() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .arg("--summary-json");
+
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+
+    assert!(stdout.contains("Per-file metrics:"), "stdout: {stdout}");
+
+    let line = stderr.lines().last().expect("stderr should have a line");
+    let totals: serde_json::Value = serde_json::from_str(line).expect("line should be JSON");
+    assert_eq!(totals["files"], 3, "{line}");
+    assert!(
+        totals["code_lines"].as_u64().is_some_and(|n| n > 0),
+        "{line}"
+    );
+    assert!(totals["test_code_percentage"].is_f64(), "{line}");
+}
+
+#[test]
+fn cli_max_files_bails_on_large_trees() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--max-files", "1"]);
+    let stderr = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8_lossy(&stderr);
+    assert!(
+        stderr.contains("Found more than 1 .nr files"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("--max-files"), "stderr: {stderr}");
+}
+
+#[test]
+fn cli_fail_if_no_tests_rejects_untested_projects() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/statements")
+        .arg("--fail-if-no-tests");
+    let stderr = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8_lossy(&stderr);
+    assert!(
+        stderr.contains("no #[test] functions found in 1 file(s)"),
+        "stderr: {stderr}"
+    );
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .arg("--fail-if-no-tests");
+    cmd.assert().success();
+}
+
+#[test]
+fn cli_round_controls_percentage_precision() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--round", "0"]);
+    let stdout = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8_lossy(&stdout);
+    assert!(stdout.contains("test_code=67%"), "stdout: {stdout}");
+    assert!(stdout.contains("Maintainability: 98 ("), "stdout: {stdout}");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--round", "1", "--format", "json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: serde_json::Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+    assert_eq!(v["totals"]["test_code_percentage"], 66.7);
+    assert_eq!(v["totals"]["maintainability_index"], 98.4);
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--round", "0", "--format", "json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: serde_json::Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+    assert_eq!(v["totals"]["test_code_percentage"], 67.0);
+}
+
+#[test]
+fn cli_require_test_files_reports_uncovered_sources() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/test_files")
+        .args(["--all", "--require-test-files"]);
+    let stderr = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8_lossy(&stderr);
+    assert!(
+        stderr.contains("1 source file(s) without a test file: src/main.nr"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_stdin_analyzes_piped_source_as_one_file() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.args(["--stdin", "--name", "src/foo.nr", "--format", "json"])
+        .write_stdin("// piped\nfn main(x: Field) {\n\n    assert(x != 0);\n}\n");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: serde_json::Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+
+    assert_eq!(v["totals"]["files"], 1);
+    assert_eq!(v["files"][0]["path"], "src/foo.nr");
+    assert_eq!(v["files"][0]["code_lines"], 3);
+    assert_eq!(v["files"][0]["comment_lines"], 1);
+}
@@ -97,3 +97,810 @@ fn cli_deprecated_json_flag_still_outputs_json() {
 
     let _: Value = serde_json::from_str(&stdout).expect("stdout is valid JSON");
 }
+
+#[test]
+fn cli_validate_schema_accepts_good_and_rejects_malformed_reports() {
+    let fixture = PathBuf::from("tests/fixtures/project_metrics");
+
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let good_path = std::env::temp_dir().join(format!("noir_metrics_schema_good_{unique}.json"));
+    let bad_path = std::env::temp_dir().join(format!("noir_metrics_schema_bad_{unique}.json"));
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&fixture)
+        .arg("--format")
+        .arg("json")
+        .arg("--output")
+        .arg(&good_path);
+    cmd.assert().success();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("--validate-schema").arg(&good_path);
+    cmd.assert().success();
+
+    let mut v: Value =
+        serde_json::from_str(&fs::read_to_string(&good_path).unwrap()).expect("valid JSON");
+    v["totals"]["code_lines"] = Value::from(-1);
+    fs::write(&bad_path, serde_json::to_string(&v).unwrap()).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("--validate-schema").arg(&bad_path);
+    let output = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8_lossy(&output);
+    assert!(stderr.contains("/totals/code_lines"), "stderr: {stderr}");
+
+    let _ = fs::remove_file(&good_path);
+    let _ = fs::remove_file(&bad_path);
+}
+
+#[test]
+fn cli_flat_json_uses_dotted_keys() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--format", "flat-json"]);
+
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).expect("stdout is utf-8");
+    let v: Value = serde_json::from_str(&stdout).expect("stdout is valid JSON");
+
+    assert_eq!(v["tool.name"], "noir-metrics");
+    assert_eq!(v["files[0].path"], "src/main.nr");
+    assert_eq!(v["totals.todo_count"], 1);
+    assert!(v.get("totals").is_none(), "expected no nested objects");
+}
+
+#[test]
+fn cli_diffs_against_csv_baseline() {
+    let fixture = PathBuf::from("tests/fixtures/project_metrics");
+
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let csv_path = std::env::temp_dir().join(format!("noir_metrics_baseline_{unique}.csv"));
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&fixture)
+        .args(["--format", "csv", "--output"])
+        .arg(&csv_path);
+    cmd.assert().success();
+
+    // Drop src/main2.nr from the baseline so it shows up as added.
+    let csv = fs::read_to_string(&csv_path).unwrap();
+    assert!(csv.starts_with("path,"), "csv: {csv}");
+    let trimmed: String = csv
+        .lines()
+        .filter(|line| !line.starts_with("src/main2.nr,"))
+        .map(|line| format!("{line}\n"))
+        .collect();
+    fs::write(&csv_path, trimmed).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&fixture)
+        .args(["--format", "json", "--baseline"])
+        .arg(&csv_path);
+    let assert = cmd.assert().success();
+    let v: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+
+    let statuses: Vec<(&str, &str)> = v["file_diffs"]
+        .as_array()
+        .expect("file_diffs should be present")
+        .iter()
+        .map(|d| (d["path"].as_str().unwrap(), d["status"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("src/main.nr", "unchanged"),
+            ("src/main2.nr", "added"),
+            ("src/pub_todo.nr", "unchanged"),
+        ]
+    );
+
+    let _ = fs::remove_file(&csv_path);
+}
+
+#[test]
+fn cli_totals_only_omits_files() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--format", "json", "--totals-only"]);
+
+    let assert = cmd.assert().success();
+    let v: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+
+    assert!(v.get("files").is_none(), "files should be omitted: {v}");
+    assert!(
+        v.get("project_root").is_none(),
+        "project_root should be omitted: {v}"
+    );
+    assert_eq!(v["totals"]["files"], 3);
+    assert_eq!(v["tool"]["name"], "noir-metrics");
+}
+
+#[test]
+fn cli_merges_reports() {
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+
+    let mut paths = Vec::new();
+    let mut code_lines = 0;
+    for fixture in ["simple_noir", "derives"] {
+        let path = std::env::temp_dir().join(format!("noir_metrics_merge_{fixture}_{unique}.json"));
+
+        let mut cmd = cargo_bin_cmd!("noir-metrics");
+        cmd.arg(PathBuf::from("tests/fixtures").join(fixture))
+            .args(["--format", "json", "--output"])
+            .arg(&path);
+        cmd.assert().success();
+
+        let v: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        code_lines += v["totals"]["code_lines"].as_u64().unwrap();
+        paths.push(path);
+    }
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.args(["--format", "json", "--merge"]).args(&paths);
+    let assert = cmd.assert().success();
+    let v: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+
+    assert_eq!(v["totals"]["code_lines"].as_u64(), Some(code_lines));
+    let files: Vec<&str> = v["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        files,
+        vec![
+            "derives/src/main.nr",
+            "simple_noir/src/main.nr",
+            "simple_noir/src/main2.nr"
+        ]
+    );
+
+    for path in paths {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[test]
+fn cli_scans_only_the_source_dir_unless_all() {
+    let paths = |extra: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("noir-metrics");
+        cmd.arg("tests/fixtures/source_dir")
+            .args(["--format", "json"])
+            .args(extra);
+        let assert = cmd.assert().success();
+        let v: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+        v["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["path"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(paths(&[]), ["src/main.nr"]);
+    assert_eq!(paths(&["--all"]), ["scripts/gen.nr", "src/main.nr"]);
+}
+
+#[test]
+fn cli_rewrites_path_prefixes() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/inline_tests").args([
+        "--all",
+        "--format",
+        "json",
+        "--path-prefix-strip",
+        "src/",
+        "--path-prefix-add",
+        "circuits",
+    ]);
+
+    let assert = cmd.assert().success();
+    let v: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+
+    let files: Vec<&str> = v["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        files,
+        vec![
+            "circuits/helpers.nr",
+            "circuits/main.nr",
+            "circuits/tests/helpers_test.nr"
+        ]
+    );
+}
+
+#[test]
+fn cli_json_output_is_deterministic() {
+    // Exercise every map-valued section (indent_styles, derives, regex_counts, todo_markers) plus the
+    // derived lists, so an unordered collection anywhere in the report shows up as a diff.
+    let run = || {
+        let mut cmd = cargo_bin_cmd!("noir-metrics");
+        cmd.arg("tests/fixtures/project_metrics").args([
+            "--format",
+            "json",
+            "--list-derives",
+            "--list-tests",
+            "--find-duplicates",
+            "--longest-functions",
+            "5",
+            "--count-regex",
+            "asserts=assert",
+            "--count-regex",
+            "lets=let ",
+        ]);
+        cmd.assert().success().get_output().stdout.clone()
+    };
+
+    let first = run();
+    for _ in 0..4 {
+        assert_eq!(run(), first, "JSON output differs between runs");
+    }
+}
+
+#[test]
+fn cli_discover_reports_each_project_and_combined_totals() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.args([
+        "--format",
+        "json",
+        "--discover",
+        "tests/fixtures/workspace/*",
+    ]);
+
+    let assert = cmd.assert().success();
+    let v: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+
+    let projects = v["projects"].as_object().expect("projects should be keyed");
+    let keys: Vec<&str> = projects.keys().map(String::as_str).collect();
+    assert_eq!(
+        keys,
+        vec![
+            "tests/fixtures/workspace/pkg_a",
+            "tests/fixtures/workspace/pkg_b"
+        ]
+    );
+    assert_eq!(v["totals"]["files"], 2);
+    assert_eq!(v["tool"]["name"], "noir-metrics");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.args(["--discover", "tests/fixtures/workspace/none*"]);
+    let assert = cmd.assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("no directories with a Nargo.toml"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_relative_to_parent_lengthens_paths() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/inline_tests").args([
+        "--format",
+        "json",
+        "--relative-to",
+        "tests/fixtures",
+    ]);
+
+    let assert = cmd.assert().success();
+    let v: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+    assert_eq!(v["files"][0]["path"], "inline_tests/src/helpers.nr");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/inline_tests")
+        .args(["--relative-to", "tests/fixtures/simple_noir"]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn cli_sarif_output_snapshot() {
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let budgets = std::env::temp_dir().join(format!("noir_metrics_sarif_budgets_{unique}.toml"));
+    fs::write(
+        &budgets,
+        "[[budget]]\nglob = \"src/main.nr\"\nmax_code_lines = 1\n\n\
+         [[budget]]\nglob = \"src/main2.nr\"\nmax_function_complexity = 0\n",
+    )
+    .unwrap();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--format", "sarif", "--budgets"])
+        .arg(&budgets);
+
+    // Budget violations make the run fail, but the SARIF log is still written.
+    let assert = cmd.assert().failure();
+    let _ = fs::remove_file(&budgets);
+
+    let mut v: Value = serde_json::from_slice(&assert.get_output().stdout).expect("valid JSON");
+    assert_eq!(v["version"], "2.1.0");
+
+    let driver = &mut v["runs"][0]["tool"]["driver"];
+    assert_eq!(driver["version"], env!("CARGO_PKG_VERSION"));
+    driver["version"] = Value::String("<VERSION>".to_string());
+
+    insta::assert_json_snapshot!(v);
+}
+
+#[test]
+fn cli_manifest_path_analyzes_the_manifest_directory() {
+    let json = |args: &[&str]| -> Value {
+        let mut cmd = cargo_bin_cmd!("noir-metrics");
+        cmd.args(args).args(["--format", "json"]);
+        let output = cmd.assert().success().get_output().stdout.clone();
+        serde_json::from_slice(&output).expect("stdout is valid JSON")
+    };
+
+    let by_manifest = json(&[
+        "--manifest-path",
+        "tests/fixtures/project_metrics/Nargo.toml",
+    ]);
+    let by_root = json(&["tests/fixtures/project_metrics"]);
+
+    assert!(
+        by_manifest["project_root"]
+            .as_str()
+            .is_some_and(|root| root.ends_with("tests/fixtures/project_metrics")),
+        "{by_manifest}"
+    );
+    assert_eq!(by_manifest["totals"], by_root["totals"]);
+}
+
+#[test]
+fn cli_manifest_path_must_be_an_existing_nargo_toml() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.args([
+        "--manifest-path",
+        "tests/fixtures/project_metrics/src/main.nr",
+    ]);
+    let stderr = cmd.assert().failure().get_output().stderr.clone();
+    assert!(String::from_utf8_lossy(&stderr).contains("must point to a Nargo.toml"));
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.args(["--manifest-path", "tests/fixtures/missing/Nargo.toml"]);
+    let stderr = cmd.assert().failure().get_output().stderr.clone();
+    assert!(String::from_utf8_lossy(&stderr).contains("does not exist"));
+}
+
+#[test]
+fn cli_ndjson_per_file_streams_sorted_files_then_totals() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .arg("--ndjson-per-file");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let records: Vec<Value> = String::from_utf8(output)
+        .expect("stdout is utf-8")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is JSON"))
+        .collect();
+
+    let (totals, files) = records.split_last().expect("at least the totals record");
+    let paths: Vec<&str> = files
+        .iter()
+        .map(|r| r["file"]["path"].as_str().expect("file record has a path"))
+        .collect();
+    assert_eq!(paths, ["src/main.nr", "src/main2.nr", "src/pub_todo.nr"]);
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--format", "json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let batch: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+
+    assert_eq!(totals["totals"], batch["totals"]);
+}
+
+#[test]
+fn cli_json_keys_match_the_schema_version_golden() {
+    // Top-level, `tool` and `totals` keys are recorded per schema version. Removing or renaming a key is a
+    // breaking change and needs a `JSON_SCHEMA_VERSION` bump plus a new golden file; added keys must be
+    // appended to the current golden file so every layout change is reviewed.
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/project_metrics")
+        .args(["--format", "json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+
+    let schema_version = v["tool"]["schema_version"]
+        .as_u64()
+        .expect("tool.schema_version should be a number");
+    assert_eq!(schema_version, u64::from(noir_metrics::JSON_SCHEMA_VERSION));
+
+    let keys = |prefix: &str, value: &Value| -> Vec<String> {
+        value
+            .as_object()
+            .expect("section should be an object")
+            .keys()
+            .map(|k| format!("{prefix}{k}"))
+            .collect()
+    };
+    let mut actual = keys("", &v);
+    actual.extend(keys("tool.", &v["tool"]));
+    actual.extend(keys("totals.", &v["totals"]));
+    actual.sort();
+
+    let golden_path = format!("tests/golden/schema_v{schema_version}_keys.txt");
+    let golden = fs::read_to_string(&golden_path)
+        .unwrap_or_else(|e| panic!("missing golden key set {golden_path}: {e}"));
+    let mut expected: Vec<String> = golden
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    expected.sort();
+
+    let added: Vec<&String> = actual.iter().filter(|k| !expected.contains(k)).collect();
+    let removed: Vec<&String> = expected.iter().filter(|k| !actual.contains(k)).collect();
+    assert!(
+        added.is_empty() && removed.is_empty(),
+        "JSON keys drifted from {golden_path}: added {added:?}, removed {removed:?}. \
+         Record added keys there; for removed or renamed keys bump JSON_SCHEMA_VERSION and add a new golden file."
+    );
+}
+
+#[test]
+fn cli_json_stream_to_appends_one_record_per_run() {
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let stream = std::env::temp_dir().join(format!("noir_metrics_stream_{unique}.ndjson"));
+
+    for _ in 0..2 {
+        let mut cmd = cargo_bin_cmd!("noir-metrics");
+        cmd.arg("tests/fixtures/project_metrics")
+            .arg("--json-stream-to")
+            .arg(&stream);
+        cmd.assert().success();
+    }
+
+    let contents = fs::read_to_string(&stream).expect("stream file should be created");
+    let records: Vec<Value> = contents
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is valid JSON"))
+        .collect();
+    assert_eq!(records.len(), 2, "contents: {contents}");
+    for record in &records {
+        assert!(record["timestamp"].as_u64().is_some(), "{record}");
+        assert_eq!(
+            record["schema_version"].as_u64(),
+            Some(u64::from(noir_metrics::JSON_SCHEMA_VERSION))
+        );
+        assert_eq!(record["totals"]["files"].as_u64(), Some(3));
+    }
+
+    let _ = fs::remove_file(&stream);
+}
+
+#[test]
+fn cli_group_tests_emits_production_and_test_totals() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/inline_tests")
+        .args(["--all", "--group-tests", "--format", "json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+
+    let code = |key: &str| {
+        v[key]["code_lines"]
+            .as_u64()
+            .expect("code_lines is a number")
+    };
+    assert_eq!(
+        code("totals_production") + code("totals_tests"),
+        code("totals")
+    );
+    assert_eq!(v["totals_tests"]["files"], 1);
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/inline_tests")
+        .args(["--format", "json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+    assert!(v.get("totals_production").is_none());
+}
+
+#[test]
+fn cli_tags_and_label_are_emitted_in_meta() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/simple_noir").args([
+        "--format",
+        "json",
+        "--tag",
+        "sha=abc123",
+        "--tag",
+        "branch=main",
+        "--label",
+        "nightly",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+
+    assert_eq!(v["meta"]["tags"]["sha"], "abc123");
+    assert_eq!(v["meta"]["tags"]["branch"], "main");
+    assert_eq!(v["meta"]["tags"]["label"], "nightly");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("tests/fixtures/simple_noir")
+        .args(["--format", "json", "--tag", "no-separator"]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn cli_parallel_output_does_not_depend_on_thread_count() {
+    let run = |threads: &str| {
+        let mut cmd = cargo_bin_cmd!("noir-metrics");
+        cmd.arg("tests/fixtures/project_metrics").args([
+            "--all",
+            "--format",
+            "json",
+            "--parallel",
+            threads,
+        ]);
+        cmd.assert().success().get_output().stdout.clone()
+    };
+
+    let sequential = run("1");
+    assert!(!sequential.is_empty());
+    assert_eq!(run("4"), sequential);
+    assert_eq!(run("0"), sequential);
+}
+
+#[test]
+fn cli_watch_rewrites_output_atomically_on_each_change() {
+    let dir = copy_fixture("project_metrics");
+    let out = dir.join("report.json");
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_noir-metrics"))
+        .arg(&dir)
+        .args(["--format", "json", "--watch", "--output"])
+        .arg(&out)
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .expect("noir-metrics should start");
+
+    // Poll the output until `functions` reaches `expected`; every read must be complete JSON.
+    let wait_for_functions = |expected: Option<u64>| -> u64 {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(30);
+        loop {
+            if let Ok(s) = fs::read_to_string(&out) {
+                let v: Value = serde_json::from_str(&s)
+                    .unwrap_or_else(|e| panic!("output is not complete JSON ({e}): {s}"));
+                let functions = v["totals"]["functions"]
+                    .as_u64()
+                    .expect("functions is a number");
+                if expected.is_none_or(|expected| functions == expected) {
+                    return functions;
+                }
+            }
+            assert!(
+                std::time::Instant::now() < deadline,
+                "timed out waiting for {out:?}"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    };
+
+    let initial = wait_for_functions(None);
+    for added in 1..=2 {
+        let main = dir.join("src/main.nr");
+        let mut source = fs::read_to_string(&main).unwrap();
+        source.push_str(&format!("\nfn added_{added}() {{}}\n"));
+        fs::write(&main, source).unwrap();
+        wait_for_functions(Some(initial + added));
+    }
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    let leftovers: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().contains(".tmp-"))
+        .collect();
+    assert!(leftovers.is_empty(), "{leftovers:?}");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Copy a fixture project (manifest and `src/*.nr`) into a fresh temp directory.
+fn copy_fixture(fixture: &str) -> PathBuf {
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let src = PathBuf::from("tests/fixtures").join(fixture);
+    let dir = std::env::temp_dir().join(format!("noir_metrics_{fixture}_{unique}"));
+    fs::create_dir_all(dir.join("src")).unwrap();
+
+    fs::copy(src.join("Nargo.toml"), dir.join("Nargo.toml")).unwrap();
+    for entry in fs::read_dir(src.join("src")).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, dir.join("src").join(path.file_name().unwrap())).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn cli_baseline_auto_without_baseline_reports_and_optionally_writes_it() {
+    let dir = copy_fixture("project_metrics");
+    let baseline = dir.join(".noir-metrics-baseline.json");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir).args(["--format", "json", "--baseline-auto"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+    assert!(v.get("file_diffs").is_none(), "{v}");
+    assert!(!baseline.exists());
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir)
+        .args(["--baseline-auto", "--baseline-auto-write"]);
+    cmd.assert().success();
+    let written: Value =
+        serde_json::from_str(&fs::read_to_string(&baseline).expect("baseline should be written"))
+            .expect("baseline is valid JSON");
+    assert_eq!(written["totals"]["files"], 3);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cli_baseline_auto_diffs_against_the_project_baseline() {
+    let dir = copy_fixture("project_metrics");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir)
+        .args(["--baseline-auto", "--baseline-auto-write"]);
+    cmd.assert().success();
+
+    fs::write(dir.join("src/extra.nr"), "fn extra() {}\n").unwrap();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir).args([
+        "--format",
+        "json",
+        "--baseline-auto",
+        "--baseline-auto-write",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+
+    let statuses: Vec<(&str, &str)> = v["file_diffs"]
+        .as_array()
+        .expect("file_diffs should be present")
+        .iter()
+        .map(|d| (d["path"].as_str().unwrap(), d["status"].as_str().unwrap()))
+        .collect();
+    assert!(
+        statuses.contains(&("src/extra.nr", "added")),
+        "{statuses:?}"
+    );
+    assert!(
+        statuses.contains(&("src/main.nr", "unchanged")),
+        "{statuses:?}"
+    );
+
+    // An existing baseline is never overwritten.
+    let baseline = fs::read_to_string(dir.join(".noir-metrics-baseline.json")).unwrap();
+    assert!(!baseline.contains("src/extra.nr"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cli_fail_on_regression_honors_the_baseline_tolerance() {
+    let dir = copy_fixture("project_metrics");
+    let baseline = dir.join("baseline.json");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir)
+        .args(["--format", "json", "--output"])
+        .arg(&baseline);
+    cmd.assert().success();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir)
+        .args(["--fail-on-regression", "--baseline"])
+        .arg(&baseline);
+    cmd.assert().success();
+
+    // Drop one of the test lines in main.nr.
+    let main = dir.join("src/main.nr");
+    let source = fs::read_to_string(&main).unwrap();
+    fs::write(&main, source.replacen("    main(1, 2);\n", "", 1)).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir)
+        .args(["--fail-on-regression", "--baseline"])
+        .arg(&baseline);
+    let stderr = cmd.assert().failure().get_output().stderr.clone();
+    let stderr = String::from_utf8_lossy(&stderr);
+    assert!(
+        stderr.contains("1 file(s) regressed against the baseline: src/main.nr"),
+        "stderr: {stderr}"
+    );
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir)
+        .args([
+            "--fail-on-regression",
+            "--baseline-tolerance",
+            "50",
+            "--baseline",
+        ])
+        .arg(&baseline);
+    cmd.assert().success();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir).arg("--fail-on-regression");
+    cmd.assert().failure();
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cli_only_changed_lists_files_that_differ_from_the_baseline() {
+    let dir = copy_fixture("project_metrics");
+    let baseline = dir.join("baseline.json");
+    for name in ["dup_a.nr", "dup_b.nr"] {
+        fs::write(dir.join("src").join(name), "fn same() {}\n").unwrap();
+    }
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir)
+        .args(["--format", "json", "--output"])
+        .arg(&baseline);
+    cmd.assert().success();
+
+    let main = dir.join("src/main.nr");
+    let source = fs::read_to_string(&main).unwrap();
+    fs::write(&main, format!("{source}\nfn extra() {{}}\n")).unwrap();
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir)
+        .args([
+            "--format",
+            "json",
+            "--find-duplicates",
+            "--only-changed",
+            "--baseline",
+        ])
+        .arg(&baseline);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let v: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+
+    let paths: Vec<&str> = v["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(paths, ["src/main.nr"]);
+    assert_eq!(v["totals"]["files"], 5);
+    // Derived sections are computed before the file list is narrowed.
+    assert_eq!(
+        v["duplicate_groups"],
+        serde_json::json!([["src/dup_a.nr", "src/dup_b.nr"]])
+    );
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg(&dir).arg("--only-changed");
+    cmd.assert().failure();
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,109 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Write `fixture`'s JSON report to a unique temp file.
+fn json_report(fixture: &str) -> PathBuf {
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("noir_metrics_sub_{fixture}_{unique}.json"));
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.args(["analyze", "--format", "json", "--output"])
+        .arg(&path)
+        .arg(Path::new("tests/fixtures").join(fixture));
+    cmd.assert().success();
+    path
+}
+
+#[test]
+fn cli_analyze_subcommand_matches_the_bare_invocation() {
+    let run = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("noir-metrics");
+        cmd.args(args);
+        let output = cmd.assert().success().get_output().stdout.clone();
+        let v: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+        v["totals"].clone()
+    };
+
+    let fixture = "tests/fixtures/project_metrics";
+    assert_eq!(
+        run(&["analyze", fixture, "--format", "json"]),
+        run(&[fixture, "--format", "json"])
+    );
+}
+
+#[test]
+fn cli_schema_subcommand_prints_the_report_schema() {
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("schema");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let schema: Value = serde_json::from_slice(&output).expect("schema is valid JSON");
+    assert!(schema["properties"]["totals"].is_object(), "{schema}");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("--print-schema");
+    let legacy = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(output, legacy);
+}
+
+#[test]
+fn cli_diff_subcommand_compares_two_reports() {
+    let old = json_report("simple_noir");
+    let new = json_report("project_metrics");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.args(["diff", "--format", "json"]).arg(&old).arg(&new);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let diffs: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+    let statuses: Vec<(&str, &str)> = diffs
+        .as_array()
+        .expect("diff output is an array")
+        .iter()
+        .map(|d| (d["path"].as_str().unwrap(), d["status"].as_str().unwrap()))
+        .collect();
+    assert!(
+        statuses.contains(&("src/pub_todo.nr", "added")),
+        "{statuses:?}"
+    );
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("diff").arg(&old).arg(&new);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8_lossy(&output);
+    assert!(stdout.starts_with("Baseline diff ("), "stdout: {stdout}");
+    assert!(stdout.contains("src/pub_todo.nr"), "stdout: {stdout}");
+
+    let _ = fs::remove_file(old);
+    let _ = fs::remove_file(new);
+}
+
+#[test]
+fn cli_merge_subcommand_writes_a_merged_report() {
+    let a = json_report("simple_noir");
+    let b = json_report("derives");
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("merge").arg(&a).arg(&b);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let merged: Value = serde_json::from_slice(&output).expect("stdout is valid JSON");
+
+    let files = |path: &Path| -> u64 {
+        let v: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        v["totals"]["files"].as_u64().unwrap()
+    };
+    assert_eq!(
+        merged["totals"]["files"].as_u64(),
+        Some(files(&a) + files(&b))
+    );
+
+    let mut cmd = cargo_bin_cmd!("noir-metrics");
+    cmd.arg("merge");
+    cmd.assert().failure();
+
+    let _ = fs::remove_file(a);
+    let _ = fs::remove_file(b);
+}